@@ -0,0 +1,452 @@
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Extensions listed with `archive_listing`; `.gz` also covers `.tar.gz`.
+pub(crate) const ARCHIVE_EXTENSIONS: &[&str] =
+    &[".zip", ".jar", ".war", ".ear", ".tar", ".tgz", ".gz"];
+
+/// The member paths and sizes of an archive, in the order they are stored, keeping only
+/// the first `max_entries` of them.
+struct Listing {
+    max_entries: usize,
+    shown: Vec<(String, u64)>,
+    entries: usize,
+    bytes: u64,
+}
+
+impl Listing {
+    fn new(max_entries: usize) -> Self {
+        Listing {
+            max_entries,
+            shown: Vec::new(),
+            entries: 0,
+            bytes: 0,
+        }
+    }
+
+    fn push(&mut self, name: String, size: u64) {
+        self.entries += 1;
+        self.bytes += size;
+        if self.shown.len() < self.max_entries {
+            self.shown.push((name, size));
+        }
+    }
+
+    /// A table in the layout of `unzip -l`:
+    ///
+    /// ```text
+    ///   Length  Name
+    /// --------  ----
+    ///     1234  src/main.rs
+    /// [... 2 more entries]
+    /// --------  ----
+    ///     5678  3 entries
+    /// ```
+    fn render(&self) -> String {
+        let width = self.bytes.to_string().len().max(8);
+        let rule = format!("{}  ----\n", "-".repeat(width));
+        let mut out = format!("{:>width$}  Name\n", "Length", width = width);
+        out.push_str(&rule);
+        for (name, size) in &self.shown {
+            out.push_str(&format!("{:>width$}  {}\n", size, name, width = width));
+        }
+        let more = self.entries - self.shown.len();
+        if more > 0 {
+            out.push_str(&format!("[... {} more entries]\n", more));
+        }
+        out.push_str(&rule);
+        let noun = if self.entries == 1 {
+            "entry"
+        } else {
+            "entries"
+        };
+        out.push_str(&format!(
+            "{:>width$}  {} {}\n",
+            self.bytes,
+            self.entries,
+            noun,
+            width = width
+        ));
+        out
+    }
+}
+
+pub(crate) fn is_archive(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    ARCHIVE_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+}
+
+/// Lists the members of the zip or tar archive at `path` without extracting anything;
+/// members that are archives themselves are listed, not opened. A `.gz` file that does not
+/// hold a tar archive is listed as its one compressed file.
+///
+/// Returns `None` when the archive cannot be read.
+pub(crate) fn archive_listing(path: &Path, max_entries: usize) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let mut listing = Listing::new(max_entries);
+    let file = File::open(path).ok()?;
+    if name.ends_with(".tar") {
+        tar_entries(&mut BufReader::new(file), &mut listing)?;
+    } else if name.ends_with(".gz") || name.ends_with(".tgz") {
+        let mut decoder = MultiGzDecoder::new(BufReader::new(file));
+        let mut block = [0u8; 512];
+        let read = read_block(&mut decoder, &mut block).ok()?;
+        if read == block.len() && (is_zero_block(&block) || tar_checksum_ok(&block)) {
+            tar_entries(&mut Cursor::new(block).chain(decoder), &mut listing)?;
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            return None;
+        } else {
+            let size = read as u64 + io::copy(&mut decoder, &mut io::sink()).ok()?;
+            let member = decoder
+                .header()
+                .and_then(|header| header.filename())
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .or_else(|| Some(path.file_stem()?.to_string_lossy().into_owned()))?;
+            listing.push(member, size);
+        }
+    } else {
+        zip_entries(file, &mut listing)?;
+    }
+    Some(listing.render())
+}
+
+/// Fills `block` as far as the reader goes, returning how many bytes were read.
+fn read_block(reader: &mut dyn Read, block: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < block.len() {
+        match reader.read(&mut block[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+fn is_zero_block(block: &[u8; 512]) -> bool {
+    block.iter().all(|&b| b == 0)
+}
+
+/// A tar header's checksum: the byte sum of the header with the checksum field as spaces.
+fn tar_checksum_ok(block: &[u8; 512]) -> bool {
+    let sum: u64 = block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if (148..156).contains(&i) {
+                32
+            } else {
+                u64::from(b)
+            }
+        })
+        .sum();
+    tar_number(&block[148..156]) == Some(sum)
+}
+
+/// A numeric tar field: octal digits, or base-256 when the high bit of the first byte is set.
+fn tar_number(field: &[u8]) -> Option<u64> {
+    if field.first().is_some_and(|&b| b & 0x80 != 0) {
+        return Some(
+            field[1..]
+                .iter()
+                .fold(u64::from(field[0] & 0x7F), |n, &b| n << 8 | u64::from(b)),
+        );
+    }
+    let digits = std::str::from_utf8(field).ok()?;
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    if digits.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(digits, 8).ok()
+}
+
+/// A NUL-terminated tar string field.
+fn tar_string(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Walks ustar, GNU, and pax headers up to the end-of-archive block, skipping each
+/// member's data.
+fn tar_entries(reader: &mut dyn Read, listing: &mut Listing) -> Option<()> {
+    let mut block = [0u8; 512];
+    // Set by a GNU long name or a pax `path` record for the header that follows.
+    let mut long_name: Option<String> = None;
+    loop {
+        match read_block(reader, &mut block).ok()? {
+            0 => return Some(()),
+            512 => {}
+            _ => return None,
+        }
+        if is_zero_block(&block) {
+            return Some(());
+        }
+        if !tar_checksum_ok(&block) {
+            return None;
+        }
+        let size = tar_number(&block[124..136])?;
+        let padded = (size + 511) / 512 * 512;
+        match block[156] {
+            b'L' | b'x' => {
+                let mut data = Vec::new();
+                reader.take(padded).read_to_end(&mut data).ok()?;
+                data.truncate(size as usize);
+                long_name = if block[156] == b'L' {
+                    Some(tar_string(&data))
+                } else {
+                    pax_path(&data)
+                };
+                continue;
+            }
+            b'g' => {
+                io::copy(&mut reader.take(padded), &mut io::sink()).ok()?;
+                continue;
+            }
+            _ => {}
+        }
+        let name = long_name.take().unwrap_or_else(|| {
+            let name = tar_string(&block[0..100]);
+            let prefix = tar_string(&block[345..500]);
+            if &block[257..262] == b"ustar" && !prefix.is_empty() {
+                format!("{}/{}", prefix, name)
+            } else {
+                name
+            }
+        });
+        // Links and directories have no data, whatever their size field says.
+        let data = if matches!(block[156], b'1' | b'2' | b'5') {
+            0
+        } else {
+            padded
+        };
+        if io::copy(&mut reader.take(data), &mut io::sink()).ok()? != data {
+            return None;
+        }
+        let size = if block[156] == b'5' { 0 } else { size };
+        listing.push(name, size);
+    }
+}
+
+/// The `path` record of a pax extended header: lines of `LEN path=VALUE\n`.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(data);
+    text.lines().find_map(|record| {
+        let (_, field) = record.split_once(' ')?;
+        field.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from(u16::from_le_bytes(
+        bytes.get(at..at + 2)?.try_into().ok()?,
+    )))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from(u32::from_le_bytes(
+        bytes.get(at..at + 4)?.try_into().ok()?,
+    )))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Reads the central directory a zip archive ends with, including zip64 archives.
+fn zip_entries(mut file: File, listing: &mut Listing) -> Option<()> {
+    let len = file.metadata().ok()?.len();
+    // The end record is 22 bytes plus a comment of up to 65535.
+    let tail_len = len.min(22 + 65_535);
+    file.seek(SeekFrom::Start(len - tail_len)).ok()?;
+    let mut tail = Vec::new();
+    file.by_ref().take(tail_len).read_to_end(&mut tail).ok()?;
+    let end = (0..tail.len().checked_sub(21)?)
+        .rev()
+        .find(|&i| tail[i..].starts_with(b"PK\x05\x06"))?;
+    let mut count = u16_at(&tail, end + 10)?;
+    let mut directory_len = u32_at(&tail, end + 12)?;
+    let mut directory_at = u32_at(&tail, end + 16)?;
+    if directory_at == 0xFFFF_FFFF || count == 0xFFFF {
+        let locator = end.checked_sub(20)?;
+        if !tail[locator..].starts_with(b"PK\x06\x07") {
+            return None;
+        }
+        let mut record = [0u8; 56];
+        file.seek(SeekFrom::Start(u64_at(&tail, locator + 8)?))
+            .ok()?;
+        file.read_exact(&mut record).ok()?;
+        if !record.starts_with(b"PK\x06\x06") {
+            return None;
+        }
+        count = u64_at(&record, 32)?;
+        directory_len = u64_at(&record, 40)?;
+        directory_at = u64_at(&record, 48)?;
+    }
+    if directory_at.checked_add(directory_len)? > len {
+        return None;
+    }
+    file.seek(SeekFrom::Start(directory_at)).ok()?;
+    let mut directory = Vec::new();
+    file.take(directory_len).read_to_end(&mut directory).ok()?;
+
+    let mut at = 0;
+    for _ in 0..count {
+        if !directory.get(at..)?.starts_with(b"PK\x01\x02") {
+            return None;
+        }
+        let mut size = u32_at(&directory, at + 24)?;
+        let name_len = u16_at(&directory, at + 28)? as usize;
+        let extra_len = u16_at(&directory, at + 30)? as usize;
+        let comment_len = u16_at(&directory, at + 32)? as usize;
+        let name_at = at + 46;
+        let name = directory.get(name_at..name_at + name_len)?;
+        if size == 0xFFFF_FFFF {
+            // The zip64 extra field starts with the uncompressed size.
+            let mut extra = directory.get(name_at + name_len..name_at + name_len + extra_len)?;
+            while extra.len() >= 4 {
+                let id = u16_at(extra, 0)?;
+                let field_len = u16_at(extra, 2)? as usize;
+                if id == 0x0001 {
+                    size = u64_at(extra, 4)?;
+                    break;
+                }
+                extra = extra.get(4 + field_len..)?;
+            }
+        }
+        listing.push(String::from_utf8_lossy(name).into_owned(), size);
+        at = name_at + name_len + extra_len + comment_len;
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestTempDir;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::fs;
+    use std::io::Write;
+
+    /// A ustar header for a regular file or, with `kind` `b'5'`, a directory.
+    fn tar_header(name: &str, size: usize, kind: u8) -> [u8; 512] {
+        let mut block = [0u8; 512];
+        block[..name.len()].copy_from_slice(name.as_bytes());
+        block[100..107].copy_from_slice(b"0000644");
+        block[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        block[156] = kind;
+        block[257..263].copy_from_slice(b"ustar\0");
+        block[148..156].fill(b' ');
+        let sum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+        block[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        block
+    }
+
+    fn tar_archive(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = Vec::new();
+        for (name, data) in members {
+            let kind = if name.ends_with('/') { b'5' } else { b'0' };
+            tar.extend_from_slice(&tar_header(name, data.len(), kind));
+            tar.extend_from_slice(data);
+            tar.resize((tar.len() + 511) / 512 * 512, 0);
+        }
+        tar.extend_from_slice(&[0; 1024]);
+        tar
+    }
+
+    /// A zip archive of stored (uncompressed) members, with a central directory.
+    fn zip_archive(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data) in members {
+            let offset = zip.len() as u32;
+            let sizes = [data.len() as u32; 2];
+            zip.extend_from_slice(b"PK\x03\x04\x14\0\0\0\0\0\0\0\0\0\0\0\0\0");
+            sizes
+                .iter()
+                .for_each(|s| zip.extend_from_slice(&s.to_le_bytes()));
+            zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            zip.extend_from_slice(&[0, 0]);
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(data);
+
+            directory.extend_from_slice(b"PK\x01\x02\x14\0\x14\0\0\0\0\0\0\0\0\0\0\0\0\0");
+            sizes
+                .iter()
+                .for_each(|s| directory.extend_from_slice(&s.to_le_bytes()));
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_at = zip.len() as u32;
+        zip.extend_from_slice(&directory);
+        zip.extend_from_slice(b"PK\x05\x06\0\0\0\0");
+        let count = (members.len() as u16).to_le_bytes();
+        zip.extend_from_slice(&count);
+        zip.extend_from_slice(&count);
+        zip.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&directory_at.to_le_bytes());
+        zip.extend_from_slice(&[0, 0]);
+        zip
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn archives_list_their_members_without_recursing() {
+        let temp_dir = TestTempDir::new("archive_listing");
+        let members: &[(&str, &[u8])] = &[
+            ("src/", b""),
+            ("src/main.rs", b"fn main() {}\n"),
+            ("vendor.zip", b"PK\x05\x06 nested archives are not opened"),
+        ];
+        let expected = "  Length  Name\n\
+                        --------  ----\n       \
+                               0  src/\n      \
+                              13  src/main.rs\n      \
+                              35  vendor.zip\n\
+                        --------  ----\n      \
+                              48  3 entries\n";
+        let zip = temp_dir.path.join("bundle.zip");
+        fs::write(&zip, zip_archive(members)).unwrap();
+        assert_eq!(archive_listing(&zip, 100).as_deref(), Some(expected));
+        let tar_gz = temp_dir.path.join("bundle.tar.gz");
+        fs::write(&tar_gz, gzip(&tar_archive(members))).unwrap();
+        assert_eq!(archive_listing(&tar_gz, 100).as_deref(), Some(expected));
+
+        assert_eq!(
+            archive_listing(&zip, 1).as_deref(),
+            Some(
+                "  Length  Name\n\
+                 --------  ----\n       \
+                        0  src/\n\
+                 [... 2 more entries]\n\
+                 --------  ----\n      \
+                       48  3 entries\n"
+            )
+        );
+
+        let log = temp_dir.path.join("server.log.gz");
+        fs::write(&log, gzip(b"plain text, not a tar\n")).unwrap();
+        assert!(archive_listing(&log, 100)
+            .unwrap()
+            .ends_with("      22  server.log\n--------  ----\n      22  1 entry\n"));
+
+        let corrupt = temp_dir.path.join("corrupt.zip");
+        fs::write(&corrupt, b"PK\x03\x04 truncated").unwrap();
+        assert_eq!(archive_listing(&corrupt, 100), None);
+        let corrupt = temp_dir.path.join("corrupt.tgz");
+        fs::write(&corrupt, gzip(b"not a tar header")).unwrap();
+        assert_eq!(archive_listing(&corrupt, 100), None);
+        assert!(is_archive(Path::new("a/B.TAR.GZ")) && !is_archive(Path::new("a/b.rs")));
+    }
+}
@@ -0,0 +1,343 @@
+use crate::filter::normalize_extension;
+use std::path::Path;
+
+/// Comment and string syntax of one language family, for `--strip-comments`.
+struct Syntax {
+    line: &'static [u8],
+    block: Option<(&'static [u8], &'static [u8])>,
+    /// Block comments nest, as in Rust.
+    nested_blocks: bool,
+    /// Single-character string delimiters. All but `` ` `` end at a newline.
+    quotes: &'static [u8],
+    /// `"""` and `'''` strings, as in Python and TOML.
+    triple_quotes: bool,
+    /// Rust raw strings (`r#"…"#`) and lifetimes, which look like unclosed char literals.
+    rust_literals: bool,
+    /// `` ` `` strings have no escapes, as in Go.
+    raw_backticks: bool,
+}
+
+const C_FAMILY: Syntax = Syntax {
+    line: b"//",
+    block: Some((b"/*", b"*/")),
+    nested_blocks: false,
+    quotes: b"\"'",
+    triple_quotes: false,
+    rust_literals: false,
+    raw_backticks: false,
+};
+
+const JS_FAMILY: Syntax = Syntax {
+    quotes: b"\"'`",
+    ..C_FAMILY
+};
+
+const GO: Syntax = Syntax {
+    quotes: b"\"'`",
+    raw_backticks: true,
+    ..C_FAMILY
+};
+
+const RUST: Syntax = Syntax {
+    nested_blocks: true,
+    quotes: b"\"",
+    rust_literals: true,
+    ..C_FAMILY
+};
+
+const HASH_FAMILY: Syntax = Syntax {
+    line: b"#",
+    block: None,
+    nested_blocks: false,
+    quotes: b"\"'",
+    triple_quotes: true,
+    rust_literals: false,
+    raw_backticks: false,
+};
+
+fn syntax_for_path(path: &Path) -> Option<&'static Syntax> {
+    let ext = normalize_extension(path.extension()?.to_str()?)?;
+    let syntax = match ext.as_str() {
+        ".rs" => &RUST,
+        ".c" | ".h" | ".cpp" | ".cc" | ".cxx" | ".hpp" | ".hh" | ".java" => &C_FAMILY,
+        ".js" | ".mjs" | ".cjs" | ".jsx" | ".ts" | ".tsx" => &JS_FAMILY,
+        ".go" => &GO,
+        ".py" | ".rb" | ".toml" => &HASH_FAMILY,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+/// Removes line and block comments from `content`, choosing the syntax by the extension
+/// of `path`. Returns `None` for languages it does not know, whose text is kept as is.
+///
+/// Comment markers inside string literals are left alone. A line that held only a
+/// comment is dropped, and whitespace before a removed trailing comment is trimmed;
+/// blank lines of the original are kept. A `#!` line at the very start is kept too.
+pub(crate) fn strip_comments(path: &Path, content: &str) -> Option<String> {
+    let syntax = syntax_for_path(path)?;
+    Some(Stripper::new(syntax, content.as_bytes()).run())
+}
+
+struct Stripper<'a> {
+    syntax: &'static Syntax,
+    src: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    line: Vec<u8>,
+    /// Part of the current line was a comment.
+    stripped: bool,
+    block_depth: usize,
+}
+
+impl<'a> Stripper<'a> {
+    fn new(syntax: &'static Syntax, src: &'a [u8]) -> Self {
+        Stripper {
+            syntax,
+            src,
+            pos: 0,
+            out: Vec::with_capacity(src.len()),
+            line: Vec::new(),
+            stripped: false,
+            block_depth: 0,
+        }
+    }
+
+    fn run(mut self) -> String {
+        if self.syntax.line == b"#" && self.src.starts_with(b"#!") {
+            self.copy_until_newline();
+        }
+        while self.pos < self.src.len() {
+            if self.block_depth > 0 {
+                self.skip_block();
+            } else {
+                self.code();
+            }
+        }
+        if !self.line.is_empty() || self.stripped {
+            self.end_line(false);
+        }
+        // Only ASCII delimiters were cut around, so the bytes are still valid UTF-8.
+        String::from_utf8(self.out).expect("comments are removed at ASCII boundaries")
+    }
+
+    fn at(&self, token: &[u8]) -> bool {
+        self.src[self.pos..].starts_with(token)
+    }
+
+    fn code(&mut self) {
+        let syntax = self.syntax;
+        let byte = self.src[self.pos];
+        if byte == b'\n' {
+            self.pos += 1;
+            self.end_line(true);
+        } else if self.at(syntax.line) {
+            self.stripped = true;
+            while self.pos < self.src.len() && !self.at(b"\n") && !self.at(b"\r\n") {
+                self.pos += 1;
+            }
+        } else if let Some((open, _)) = syntax.block.filter(|(open, _)| self.at(open)) {
+            self.stripped = true;
+            self.block_depth = 1;
+            self.pos += open.len();
+        } else if syntax.rust_literals && self.at_raw_string() {
+            self.copy_raw_string();
+        } else if syntax.rust_literals && byte == b'\'' {
+            self.copy_char_or_lifetime();
+        } else if syntax.triple_quotes && (self.at(b"\"\"\"") || self.at(b"'''")) {
+            let quote = self.src[self.pos..self.pos + 3].to_vec();
+            self.copy(3);
+            self.copy_string(&quote, true, true);
+        } else if syntax.quotes.contains(&byte) {
+            let backtick = byte == b'`';
+            self.copy(1);
+            self.copy_string(&[byte], backtick, !(backtick && syntax.raw_backticks));
+        } else {
+            self.copy(1);
+        }
+    }
+
+    fn skip_block(&mut self) {
+        let (open, close) = self.syntax.block.expect("only entered for block comments");
+        if self.syntax.nested_blocks && self.at(open) {
+            self.block_depth += 1;
+            self.pos += open.len();
+        } else if self.at(close) {
+            self.block_depth -= 1;
+            self.pos += close.len();
+        } else if self.at(b"\n") {
+            self.pos += 1;
+            self.end_line(true);
+            self.stripped = true;
+        } else {
+            self.pos += 1;
+        }
+    }
+
+    fn copy(&mut self, len: usize) {
+        let end = (self.pos + len).min(self.src.len());
+        self.line.extend_from_slice(&self.src[self.pos..end]);
+        self.pos = end;
+    }
+
+    fn copy_until_newline(&mut self) {
+        while self.pos < self.src.len() && self.src[self.pos] != b'\n' {
+            self.copy(1);
+        }
+    }
+
+    /// Copies the rest of a string literal whose opening `quote` was already copied.
+    fn copy_string(&mut self, quote: &[u8], multiline: bool, escapes: bool) {
+        while self.pos < self.src.len() {
+            if self.at(quote) {
+                self.copy(quote.len());
+                return;
+            }
+            match self.src[self.pos] {
+                b'\\' if escapes => self.copy(2),
+                b'\n' if !multiline => return,
+                b'\n' => {
+                    self.pos += 1;
+                    self.end_line(true);
+                }
+                _ => self.copy(1),
+            }
+        }
+    }
+
+    /// `r"…"`, `r#"…"#`, and their `br` forms, not preceded by an identifier character.
+    fn at_raw_string(&self) -> bool {
+        let rest = &self.src[self.pos..];
+        let after_prefix = match rest {
+            [b'r', ..] => &rest[1..],
+            [b'b', b'r', ..] => &rest[2..],
+            _ => return false,
+        };
+        let starts_word = self.pos == 0 || !is_ident_byte(self.src[self.pos - 1]);
+        let hashes = after_prefix.iter().take_while(|&&b| b == b'#').count();
+        starts_word && after_prefix.get(hashes) == Some(&b'"')
+    }
+
+    fn copy_raw_string(&mut self) {
+        let prefix = if self.src[self.pos] == b'b' { 2 } else { 1 };
+        let hashes = self.src[self.pos + prefix..]
+            .iter()
+            .take_while(|&&b| b == b'#')
+            .count();
+        self.copy(prefix + hashes + 1);
+        let mut close = vec![b'"'];
+        close.extend(std::iter::repeat(b'#').take(hashes));
+        while self.pos < self.src.len() {
+            if self.at(&close) {
+                self.copy(close.len());
+                return;
+            }
+            if self.src[self.pos] == b'\n' {
+                self.pos += 1;
+                self.end_line(true);
+            } else {
+                self.copy(1);
+            }
+        }
+    }
+
+    /// `'x'`, `'\n'`, and `'é'` are char literals; `'a` in `&'a str` is a lifetime.
+    fn copy_char_or_lifetime(&mut self) {
+        let rest = &self.src[self.pos..];
+        if rest.get(1) == Some(&b'\\') {
+            self.copy(1);
+            self.copy_string(b"'", false, true);
+            return;
+        }
+        let char_len = rest.get(1).map_or(1, |&b| match b.leading_ones() {
+            0 => 1,
+            n => n as usize,
+        });
+        if rest.get(1 + char_len) == Some(&b'\'') {
+            self.copy(char_len + 2);
+        } else {
+            self.copy(1);
+        }
+    }
+
+    /// Moves the current line to the output. A line with a removed comment loses its
+    /// trailing whitespace and is dropped altogether when nothing else is left.
+    fn end_line(&mut self, newline: bool) {
+        let mut line = std::mem::take(&mut self.line);
+        if self.stripped {
+            let crlf = line.last() == Some(&b'\r');
+            if crlf {
+                line.pop();
+            }
+            while matches!(line.last(), Some(b' ' | b'\t')) {
+                line.pop();
+            }
+            if line.is_empty() {
+                self.stripped = self.block_depth > 0;
+                return;
+            }
+            if crlf {
+                line.push(b'\r');
+            }
+        }
+        self.out.extend_from_slice(&line);
+        if newline {
+            self.out.push(b'\n');
+        }
+        self.stripped = self.block_depth > 0;
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(name: &str, content: &str) -> Option<String> {
+        strip_comments(Path::new(name), content)
+    }
+
+    #[test]
+    fn c_style_comments_are_removed_outside_strings() {
+        let source = "// header\nint main() { /* inline */ return 0; } // trailing\n\n/*\n * block\n */\nchar *s = \"// not a comment /* either */\";\nchar c = '\"'; // quote char\n";
+        assert_eq!(
+            strip("main.c", source).unwrap(),
+            "int main() {  return 0; }\n\nchar *s = \"// not a comment /* either */\";\nchar c = '\"';\n"
+        );
+        assert_eq!(
+            strip("a.ts", "const u = `http://x/${y}`; // url\n").unwrap(),
+            "const u = `http://x/${y}`;\n"
+        );
+        assert_eq!(
+            strip("a.go", "p := `C:\\` // dir\n").unwrap(),
+            "p := `C:\\`\n"
+        );
+    }
+
+    #[test]
+    fn rust_handles_nesting_raw_strings_and_lifetimes() {
+        let source = "/* outer /* inner */ still comment */\nfn f<'a>(s: &'a str) -> char { // doc\n    let _ = r#\"// \"kept\"\"#;\n    '/' // slash\n}\n";
+        assert_eq!(
+            strip("lib.rs", source).unwrap(),
+            "fn f<'a>(s: &'a str) -> char {\n    let _ = r#\"// \"kept\"\"#;\n    '/'\n}\n"
+        );
+    }
+
+    #[test]
+    fn hash_comments_keep_shebang_and_strings() {
+        let source =
+            "#!/usr/bin/env python\n# comment\nx = \"# not\"  # trailing\ns = '''\n# inside\n'''\n";
+        assert_eq!(
+            strip("tool.py", source).unwrap(),
+            "#!/usr/bin/env python\nx = \"# not\"\ns = '''\n# inside\n'''\n"
+        );
+        assert_eq!(
+            strip("Cargo.toml", "[package] # pkg\r\nname = \"a#b\"\r\n").unwrap(),
+            "[package]\r\nname = \"a#b\"\r\n"
+        );
+        assert_eq!(strip("notes.md", "# Title\n"), None);
+    }
+}
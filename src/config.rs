@@ -0,0 +1,421 @@
+use crate::filter::{FileOrder, HiddenFiles};
+use crate::render::{HeaderStyle, OutputFormat, TocPlacement, XmlLayout, XmlSkipped};
+use crate::tokens::TrimStrategy;
+use crate::tree::TreeDetail;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+
+/// Extensions read by default.
+pub const DEFAULT_ALLOWED_EXTENSIONS: &[&str] = &[
+    ".txt", ".md", ".py", ".js", ".java", ".cpp", ".c", ".cs", ".rb", ".go", ".rs", ".hpp", ".ts",
+    ".tsx", ".d.ts", ".jsx", ".toml", ".msg", ".srv", ".action", ".launch", ".urdf", ".xacro",
+    ".cfg",
+];
+
+/// Extensions skipped by default.
+pub const DEFAULT_IGNORE_EXTENSIONS: &[&str] = &[
+    ".bin", ".zip", ".tar", ".gz", ".7z", ".rar", ".exe", ".dll", ".so", ".dylib", ".a", ".lib",
+    ".obj", ".o", ".class", ".jar", ".war", ".ear", ".ipynb", ".jpg", ".jpeg", ".png", ".gif",
+];
+
+/// Directory names that are never descended into by default.
+pub const DEFAULT_IGNORE_DIRS: &[&str] = &[
+    ".git",
+    ".vscode",
+    "target",
+    "node_modules",
+    "__pycache__",
+    ".idea",
+    "build",
+    "dist",
+    ".ruff_cache",
+    ".cache",
+    ".tox",
+    ".nox",
+    ".pytest_cache",
+    "htmlcov",
+    "instance",
+    ".env",
+    ".venv",
+    "env",
+    "venv",
+    "ENV",
+    "site",
+    ".mypy_cache",
+    "debug",
+];
+
+/// Filenames included regardless of their extension by default.
+pub const DEFAULT_WHITELIST_FILENAMES: &[&str] = &["Dockerfile", "Makefile", "justfile"];
+
+/// Extensionless filenames that are eligible while an extension allowlist is active.
+pub const DEFAULT_EXTENSIONLESS_FILENAMES: &[&str] = &[
+    "Makefile",
+    "Dockerfile",
+    "LICENSE",
+    "README",
+    ".gitignore",
+    ".gitattributes",
+    "justfile",
+];
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct WhitelistConfig {
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, Default)]
+pub struct BlacklistConfig {
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub directories: Vec<String>,
+}
+
+/// Filters loaded from a TOML file with `--config`.
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub whitelist: WhitelistConfig,
+    #[serde(default)]
+    pub blacklist: BlacklistConfig,
+}
+
+fn sorted_strings(values: &[&str]) -> Vec<String> {
+    let mut items: Vec<String> = values.iter().map(|s| s.to_string()).collect();
+    items.sort_unstable();
+    items
+}
+
+/// Formats a TOML error on one line, quoting the offending line so the key is visible.
+fn toml_error_message(content: &str, err: &toml::de::Error) -> String {
+    let message = err.message().trim_end().replace('\n', "; ");
+    match err.span() {
+        Some(span) => {
+            let line_start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = content[span.start..]
+                .find('\n')
+                .map_or(content.len(), |i| span.start + i);
+            let line_number = content[..span.start].matches('\n').count() + 1;
+            format!(
+                "Config TOML parse error at line {} (`{}`): {}",
+                line_number,
+                content[line_start..line_end].trim(),
+                message
+            )
+        }
+        None => format!("Config TOML parse error: {}", message),
+    }
+}
+
+impl Config {
+    /// The built-in filters, as printed by `--generate-config`.
+    pub fn from_defaults() -> Self {
+        Config {
+            whitelist: WhitelistConfig {
+                extensions: sorted_strings(DEFAULT_ALLOWED_EXTENSIONS),
+                files: DEFAULT_WHITELIST_FILENAMES
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            },
+            blacklist: BlacklistConfig {
+                extensions: DEFAULT_IGNORE_EXTENSIONS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+                files: vec![],
+                directories: sorted_strings(DEFAULT_IGNORE_DIRS),
+            },
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Config file not found or unreadable: {}", e))?;
+        let config: Config =
+            toml::from_str(&content).map_err(|e| toml_error_message(&content, &e))?;
+        Ok(config)
+    }
+}
+
+/// File name looked up in the first scanned directory when `--config` is not given.
+pub const PROJECT_CONFIG_FILENAME: &str = ".oreuit.toml";
+
+/// A project config such as `.oreuit.toml`: defaults for command-line options, keyed by
+/// their long names in snake_case, plus the optional `[whitelist]` / `[blacklist]` tables
+/// of `Config`.
+///
+/// Options given on the command line win over these values. Unknown keys are rejected so
+/// that typos are reported instead of silently ignored.
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub whitelist: Option<WhitelistConfig>,
+    pub blacklist: Option<BlacklistConfig>,
+    pub directory: Option<Vec<String>>,
+    /// Replaces the built-in allowlist, like `--extensions .rs,.toml`.
+    pub extensions: Option<Vec<String>>,
+    /// Added to the allowlist, like `--extensions +,.vue`.
+    pub extensions_add: Option<Vec<String>>,
+    pub ignore_extensions: Option<Vec<String>>,
+    pub ignore_files: Option<Vec<String>>,
+    /// Replaces the built-in directory ignore list, like `--ignore-dirs build`.
+    pub ignore_dirs: Option<Vec<String>>,
+    /// Added to the directory ignore list, like `--ignore-dirs +,temp`.
+    pub ignore_dirs_add: Option<Vec<String>>,
+    pub whitelist_filenames: Option<Vec<String>>,
+    pub include_glob: Option<Vec<String>>,
+    pub exclude_glob: Option<Vec<String>>,
+    pub output: Option<String>,
+    pub format: Option<OutputFormat>,
+    pub xml_skipped: Option<XmlSkipped>,
+    pub header_style: Option<HeaderStyle>,
+    pub max_size: Option<u64>,
+    pub min_size: Option<u64>,
+    pub since: Option<String>,
+    pub max_depth: Option<usize>,
+    pub max_tokens: Option<usize>,
+    pub trim_strategy: Option<TrimStrategy>,
+    pub jobs: Option<usize>,
+    pub head_lines: Option<usize>,
+    pub tail_lines: Option<usize>,
+    pub gzip_level: Option<u32>,
+    pub clipboard: Option<bool>,
+    pub tee: Option<bool>,
+    pub append: Option<bool>,
+    pub respect_gitignore: Option<bool>,
+    pub follow_symlinks: Option<bool>,
+    pub line_numbers: Option<bool>,
+    pub no_token_counts: Option<bool>,
+    pub gzip: Option<bool>,
+    pub stats: Option<bool>,
+    pub no_stats: Option<bool>,
+    pub quiet: Option<bool>,
+    pub dedupe: Option<bool>,
+    pub no_oreuitignore: Option<bool>,
+    pub encoding: Option<Vec<String>>,
+    pub show_encoding: Option<bool>,
+    pub hidden: Option<HiddenFiles>,
+    pub grep: Option<String>,
+    pub split_size: Option<u64>,
+    pub split_tokens: Option<u64>,
+    pub grep_exclude: Option<Vec<String>>,
+    pub max_lines_per_file: Option<usize>,
+    pub tree_details: Option<Vec<TreeDetail>>,
+    pub tree_dir_totals: Option<bool>,
+    pub exclude_regex: Option<Vec<String>>,
+    pub git_tracked: Option<bool>,
+    pub toc: Option<TocPlacement>,
+    pub changed_since: Option<String>,
+    pub strip_comments: Option<bool>,
+    pub squeeze_blank: Option<bool>,
+    pub xml_layout: Option<XmlLayout>,
+    pub relative_to: Option<String>,
+    pub sort: Option<FileOrder>,
+    pub verbose: Option<bool>,
+    pub whitelist_case_insensitive: Option<bool>,
+    pub tree_stats: Option<bool>,
+    pub ascii_tree: Option<bool>,
+    pub report: Option<String>,
+    pub normalize_newlines: Option<bool>,
+    pub trim_whitespace: Option<bool>,
+    pub trim_markdown: Option<bool>,
+    pub lang_map: Option<Vec<String>>,
+    pub show_language: Option<bool>,
+    pub max_size_for: Option<Vec<String>>,
+    pub stream_threshold: Option<u64>,
+    pub no_tree: Option<bool>,
+    pub git_blame_summary: Option<bool>,
+    pub ignore_file_pattern: Option<Vec<String>>,
+    pub hashes: Option<bool>,
+    pub hash_manifest: Option<String>,
+    pub binary_preview: Option<usize>,
+    pub embed_images: Option<u64>,
+    pub notebooks: Option<bool>,
+    pub archive_listing: Option<usize>,
+    pub pin: Option<Vec<String>>,
+    /// The top-level keys as written, for `option_values`.
+    #[serde(skip)]
+    values: toml::Table,
+}
+
+impl ProjectConfig {
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Config file not found or unreadable: {}", e))?;
+        let mut config: ProjectConfig =
+            toml::from_str(&content).map_err(|e| toml_error_message(&content, &e))?;
+        config.values = toml::from_str(&content)?;
+        Ok(config)
+    }
+
+    /// The options the file sets, each key with the values its command-line option takes:
+    /// one per array element, otherwise one. `extensions_add` and `ignore_dirs_add` are
+    /// folded into `extensions` and `ignore_dirs` in their `+,` syntax, and the
+    /// `[whitelist]` / `[blacklist]` tables are left out.
+    pub fn option_values(&self) -> Vec<(String, Vec<String>)> {
+        let text = |value: &toml::Value| match value {
+            toml::Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        let additive = [
+            (
+                "extensions",
+                additive_list(&self.extensions, &self.extensions_add),
+            ),
+            (
+                "ignore_dirs",
+                additive_list(&self.ignore_dirs, &self.ignore_dirs_add),
+            ),
+        ];
+        let mut options: Vec<(String, Vec<String>)> = self
+            .values
+            .iter()
+            .filter(|(key, _)| {
+                !matches!(
+                    key.as_str(),
+                    "extensions" | "extensions_add" | "ignore_dirs" | "ignore_dirs_add"
+                )
+            })
+            .filter_map(|(key, value)| match value {
+                toml::Value::Table(_) => None,
+                toml::Value::Array(items) => Some((key.clone(), items.iter().map(text).collect())),
+                value => Some((key.clone(), vec![text(value)])),
+            })
+            .collect();
+        for (key, list) in additive {
+            options.extend(list.map(|list| (key.to_string(), vec![list])));
+        }
+        options
+    }
+
+    /// The `[whitelist]` / `[blacklist]` filters, when the file has either table. These
+    /// replace the filter options entirely, as with a plain `Config`.
+    pub fn filters(&self) -> Option<Config> {
+        if self.whitelist.is_none() && self.blacklist.is_none() {
+            return None;
+        }
+        Some(Config {
+            whitelist: self.whitelist.clone().unwrap_or_default(),
+            blacklist: self.blacklist.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Joins a replacing list and an additive list into the `--extensions` / `--ignore-dirs`
+/// syntax, where a leading `+,` adds to the built-in defaults.
+fn additive_list(replace: &Option<Vec<String>>, add: &Option<Vec<String>>) -> Option<String> {
+    match (replace, add) {
+        (Some(list), add) => Some(
+            list.iter()
+                .chain(add.iter().flatten())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        (None, Some(add)) => Some(format!("+,{}", add.join(","))),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestTempDir;
+
+    #[test]
+    fn config_loads_valid_toml() {
+        let temp_dir = TestTempDir::new("valid_toml");
+        let config_path = temp_dir.write_file(
+            "oreuit.toml",
+            r#"
+[whitelist]
+extensions = [".rs", "py"]
+files = ["Dockerfile"]
+
+[blacklist]
+extensions = [".png"]
+files = ["Cargo.lock"]
+directories = ["target"]
+"#,
+        );
+
+        let config = Config::from_file(config_path.to_str().unwrap()).unwrap();
+
+        assert_eq!(config.whitelist.extensions, vec![".rs", "py"]);
+        assert_eq!(config.whitelist.files, vec!["Dockerfile"]);
+        assert_eq!(config.blacklist.extensions, vec![".png"]);
+        assert_eq!(config.blacklist.files, vec!["Cargo.lock"]);
+        assert_eq!(config.blacklist.directories, vec!["target"]);
+    }
+
+    #[test]
+    fn config_errors_on_missing_file() {
+        let temp_dir = TestTempDir::new("missing_config");
+        let missing_path = temp_dir.path.join("does_not_exist.toml");
+
+        let err = Config::from_file(missing_path.to_str().unwrap()).unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("Config file not found or unreadable"));
+    }
+
+    #[test]
+    fn config_errors_on_invalid_toml() {
+        let temp_dir = TestTempDir::new("invalid_toml");
+        let config_path = temp_dir.write_file(
+            "broken.toml",
+            r#"
+[whitelist
+extensions = [".rs"]
+"#,
+        );
+
+        let err = Config::from_file(config_path.to_str().unwrap()).unwrap_err();
+
+        assert!(err.to_string().contains("Config TOML parse error"));
+    }
+
+    #[test]
+    fn project_config_reads_options_and_points_at_bad_keys() {
+        let temp_dir = TestTempDir::new("project_config");
+        let config_path = temp_dir.write_file(
+            ".oreuit.toml",
+            r#"
+extensions_add = [".vue"]
+max_size = 2048
+format = "markdown"
+"#,
+        );
+
+        let config = ProjectConfig::from_file(config_path.to_str().unwrap()).unwrap();
+
+        assert_eq!(config.extensions_add, Some(vec![".vue".to_string()]));
+        assert_eq!(config.max_size, Some(2048));
+        assert_eq!(config.format, Some(OutputFormat::Markdown));
+        assert!(config.filters().is_none());
+        let mut values = config.option_values();
+        values.sort();
+        assert_eq!(
+            values,
+            [
+                ("extensions".to_string(), vec!["+,.vue".to_string()]),
+                ("format".to_string(), vec!["markdown".to_string()]),
+                ("max_size".to_string(), vec!["2048".to_string()]),
+            ]
+        );
+
+        let broken_path = temp_dir.write_file("broken.toml", "extension = [\".rs\"]\n");
+        let err = ProjectConfig::from_file(broken_path.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Config TOML parse error"));
+        assert!(err.to_string().contains("unknown field `extension`"));
+    }
+}
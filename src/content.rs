@@ -0,0 +1,1665 @@
+use crate::archive::{archive_listing, is_archive};
+use crate::comments::strip_comments;
+use crate::filter::{is_broken_symlink, normalize_extension, shebang_interpreter};
+use crate::last_commit::LastCommit;
+use crate::notebook::notebook_source;
+use crate::sha256::{sha256_hex, Sha256};
+use crate::tokens::{estimate_tokens, format_size, format_thousands, TokenCounter};
+use encoding_rs::{
+    CoderResult, DecoderResult, Encoding, EUC_JP, ISO_2022_JP, SHIFT_JIS, UTF_16BE, UTF_16LE,
+    UTF_8, WINDOWS_1252,
+};
+use regex_automata::meta::Regex;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// One file in the contents section, already read or replaced with a placeholder.
+#[derive(Clone, Debug)]
+pub struct FileSection {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub dir_name: String,
+    pub content: String,
+    /// Set when `content` is a placeholder instead of the file's text.
+    pub skipped: Option<SkipReason>,
+    /// Estimated token count of `content`.
+    pub tokens: usize,
+    /// Name of the encoding the file was decoded from, when it was not UTF-8.
+    pub encoding: Option<&'static str>,
+    /// Size of the file on disk in bytes, also for files replaced with a placeholder.
+    pub size: u64,
+    /// Set for a file over the stream threshold: `content` is empty and the text is read
+    /// again, a chunk at a time, when the report is written.
+    pub streamed: Option<StreamedContent>,
+    /// The last commit that touched the file, with `SummaryOptions::git_blame_summary`.
+    pub last_commit: Option<LastCommit>,
+    /// For a file without an extension, the interpreter on its `#!` line, e.g. `bash`.
+    pub interpreter: Option<String>,
+    /// SHA-256 of the file's bytes on disk as lowercase hex, with
+    /// `SummaryOptions::hashes`; also set for most placeholders.
+    pub sha256: Option<String>,
+    /// Set for a file given to `SummaryOptions::pin`; its header gets `[PINNED]`.
+    pub pinned: bool,
+}
+
+impl FileSection {
+    /// Length of the text in bytes, also for a streamed file.
+    pub fn text_len(&self) -> usize {
+        self.streamed
+            .as_ref()
+            .map_or(self.content.len(), |streamed| streamed.len)
+    }
+
+    /// Number of lines of the text, counted like `str::lines`, also for a streamed file.
+    pub fn line_count(&self) -> usize {
+        self.streamed
+            .as_ref()
+            .map_or_else(|| self.content.lines().count(), |streamed| streamed.lines)
+    }
+
+    pub(crate) fn ends_with_newline(&self) -> bool {
+        self.streamed
+            .as_ref()
+            .map_or(self.content.ends_with('\n'), |streamed| {
+                streamed.ends_with_newline
+            })
+    }
+
+    /// Longest run of backticks in the text, which sizes Markdown fences.
+    pub(crate) fn longest_backtick_run(&self) -> usize {
+        match &self.streamed {
+            Some(streamed) => streamed.longest_backtick_run,
+            None => {
+                let mut scan = TextScan::default();
+                scan.feed(&self.content);
+                scan.longest_backtick_run
+            }
+        }
+    }
+
+    /// Passes the text to `write`: `content` at once, or a streamed file decoded again a
+    /// chunk at a time.
+    pub(crate) fn for_each_chunk(
+        &self,
+        write: &mut dyn FnMut(&str) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match &self.streamed {
+            Some(streamed) => {
+                decode_chunks(&self.path, streamed.encoding, streamed.bom_len, true, write)?;
+                Ok(())
+            }
+            None => write(&self.content),
+        }
+    }
+}
+
+/// Where a streamed file's text comes from, with figures about it gathered while its
+/// encoding was checked.
+#[derive(Clone, Debug)]
+pub struct StreamedContent {
+    encoding: &'static Encoding,
+    /// Bytes of a byte order mark to skip, as files read in full leave it out too.
+    bom_len: usize,
+    len: usize,
+    lines: usize,
+    ends_with_newline: bool,
+    longest_backtick_run: usize,
+    tokens: usize,
+}
+
+/// A file that would be read, as listed by `Summarizer::dry_run`.
+#[derive(Clone, Debug)]
+pub struct PlannedFile {
+    pub path: PathBuf,
+    pub relative_path: String,
+    pub dir_name: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Set when the file would be replaced with a placeholder (`TooLarge`, `Binary`, or
+    /// `BrokenSymlink`).
+    pub skipped: Option<SkipReason>,
+}
+
+/// Why a file's content was replaced with a placeholder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    TooLarge,
+    Binary,
+    DecodeFailed,
+    TokenBudget,
+    /// Same text as an earlier file; see `mark_duplicates`.
+    Duplicate,
+    /// A symbolic link whose target does not exist.
+    BrokenSymlink,
+}
+
+impl SkipReason {
+    /// Text emitted in place of the file content.
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            SkipReason::TooLarge => "[File size exceeds limit; skipped]\n",
+            SkipReason::Binary => "[Binary file skipped]\n",
+            SkipReason::DecodeFailed => "[Cannot decode file content]",
+            SkipReason::TokenBudget => "[omitted: token budget exceeded]\n",
+            // `mark_duplicates` names the first file instead.
+            SkipReason::Duplicate => "[Duplicate file skipped]\n",
+            SkipReason::BrokenSymlink => "[Broken symlink]\n",
+        }
+    }
+
+    /// Short kebab-case name, e.g. `too-large`, used as the `skipped` attribute in XML and
+    /// in the `--report` file.
+    pub fn name(self) -> &'static str {
+        match self {
+            SkipReason::TooLarge => "too-large",
+            SkipReason::Binary => "binary",
+            SkipReason::DecodeFailed => "decode-failed",
+            SkipReason::TokenBudget => "token-budget",
+            SkipReason::Duplicate => "duplicate",
+            SkipReason::BrokenSymlink => "broken-symlink",
+        }
+    }
+
+    /// Short description used in reports, e.g. by `--verbose`.
+    pub fn label(self) -> &'static str {
+        match self {
+            SkipReason::TooLarge => "larger than --max-size",
+            SkipReason::Binary => "binary",
+            SkipReason::DecodeFailed => "cannot decode",
+            SkipReason::TokenBudget => "over the --max-tokens budget",
+            SkipReason::Duplicate => "duplicate",
+            SkipReason::BrokenSymlink => "broken symlink",
+        }
+    }
+}
+
+/// Share of control bytes in the sampled window above which a file counts as binary.
+pub const DEFAULT_BINARY_THRESHOLD: f64 = 0.1;
+
+/// Size in bytes above which a file's text is streamed to the output instead of being
+/// read in full.
+pub const DEFAULT_STREAM_THRESHOLD: u64 = 4 * 1024 * 1024;
+
+const UTF16_BOMS: [[u8; 2]; 2] = [[0xFF, 0xFE], [0xFE, 0xFF]];
+
+/// Image extensions embedded with `embed_images`, and the MIME type each implies.
+pub(crate) const IMAGE_EXTENSIONS: &[(&str, &str)] = &[
+    (".png", "image/png"),
+    (".jpg", "image/jpeg"),
+    (".jpeg", "image/jpeg"),
+    (".gif", "image/gif"),
+    (".webp", "image/webp"),
+    (".bmp", "image/bmp"),
+];
+
+/// Determines if a file is binary by sampling its first 1024 bytes; see `looks_binary`.
+fn is_binary(file_path: &Path, threshold: f64) -> bool {
+    if let Ok(mut file) = fs::File::open(file_path) {
+        let mut buffer = [0u8; 1024];
+        if let Ok(n) = file.read(&mut buffer) {
+            return looks_binary(&buffer[..n], threshold);
+        }
+    }
+    true
+}
+
+/// A sample is binary when it contains a NUL byte or when the share of control bytes
+/// exceeds `threshold`. Samples starting with a UTF-16 BOM are text, since every ASCII
+/// character in them comes with a NUL byte.
+///
+/// Tab, line feed, form feed, carriage return, and escape (for ANSI-colored logs) are not
+/// counted as control bytes.
+fn looks_binary(sample: &[u8], threshold: f64) -> bool {
+    if UTF16_BOMS.iter().any(|bom| sample.starts_with(bom)) {
+        return false;
+    }
+    if sample.contains(&0) {
+        return true;
+    }
+    if sample.is_empty() {
+        return false;
+    }
+    let control = sample
+        .iter()
+        .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | 0x0c | b'\r' | 0x1b)) || b == 0x7f)
+        .count();
+    control as f64 / sample.len() as f64 > threshold
+}
+
+/// Labels of the encodings that `--encoding` accepts, listed when a label is unknown. Most
+/// have aliases too, such as `latin1` for windows-1252 or `sjis` for Shift_JIS.
+pub const ENCODING_NAMES: &[&str] = &[
+    "utf-8",
+    "utf-16le",
+    "utf-16be",
+    "shift_jis",
+    "euc-jp",
+    "iso-2022-jp",
+    "euc-kr",
+    "big5",
+    "gbk",
+    "gb18030",
+    "windows-874",
+    "windows-1250",
+    "windows-1251",
+    "windows-1252",
+    "windows-1253",
+    "windows-1254",
+    "windows-1255",
+    "windows-1256",
+    "windows-1257",
+    "windows-1258",
+    "iso-8859-2",
+    "iso-8859-3",
+    "iso-8859-4",
+    "iso-8859-5",
+    "iso-8859-6",
+    "iso-8859-7",
+    "iso-8859-8",
+    "iso-8859-8-i",
+    "iso-8859-10",
+    "iso-8859-13",
+    "iso-8859-14",
+    "iso-8859-15",
+    "iso-8859-16",
+    "koi8-r",
+    "koi8-u",
+    "ibm866",
+    "macintosh",
+    "x-mac-cyrillic",
+];
+
+/// Looks up a WHATWG encoding label, ignoring case and surrounding whitespace.
+pub(crate) fn encoding_for_label(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| {
+        format!(
+            "Unknown --encoding label '{}'; valid labels: {}",
+            label.trim(),
+            ENCODING_NAMES.join(", ")
+        )
+    })
+}
+
+/// Checks that `label` names an encoding `--encoding` accepts; the error lists
+/// `ENCODING_NAMES`.
+pub fn check_encoding_label(label: &str) -> Result<(), String> {
+    encoding_for_label(label).map(|_| ())
+}
+
+/// Reads a file as the first of `encodings` that decodes it without errors, or, with no
+/// `encodings`, as UTF-8 and otherwise the encoding `detect_encoding` picks. A single
+/// encoding is forced: bytes it cannot decode become U+FFFD instead of failing the file.
+/// Files starting with a UTF-8 or UTF-16 BOM are decoded accordingly, without the BOM.
+/// If every attempt fails, returns `None`.
+fn read_file_contents(
+    file_path: &Path,
+    encodings: &[&'static Encoding],
+) -> Option<(String, &'static Encoding)> {
+    decode_bytes(&fs::read(file_path).ok()?, encodings)
+}
+
+/// Decodes a whole file's bytes the way `read_file_contents` describes.
+fn decode_bytes(
+    bytes: &[u8],
+    encodings: &[&'static Encoding],
+) -> Option<(String, &'static Encoding)> {
+    let decode = |encoding: &'static Encoding, bytes: &[u8]| {
+        encoding
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(|text| (text.into_owned(), encoding))
+    };
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        // A byte order mark settles the encoding and is left out of the text.
+        return decode(encoding, &bytes[bom_len..]);
+    }
+    match encodings {
+        [] if is_iso_2022_jp(bytes) => decode(ISO_2022_JP, bytes),
+        [] => decode(UTF_8, bytes).or_else(|| detect_encoding(bytes)),
+        [encoding] => {
+            let (text, _) = encoding.decode_without_bom_handling(bytes);
+            Some((text.into_owned(), *encoding))
+        }
+        _ => encodings
+            .iter()
+            .find_map(|&encoding| decode(encoding, bytes)),
+    }
+}
+
+/// 7-bit text with a JIS escape sequence. It is also valid UTF-8, so it is checked first.
+fn is_iso_2022_jp(bytes: &[u8]) -> bool {
+    bytes.is_ascii() && has_jis_escape(bytes)
+}
+
+fn has_jis_escape(bytes: &[u8]) -> bool {
+    [b"\x1b$B", b"\x1b$@", b"\x1b(J", b"\x1b(I"]
+        .iter()
+        .any(|escape| bytes.windows(3).any(|window| window == *escape))
+}
+
+/// Decodes text that is not UTF-8 with whichever of Shift_JIS, EUC-JP, and windows-1252
+/// (Latin-1) gives the most plausible characters; ties go to the earlier one.
+///
+/// Shift_JIS and EUC-JP are used whenever they decode without errors, like the old fixed
+/// fallback. windows-1252 accepts any bytes, so it needs a minimum score; without one,
+/// the file keeps the decode-failure placeholder.
+fn detect_encoding(bytes: &[u8]) -> Option<(String, &'static Encoding)> {
+    const MIN_LATIN1_SCORE: f64 = 0.3;
+    let mut best: Option<(String, &'static Encoding, f64)> = None;
+    for encoding in [SHIFT_JIS, EUC_JP, WINDOWS_1252] {
+        let Some(text) = encoding.decode_without_bom_handling_and_without_replacement(bytes) else {
+            continue;
+        };
+        let score = plausibility(&text);
+        if encoding == WINDOWS_1252 && score < MIN_LATIN1_SCORE {
+            continue;
+        }
+        if best.as_ref().map_or(true, |(_, _, best)| score > *best) {
+            best = Some((text.into_owned(), encoding, score));
+        }
+    }
+    best.map(|(text, encoding, _)| (text, encoding))
+}
+
+/// Average weight of the non-ASCII characters in `text`: kana count most, kanji and
+/// full-width forms and accented Latin letters less, and half-width katakana and symbols,
+/// which mostly come from decoding with the wrong encoding, hardly at all.
+fn plausibility(text: &str) -> f64 {
+    let mut scan = TextScan::default();
+    scan.feed(text);
+    scan.plausibility()
+}
+
+fn char_weight(c: char) -> f64 {
+    match c {
+        '\u{3040}'..='\u{30ff}' => 1.0,
+        '\u{00c0}'..='\u{024f}' if c != '×' && c != '÷' => 0.6,
+        '\u{4e00}'..='\u{9fff}' | '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff5e}' => 0.5,
+        '\u{00a0}'..='\u{00bf}' | '\u{2010}'..='\u{205f}' | '€' | '×' | '÷' => 0.3,
+        '\u{ff61}'..='\u{ff9f}' => 0.1,
+        _ => 0.0,
+    }
+}
+
+/// Figures about a decoded text, fed a chunk at a time so that large files never have to
+/// be held in memory.
+#[derive(Default)]
+struct TextScan {
+    len: usize,
+    newlines: usize,
+    last: Option<char>,
+    backtick_run: usize,
+    longest_backtick_run: usize,
+    non_ascii: bool,
+    /// Seen a JIS escape sequence while the text was still ASCII.
+    jis_escape: bool,
+    /// End of the previous chunk, for escape sequences split between chunks.
+    tail: String,
+    /// Sum and count of `char_weight` over the non-ASCII characters.
+    weight: f64,
+    weighted: usize,
+    tokens: TokenCounter,
+}
+
+impl TextScan {
+    fn feed(&mut self, chunk: &str) {
+        self.len += chunk.len();
+        self.tokens.feed(chunk);
+        for c in chunk.chars() {
+            match c {
+                '\n' => self.newlines += 1,
+                '`' => {
+                    self.backtick_run += 1;
+                    self.longest_backtick_run = self.longest_backtick_run.max(self.backtick_run);
+                    continue;
+                }
+                _ if !c.is_ascii() => {
+                    self.weight += char_weight(c);
+                    self.weighted += 1;
+                }
+                _ => {}
+            }
+            self.backtick_run = 0;
+        }
+        if let Some(c) = chunk.chars().next_back() {
+            self.last = Some(c);
+        }
+        self.non_ascii |= !chunk.is_ascii();
+        if !self.non_ascii && !self.jis_escape {
+            // The chunk is ASCII here, so it can be cut at any byte.
+            let boundary = format!("{}{}", self.tail, &chunk[..chunk.len().min(2)]);
+            self.jis_escape =
+                has_jis_escape(boundary.as_bytes()) || has_jis_escape(chunk.as_bytes());
+            self.tail.push_str(&chunk[chunk.len().saturating_sub(2)..]);
+            let excess = self.tail.len().saturating_sub(2);
+            self.tail.drain(..excess);
+        }
+    }
+
+    /// Average `char_weight` of the non-ASCII characters; see `detect_encoding`.
+    fn plausibility(&self) -> f64 {
+        if self.weighted == 0 {
+            0.0
+        } else {
+            self.weight / self.weighted as f64
+        }
+    }
+
+    fn into_streamed(self, encoding: &'static Encoding, bom_len: usize) -> StreamedContent {
+        StreamedContent {
+            encoding,
+            bom_len,
+            len: self.len,
+            lines: self.newlines + usize::from(self.len > 0 && self.last != Some('\n')),
+            ends_with_newline: self.last == Some('\n'),
+            longest_backtick_run: self.longest_backtick_run,
+            tokens: self.tokens.total(),
+        }
+    }
+}
+
+/// Bytes read at a time from streamed files.
+const STREAM_CHUNK: usize = 64 * 1024;
+
+/// Decodes the file at `path` as `encoding`, after skipping `skip` leading bytes, and
+/// passes the text to `sink` a chunk at a time. With `replace`, bytes that cannot be
+/// decoded become U+FFFD; without it, decoding stops there and `Ok(false)` is returned.
+fn decode_chunks(
+    path: &Path,
+    encoding: &'static Encoding,
+    skip: usize,
+    replace: bool,
+    sink: &mut dyn FnMut(&str) -> io::Result<()>,
+) -> io::Result<bool> {
+    let mut file = fs::File::open(path)?;
+    io::copy(&mut (&mut file).take(skip as u64), &mut io::sink())?;
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut buffer = vec![0; STREAM_CHUNK];
+    let mut text = String::with_capacity(
+        decoder
+            .max_utf8_buffer_length(STREAM_CHUNK)
+            .unwrap_or(STREAM_CHUNK * 3),
+    );
+    loop {
+        let read = file.read(&mut buffer)?;
+        let last = read == 0;
+        let mut input = &buffer[..read];
+        loop {
+            text.clear();
+            let (input_empty, consumed) = if replace {
+                let (result, consumed, _) = decoder.decode_to_string(input, &mut text, last);
+                (result == CoderResult::InputEmpty, consumed)
+            } else {
+                match decoder.decode_to_string_without_replacement(input, &mut text, last) {
+                    (DecoderResult::Malformed(..), _) => return Ok(false),
+                    (result, consumed) => (result == DecoderResult::InputEmpty, consumed),
+                }
+            };
+            input = &input[consumed..];
+            sink(&text)?;
+            if input_empty {
+                break;
+            }
+        }
+        if last {
+            return Ok(true);
+        }
+    }
+}
+
+/// Picks the encoding of a file over the stream threshold the way `read_file_contents`
+/// does, decoding it in chunks without keeping the text. Detecting an encoding other than
+/// UTF-8 takes a pass over the file per candidate.
+fn scan_large_file(path: &Path, encodings: &[&'static Encoding]) -> Option<StreamedContent> {
+    let scan = |encoding: &'static Encoding, skip: usize, replace: bool| {
+        let mut scan = TextScan::default();
+        let decoded = decode_chunks(path, encoding, skip, replace, &mut |text| {
+            scan.feed(text);
+            Ok(())
+        });
+        decoded
+            .ok()
+            .filter(|&complete| complete)
+            .map(|_| scan.into_streamed(encoding, skip))
+    };
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(3)
+        .read_to_end(&mut head)
+        .ok()?;
+    if let Some((encoding, bom_len)) = Encoding::for_bom(&head) {
+        return scan(encoding, bom_len, false);
+    }
+    match encodings {
+        [] => {
+            let mut utf8 = TextScan::default();
+            let decoded = decode_chunks(path, UTF_8, 0, false, &mut |text| {
+                utf8.feed(text);
+                Ok(())
+            });
+            if decoded.ok()? {
+                if !utf8.non_ascii && utf8.jis_escape {
+                    return scan(ISO_2022_JP, 0, false);
+                }
+                return Some(utf8.into_streamed(UTF_8, 0));
+            }
+            // The same choice as `detect_encoding`, one pass per candidate.
+            const MIN_LATIN1_SCORE: f64 = 0.3;
+            let mut best: Option<(StreamedContent, f64)> = None;
+            for encoding in [SHIFT_JIS, EUC_JP, WINDOWS_1252] {
+                let mut text = TextScan::default();
+                let decoded = decode_chunks(path, encoding, 0, false, &mut |chunk| {
+                    text.feed(chunk);
+                    Ok(())
+                });
+                if !decoded.ok()? {
+                    continue;
+                }
+                let score = text.plausibility();
+                if encoding == WINDOWS_1252 && score < MIN_LATIN1_SCORE {
+                    continue;
+                }
+                if best.as_ref().map_or(true, |(_, best)| score > *best) {
+                    best = Some((text.into_streamed(encoding, 0), score));
+                }
+            }
+            best.map(|(streamed, _)| streamed)
+        }
+        [encoding] => scan(encoding, 0, true),
+        _ => encodings
+            .iter()
+            .find_map(|&encoding| scan(encoding, 0, false)),
+    }
+}
+
+/// Per-file settings applied while reading contents.
+pub(crate) struct ContentOptions {
+    pub(crate) max_size: u64,
+    /// Limits that replace `max_size` for files with these normalized extensions.
+    pub(crate) max_size_for: HashMap<String, u64>,
+    /// Passed to `looks_binary`.
+    pub(crate) binary_threshold: f64,
+    /// Prefix each line of readable files with its line number.
+    pub(crate) line_numbers: bool,
+    /// Keep only this many lines from the start of each file.
+    pub(crate) head_lines: Option<usize>,
+    /// Keep only this many lines from the end of each file.
+    pub(crate) tail_lines: Option<usize>,
+    /// Encodings tried in order; empty means UTF-8, then Shift_JIS.
+    pub(crate) encodings: Vec<&'static Encoding>,
+    /// Keep only files whose decoded text matches.
+    pub(crate) grep: Option<Regex>,
+    /// Drop files whose decoded text matches any of these patterns.
+    pub(crate) grep_exclude: Option<Regex>,
+    /// Remove comments from files in a language `strip_comments` knows.
+    pub(crate) strip_comments: bool,
+    /// Collapse runs of blank lines with `squeeze_blank_lines`.
+    pub(crate) squeeze_blank: bool,
+    /// Canonical base that header paths are relative to, instead of each file's directory.
+    pub(crate) relative_to: Option<PathBuf>,
+    /// Convert line endings with `normalize_newlines`.
+    pub(crate) normalize_newlines: bool,
+    /// Slim whitespace with `trim_whitespace`, except in `WHITESPACE_SENSITIVE_EXTENSIONS`.
+    pub(crate) trim_whitespace: bool,
+    /// Apply `trim_whitespace` to `WHITESPACE_SENSITIVE_EXTENSIONS` as well.
+    pub(crate) trim_markdown: bool,
+    /// Files larger than this are streamed instead of read in full, when `can_stream`.
+    pub(crate) stream_threshold: Option<u64>,
+    /// Set `FileSection::sha256` from the file's bytes, also for placeholders.
+    pub(crate) hashes: bool,
+    /// Bytes of a binary file shown as a hex dump instead of `[Binary file skipped]`; 0
+    /// shows none.
+    pub(crate) binary_preview: usize,
+    /// Embed images up to this many bytes as a data URI; larger ones get a placeholder.
+    pub(crate) embed_images: Option<u64>,
+    /// Read `.ipynb` files as their cells with `notebook_source`.
+    pub(crate) notebooks: bool,
+    /// List the members of archives, keeping at most this many, instead of reading them.
+    pub(crate) archive_listing: Option<usize>,
+    /// Keep a `DedupeSource` of each read file for `mark_duplicates`.
+    pub(crate) dedupe: bool,
+}
+
+impl ContentOptions {
+    /// `max_size`, or the limit `max_size_for` gives the extension of `file`.
+    fn max_size_of(&self, file: &Path) -> u64 {
+        file.extension()
+            .and_then(|ext| normalize_extension(&ext.to_string_lossy()))
+            .and_then(|ext| self.max_size_for.get(&ext).copied())
+            .unwrap_or(self.max_size)
+    }
+
+    /// Whether files can be streamed, i.e. no option needs their whole text at once.
+    fn can_stream(&self) -> bool {
+        self.grep.is_none()
+            && self.grep_exclude.is_none()
+            && !self.line_numbers
+            && self.head_lines.is_none()
+            && self.tail_lines.is_none()
+            && !self.strip_comments
+            && !self.squeeze_blank
+            && !self.normalize_newlines
+            && !self.trim_whitespace
+    }
+
+    /// Whether `file` is embedded as an image rather than read as text.
+    fn is_embedded_image(&self, file: &Path) -> bool {
+        self.embed_images.is_some() && image_extension_mime(file).is_some()
+    }
+
+    /// `is_binary`, except that nothing is binary when UTF-16 is forced, since its text is
+    /// full of NUL bytes.
+    fn is_binary(&self, file: &Path) -> bool {
+        let utf16 = matches!(self.encodings[..], [encoding] if encoding == UTF_16LE || encoding == UTF_16BE);
+        !utf16 && is_binary(file, self.binary_threshold)
+    }
+}
+
+/// Prefixes every line with its right-aligned number and ` | `, e.g. ` 42 | let x = 5;`.
+///
+/// The width fits the file's last line number. Lines are split on `\n` and `\r\n`, so
+/// CRLF files come out with plain `\n` endings. A missing final newline stays missing.
+pub(crate) fn number_lines(content: &str) -> String {
+    let count = content.lines().count();
+    let width = count.to_string().len();
+    let mut numbered = String::with_capacity(content.len() + count * (width + 3));
+    for (i, line) in content.lines().enumerate() {
+        if line.is_empty() {
+            numbered.push_str(&format!("{:>width$} |\n", i + 1, width = width));
+        } else {
+            numbered.push_str(&format!("{:>width$} | {}\n", i + 1, line, width = width));
+        }
+    }
+    if !content.ends_with('\n') {
+        numbered.pop();
+    }
+    numbered
+}
+
+/// Converts `\r\n` and lone `\r` line endings to `\n`.
+pub(crate) fn normalize_newlines(content: &str) -> String {
+    if !content.contains('\r') {
+        return content.to_string();
+    }
+    content.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Extensions whose trailing whitespace means something, such as Markdown hard line breaks;
+/// `trim_whitespace` leaves them alone unless `trim_markdown` is set.
+pub(crate) const WHITESPACE_SENSITIVE_EXTENSIONS: &[&str] = &[".md", ".markdown"];
+
+/// Removes trailing whitespace from every line and collapses runs of three or more blank
+/// lines into one. Shorter runs are kept, and so are line endings.
+pub(crate) fn trim_whitespace(content: &str) -> String {
+    let mut trimmed = String::with_capacity(content.len());
+    let mut blanks: Vec<&str> = Vec::new();
+    for line in content.split_inclusive('\n') {
+        let ending = if line.ends_with("\r\n") {
+            "\r\n"
+        } else if line.ends_with('\n') {
+            "\n"
+        } else {
+            ""
+        };
+        let text = line[..line.len() - ending.len()].trim_end();
+        if text.is_empty() && !ending.is_empty() {
+            blanks.push(ending);
+            continue;
+        }
+        push_blank_run(&mut trimmed, &blanks);
+        blanks.clear();
+        trimmed.push_str(text);
+        trimmed.push_str(ending);
+    }
+    push_blank_run(&mut trimmed, &blanks);
+    trimmed
+}
+
+fn push_blank_run(out: &mut String, blanks: &[&str]) {
+    let kept = if blanks.len() >= 3 {
+        &blanks[..1]
+    } else {
+        blanks
+    };
+    for ending in kept {
+        out.push_str(ending);
+    }
+}
+
+/// Collapses each run of blank lines into one, like `cat -s`, and drops blank lines at the
+/// start and end of the text.
+///
+/// Lines holding only whitespace count as blank. Kept lines keep their own endings; text
+/// that had trailing blank lines ends with a single newline.
+pub(crate) fn squeeze_blank_lines(content: &str) -> String {
+    let mut squeezed = String::with_capacity(content.len());
+    let mut pending_blank: Option<&str> = None;
+    for line in content.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            if !squeezed.is_empty() && pending_blank.is_none() {
+                pending_blank = Some(if line.ends_with("\r\n") { "\r\n" } else { "\n" });
+            }
+            continue;
+        }
+        if let Some(blank) = pending_blank.take() {
+            squeezed.push_str(blank);
+        }
+        squeezed.push_str(line);
+    }
+    if pending_blank.is_some() && !squeezed.ends_with('\n') {
+        squeezed.push('\n');
+    }
+    squeezed
+}
+
+/// Keeps the first `head` and last `tail` lines, marking where lines were dropped.
+///
+/// Head-only output ends with `[... truncated, N more lines]`, tail-only output starts with
+/// `[... first N lines omitted]`, and with both a `... [N lines omitted] ...` gap marker sits in
+/// the middle. Lines are counted after decoding, so multi-byte characters are never split.
+/// When both limits are 0 (or one is 0 and the other unset) the content is dropped entirely,
+/// leaving only the file header.
+pub(crate) fn truncate_lines(content: &str, head: Option<usize>, tail: Option<usize>) -> String {
+    let (head, tail) = (head.unwrap_or(0), tail.unwrap_or(0));
+    if head == 0 && tail == 0 {
+        return String::new();
+    }
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if head.saturating_add(tail) >= lines.len() {
+        return content.to_string();
+    }
+    let omitted = lines.len() - head - tail;
+    let (count, plural) = (
+        format_thousands(omitted),
+        if omitted == 1 { "" } else { "s" },
+    );
+    let marker = if tail == 0 {
+        format!("[... truncated, {} more line{}]\n", count, plural)
+    } else if head == 0 {
+        format!("[... first {} line{} omitted]\n", count, plural)
+    } else {
+        format!("... [{} line{} omitted] ...\n", count, plural)
+    };
+    let mut kept = lines[..head].concat();
+    kept.push_str(&marker);
+    kept.push_str(&lines[lines.len() - tail..].concat());
+    kept
+}
+
+/// The path shown in headers: `file` relative to `dir`, or with `relative_to` (a canonical
+/// path) relative to that base instead. A file outside the base gets its absolute path.
+fn relative_path(dir: &Path, file: &Path, relative_to: Option<&Path>) -> String {
+    let relative = file.strip_prefix(dir).unwrap_or(file);
+    let Some(base) = relative_to else {
+        return relative.to_string_lossy().into_owned();
+    };
+    // Only the directory is resolved, so a symlinked file keeps its own name.
+    let absolute = match fs::canonicalize(dir) {
+        Ok(dir) => dir.join(relative),
+        Err(_) => return relative.to_string_lossy().into_owned(),
+    };
+    absolute
+        .strip_prefix(base)
+        .unwrap_or(&absolute)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Checks size and binary detection for one collected file without decoding it.
+pub(crate) fn plan_file(
+    dir: &Path,
+    dir_name: String,
+    file: PathBuf,
+    options: &ContentOptions,
+) -> PlannedFile {
+    let size = fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+    let skipped = if size == 0 && is_broken_symlink(&file) {
+        Some(SkipReason::BrokenSymlink)
+    } else if options.archive_listing.is_some() && is_archive(&file) {
+        None
+    } else if options.is_embedded_image(&file) {
+        options
+            .embed_images
+            .filter(|&max| size > max)
+            .map(|_| SkipReason::TooLarge)
+    } else if size > options.max_size_of(&file) {
+        Some(SkipReason::TooLarge)
+    } else if options.is_binary(&file) {
+        Some(SkipReason::Binary)
+    } else {
+        None
+    };
+    PlannedFile {
+        relative_path: relative_path(dir, &file, options.relative_to.as_deref()),
+        path: file,
+        dir_name,
+        size,
+        skipped,
+    }
+}
+
+/// Reads one collected file into its contents section, substituting a placeholder for
+/// broken symlinks and oversized, binary, or undecodable files.
+///
+/// With `grep`, returns `None` unless the decoded text matches; files that would get a
+/// placeholder never match. With `grep_exclude`, returns `None` when the decoded text
+/// matches, whatever `grep` says. With `dedupe`, a file that was read comes with its
+/// `DedupeSource`.
+pub(crate) fn read_file_section(
+    dir: &Path,
+    dir_name: String,
+    file: PathBuf,
+    options: &ContentOptions,
+) -> Option<(FileSection, Option<DedupeSource>)> {
+    let relative_path = relative_path(dir, &file, options.relative_to.as_deref());
+    let size = fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+    let mut streamed = None;
+    // Files read whole are hashed from the bytes read; the others in a pass of their own.
+    let mut sha256 = None;
+    let image = options.is_embedded_image(&file);
+    let notebook = options.notebooks && is_notebook(&file);
+    let archive = options.archive_listing.filter(|_| is_archive(&file));
+    let result = if size == 0 && is_broken_symlink(&file) {
+        Err(SkipReason::BrokenSymlink)
+    } else if let Some(max_entries) = archive {
+        archive_listing(&file, max_entries)
+            .map(|listing| (listing, UTF_8))
+            .ok_or(SkipReason::DecodeFailed)
+    } else if image {
+        if options.embed_images.is_some_and(|max| size > max) {
+            Err(SkipReason::TooLarge)
+        } else {
+            let bytes = fs::read(&file).unwrap_or_default();
+            if options.hashes {
+                sha256 = Some(sha256_hex(&bytes));
+            }
+            Ok((image_data_uri(&file, &bytes), UTF_8))
+        }
+    } else if size > options.max_size_of(&file) {
+        Err(SkipReason::TooLarge)
+    } else if options.is_binary(&file) {
+        Err(SkipReason::Binary)
+    } else if options
+        .stream_threshold
+        .is_some_and(|threshold| size > threshold)
+        && options.can_stream()
+        && !notebook
+    {
+        streamed = scan_large_file(&file, &options.encodings);
+        streamed
+            .as_ref()
+            .map(|streamed| (String::new(), streamed.encoding))
+            .ok_or(SkipReason::DecodeFailed)
+    } else if options.hashes {
+        let bytes = fs::read(&file).unwrap_or_default();
+        sha256 = Some(sha256_hex(&bytes));
+        decode_bytes(&bytes, &options.encodings).ok_or(SkipReason::DecodeFailed)
+    } else {
+        read_file_contents(&file, &options.encodings).ok_or(SkipReason::DecodeFailed)
+    };
+    let result = match result {
+        Ok((content, encoding)) if notebook => notebook_source(&content)
+            .map(|source| (source, encoding))
+            .ok_or(SkipReason::DecodeFailed),
+        result => result,
+    };
+    if options.hashes && sha256.is_none() && !matches!(result, Err(SkipReason::BrokenSymlink)) {
+        sha256 = hash_file(&file);
+    }
+    // An embedded image has no text to search.
+    if let Some(grep) = &options.grep {
+        if image
+            || !result
+                .as_ref()
+                .is_ok_and(|(content, _)| grep.is_match(content))
+        {
+            return None;
+        }
+    }
+    if let Some(grep_exclude) = &options.grep_exclude {
+        if !image
+            && result
+                .as_ref()
+                .is_ok_and(|(content, _)| grep_exclude.is_match(content))
+        {
+            return None;
+        }
+    }
+    let interpreter = result
+        .as_ref()
+        .ok()
+        .filter(|_| file.extension().is_none())
+        .and_then(|_| shebang_interpreter(&file));
+    let mut encoding = None;
+    let mut source = None;
+    let (content, skipped) = match result {
+        Ok((content, _)) if image => {
+            if options.dedupe {
+                source = Some(DedupeSource {
+                    hash: content_hash(&content),
+                    text: None,
+                });
+            }
+            (content, None)
+        }
+        Ok((content, decoded_from)) => {
+            if decoded_from != UTF_8 {
+                encoding = Some(decoded_from.name());
+            }
+            let decoded = options.dedupe.then(|| content.clone());
+            let content = transform_content(&file, content, options);
+            source = decoded.map(|decoded| DedupeSource::new(decoded, &content));
+            (content, None)
+        }
+        Err(SkipReason::Binary) if options.binary_preview > 0 => (
+            binary_preview(&file, size, options.binary_preview),
+            Some(SkipReason::Binary),
+        ),
+        Err(SkipReason::TooLarge) if image => (
+            format!("[image too large: {}]\n", format_size(size)),
+            Some(SkipReason::TooLarge),
+        ),
+        Err(SkipReason::DecodeFailed) if archive.is_some() => (
+            "[Could not read archive]\n".to_string(),
+            Some(SkipReason::DecodeFailed),
+        ),
+        Err(SkipReason::DecodeFailed) if notebook => (
+            "[Could not parse notebook]\n".to_string(),
+            Some(SkipReason::DecodeFailed),
+        ),
+        Err(reason) => (reason.placeholder().to_string(), Some(reason)),
+    };
+    let tokens = streamed
+        .as_ref()
+        .map_or_else(|| estimate_tokens(&content), |streamed| streamed.tokens);
+    let section = FileSection {
+        path: file,
+        relative_path,
+        dir_name,
+        content,
+        skipped,
+        tokens,
+        encoding,
+        size,
+        streamed,
+        last_commit: None,
+        interpreter,
+        sha256,
+        pinned: false,
+    };
+    Some((section, source))
+}
+
+fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// The MIME type `IMAGE_EXTENSIONS` gives the extension of `path`, if it is an image.
+fn image_extension_mime(path: &Path) -> Option<&'static str> {
+    let ext = normalize_extension(&path.extension()?.to_string_lossy())?;
+    IMAGE_EXTENSIONS
+        .iter()
+        .find(|(image_ext, _)| *image_ext == ext)
+        .map(|(_, mime)| *mime)
+}
+
+/// The MIME type of an image from its magic bytes, e.g. `image/png` for a `.jpg` that is
+/// really a PNG, falling back to its extension.
+fn image_mime(path: &Path, bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        "image/jpeg"
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WEBP"[..]) {
+        "image/webp"
+    } else if bytes.starts_with(b"BM") {
+        "image/bmp"
+    } else {
+        image_extension_mime(path).unwrap_or("application/octet-stream")
+    }
+}
+
+/// An image as a single `data:image/png;base64,...` line.
+fn image_data_uri(path: &Path, bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}\n",
+        image_mime(path, bytes),
+        base64(bytes)
+    )
+}
+
+/// Standard base64 with `=` padding.
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | u32::from(byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(group >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// The placeholder of a binary file with `binary_preview`: its size, then a hex dump of its
+/// first `limit` bytes.
+fn binary_preview(path: &Path, size: u64, limit: usize) -> String {
+    let mut head = Vec::new();
+    if let Ok(file) = fs::File::open(path) {
+        let _ = file.take(limit as u64).read_to_end(&mut head);
+    }
+    format!(
+        "[Binary file: {} bytes, first {} shown]\n{}",
+        format_thousands(size as usize),
+        format_thousands(head.len()),
+        hex_dump(&head)
+    )
+}
+
+/// Rows of 16 bytes in the canonical `hexdump -C` layout: the offset, the bytes in hex in
+/// two groups of eight, and the bytes as ASCII between bars, with `.` for anything that is
+/// not printable. A short last row is padded so its ASCII column lines up.
+///
+/// ```text
+/// 00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|
+/// ```
+pub(crate) fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x}  ", row * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(byte) => out.push_str(&format!("{:02x} ", byte)),
+                None => out.push_str("   "),
+            }
+            if i == 7 {
+                out.push(' ');
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+/// SHA-256 of the file at `path`, read a chunk at a time.
+fn hash_file(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = Sha256::default();
+    let mut buffer = vec![0; STREAM_CHUNK];
+    loop {
+        match file.read(&mut buffer).ok()? {
+            0 => return Some(hasher.finish_hex()),
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+}
+
+/// Applies the enabled transforms to the decoded text of `file`, in order: newline
+/// conversion, comment and whitespace removal, then line numbers and truncation.
+fn transform_content(file: &Path, mut content: String, options: &ContentOptions) -> String {
+    if options.normalize_newlines {
+        content = normalize_newlines(&content);
+    }
+    if options.strip_comments {
+        if let Some(stripped) = strip_comments(file, &content) {
+            content = stripped;
+        }
+    }
+    if options.trim_whitespace && (options.trim_markdown || !is_whitespace_sensitive(file)) {
+        content = trim_whitespace(&content);
+    }
+    if options.squeeze_blank {
+        content = squeeze_blank_lines(&content);
+    }
+    // Number first so truncated output keeps each line's original number.
+    if options.line_numbers {
+        content = number_lines(&content);
+    }
+    if options.head_lines.is_some() || options.tail_lines.is_some() {
+        content = truncate_lines(&content, options.head_lines, options.tail_lines);
+    }
+    content
+}
+
+fn is_whitespace_sensitive(file: &Path) -> bool {
+    file.extension()
+        .and_then(|ext| normalize_extension(&ext.to_string_lossy()))
+        .is_some_and(|ext| WHITESPACE_SENSITIVE_EXTENSIONS.contains(&ext.as_str()))
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A file's decoded text before truncation and the other transforms, computed while
+/// reading for `mark_duplicates`, so files that only agree in their first lines are not
+/// taken for copies.
+pub(crate) struct DedupeSource {
+    hash: u64,
+    /// The decoded text, or `None` when the transforms left it as the section's content.
+    text: Option<String>,
+}
+
+impl DedupeSource {
+    pub(crate) fn new(decoded: String, content: &str) -> Self {
+        DedupeSource {
+            hash: content_hash(&decoded),
+            text: (decoded != content).then_some(decoded),
+        }
+    }
+}
+
+/// Replaces each non-empty file whose text equals an earlier file's with a
+/// `[Duplicate of <path> (in <dir>)]` placeholder, so only the first occurrence in output
+/// order is shown in full. Files from different scanned directories are compared too.
+///
+/// `sources[i]` is the `DedupeSource` of `sections[i]`, or `None` for files that were not
+/// read. Files are compared by their decoded text; equal hashes are confirmed by comparing
+/// it.
+pub(crate) fn mark_duplicates(sections: &mut [FileSection], sources: &[Option<DedupeSource>]) {
+    let text = |i: usize| -> &str {
+        match &sources[i] {
+            Some(DedupeSource {
+                text: Some(text), ..
+            }) => text,
+            _ => &sections[i].content,
+        }
+    };
+    let mut first_seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for (i, source) in sources.iter().enumerate() {
+        let Some(source) = source else { continue };
+        if text(i).is_empty() {
+            continue;
+        }
+        let candidates = first_seen.entry(source.hash).or_default();
+        let original = candidates.iter().copied().find(|&j| text(j) == text(i));
+        match original {
+            Some(j) => duplicates.push((i, j)),
+            None => candidates.push(i),
+        }
+    }
+    for (i, j) in duplicates {
+        let content = format!(
+            "[Duplicate of {} (in {})]\n",
+            sections[j].relative_path, sections[j].dir_name
+        );
+        let section = &mut sections[i];
+        section.tokens = estimate_tokens(&content);
+        section.content = content;
+        section.skipped = Some(SkipReason::Duplicate);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestTempDir;
+
+    #[test]
+    fn line_numbers_adapt_width_and_drop_carriage_returns() {
+        assert_eq!(number_lines("a\r\nb\r\n"), "1 | a\n2 | b\n");
+        assert_eq!(number_lines("no newline"), "1 | no newline");
+        assert_eq!(number_lines("a\n\nb"), "1 | a\n2 |\n3 | b");
+        assert_eq!(number_lines(""), "");
+
+        let long: String = (1..=12).map(|i| format!("line {}\n", i)).collect();
+        let numbered = number_lines(&long);
+        assert!(numbered.starts_with(" 1 | line 1\n"));
+        assert!(numbered.ends_with("12 | line 12\n"));
+    }
+
+    #[test]
+    fn hex_dump_aligns_rows_and_replaces_unprintable_bytes() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(
+            hex_dump(png),
+            "00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n"
+        );
+        let short = hex_dump(b"0123456789abcdefA z\x7f\xff");
+        let rows: Vec<&str> = short.lines().collect();
+        assert_eq!(
+            rows[1],
+            "00000010  41 20 7a 7f ff                                    |A z..|"
+        );
+        assert_eq!(rows[0].find('|'), rows[1].find('|'));
+        assert_eq!(hex_dump(b""), "");
+    }
+
+    #[test]
+    fn base64_pads_the_last_group() {
+        assert_eq!(base64(b"x-access-token:abc"), "eC1hY2Nlc3MtdG9rZW46YWJj");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"a"), "YQ==");
+        assert_eq!(base64(b""), "");
+    }
+
+    #[test]
+    fn line_numbers_skip_placeholders() {
+        let temp_dir = TestTempDir::new("line_numbers");
+        let text = temp_dir.write_file("a.txt", "x\n");
+        let binary = temp_dir.write_file("b.txt", "\0\0");
+        let options = ContentOptions {
+            max_size: u64::MAX,
+            max_size_for: HashMap::new(),
+            binary_threshold: DEFAULT_BINARY_THRESHOLD,
+            line_numbers: true,
+            head_lines: None,
+            tail_lines: None,
+            encodings: Vec::new(),
+            grep: None,
+            grep_exclude: None,
+            strip_comments: false,
+            squeeze_blank: false,
+            relative_to: None,
+            normalize_newlines: false,
+            trim_whitespace: false,
+            trim_markdown: false,
+            stream_threshold: None,
+            hashes: false,
+            binary_preview: 0,
+            embed_images: None,
+            notebooks: false,
+            archive_listing: None,
+            dedupe: false,
+        };
+
+        let section = read_file_section(&temp_dir.path, ".".to_string(), text, &options)
+            .unwrap()
+            .0;
+        assert_eq!(section.content, "1 | x\n");
+
+        let section = read_file_section(&temp_dir.path, ".".to_string(), binary.clone(), &options)
+            .unwrap()
+            .0;
+        assert_eq!(section.content, "[Binary file skipped]\n");
+
+        let options = ContentOptions {
+            binary_preview: 64,
+            ..options
+        };
+        let section = read_file_section(&temp_dir.path, ".".to_string(), binary, &options)
+            .unwrap()
+            .0;
+        assert_eq!(
+            section.content,
+            "[Binary file: 2 bytes, first 2 shown]\n\
+             00000000  00 00                                             |..|\n"
+        );
+        assert_eq!(section.skipped, Some(SkipReason::Binary));
+    }
+
+    #[test]
+    fn line_numbers_follow_decoded_text() {
+        let temp_dir = TestTempDir::new("line_numbers_decoded");
+        let utf8 = temp_dir.write_file("utf8.txt", "日本語\nテスト\n");
+        let sjis = temp_dir.path.join("sjis.txt");
+        let (bytes, _, _) = SHIFT_JIS.encode("こんにちは\r\n世界\r\n");
+        fs::write(&sjis, &bytes).unwrap();
+        let options = ContentOptions {
+            max_size: u64::MAX,
+            max_size_for: HashMap::new(),
+            binary_threshold: DEFAULT_BINARY_THRESHOLD,
+            line_numbers: true,
+            head_lines: None,
+            tail_lines: None,
+            encodings: Vec::new(),
+            grep: None,
+            grep_exclude: None,
+            strip_comments: false,
+            squeeze_blank: false,
+            relative_to: None,
+            normalize_newlines: false,
+            trim_whitespace: false,
+            trim_markdown: false,
+            stream_threshold: None,
+            hashes: false,
+            binary_preview: 0,
+            embed_images: None,
+            notebooks: false,
+            archive_listing: None,
+            dedupe: false,
+        };
+
+        let section = read_file_section(&temp_dir.path, ".".to_string(), utf8, &options)
+            .unwrap()
+            .0;
+        assert_eq!(section.content, "1 | 日本語\n2 | テスト\n");
+
+        let section = read_file_section(&temp_dir.path, ".".to_string(), sjis, &options)
+            .unwrap()
+            .0;
+        assert_eq!(section.content, "1 | こんにちは\n2 | 世界\n");
+    }
+
+    #[test]
+    fn squeeze_blank_collapses_runs_and_trims_the_ends() {
+        assert_eq!(
+            squeeze_blank_lines("\n\n  \na\n\n\n\t\nb\n\nc\n\n\n"),
+            "a\n\nb\n\nc\n"
+        );
+        assert_eq!(squeeze_blank_lines("a\r\n\r\n\r\nb\r\n"), "a\r\n\r\nb\r\n");
+        assert_eq!(squeeze_blank_lines("a\nb"), "a\nb");
+        assert_eq!(squeeze_blank_lines("\n\n"), "");
+    }
+
+    #[test]
+    fn trim_whitespace_strips_line_ends_and_collapses_long_blank_runs() {
+        assert_eq!(
+            trim_whitespace("a  \n\t\n\nb\t\r\n \r\n\r\n\r\n\r\nc \n\n\n\n"),
+            "a\n\n\nb\r\n\r\nc\n\n"
+        );
+        assert_eq!(trim_whitespace("x   "), "x");
+        assert!(is_whitespace_sensitive(Path::new("README.MD")));
+        assert!(!is_whitespace_sensitive(Path::new("main.rs")));
+    }
+
+    #[test]
+    fn normalize_newlines_converts_crlf_and_lone_cr() {
+        assert_eq!(normalize_newlines("a\r\nb\rc\n\r\r\n"), "a\nb\nc\n\n\n");
+        assert_eq!(normalize_newlines("plain\n"), "plain\n");
+    }
+
+    #[test]
+    fn head_lines_truncate_with_marker() {
+        let content: String = (1..=1003).map(|i| format!("行 {}\n", i)).collect();
+
+        assert_eq!(
+            truncate_lines(&content, Some(2), None),
+            "行 1\n行 2\n[... truncated, 1,001 more lines]\n"
+        );
+        assert_eq!(truncate_lines("a\nb", Some(2), None), "a\nb");
+        assert_eq!(truncate_lines("a\nb\n", Some(5), None), "a\nb\n");
+        assert_eq!(truncate_lines(&content, Some(0), None), "");
+
+        let numbered = truncate_lines(&number_lines(&content), Some(1), None);
+        assert!(numbered.starts_with("   1 | 行 1\n[... truncated"));
+        assert_eq!(
+            truncate_lines("a\nb\nc\n", Some(2), None),
+            "a\nb\n[... truncated, 1 more line]\n"
+        );
+    }
+
+    #[test]
+    fn tail_lines_keep_the_end_with_banner() {
+        let content = "1\n2\n3\n4\n5";
+
+        assert_eq!(
+            truncate_lines(content, None, Some(2)),
+            "[... first 3 lines omitted]\n4\n5"
+        );
+        assert_eq!(
+            truncate_lines(content, Some(1), Some(1)),
+            "1\n... [3 lines omitted] ...\n5"
+        );
+        assert_eq!(truncate_lines(content, Some(3), Some(2)), content);
+        assert_eq!(
+            truncate_lines(content, Some(2), Some(0)),
+            "1\n2\n[... truncated, 3 more lines]\n"
+        );
+        assert_eq!(
+            truncate_lines(content, None, Some(4)),
+            "[... first 1 line omitted]\n2\n3\n4\n5"
+        );
+        assert_eq!(
+            truncate_lines(content, Some(2), Some(2)),
+            "1\n2\n... [1 line omitted] ...\n4\n5"
+        );
+    }
+
+    #[test]
+    fn binary_detection_uses_control_ratio_and_utf16_boms() {
+        let temp_dir = TestTempDir::new("binary_detection");
+        let source = temp_dir.write_file("main.rs", "fn main() {\n\tprintln!(\"\\x1b[1m\");\n}\n");
+        let png = temp_dir.path.join("logo.png");
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x10").unwrap();
+        let utf16 = temp_dir.path.join("notes.txt");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend("héllo\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&utf16, &bytes).unwrap();
+
+        assert!(!is_binary(&source, DEFAULT_BINARY_THRESHOLD));
+        assert!(is_binary(&png, DEFAULT_BINARY_THRESHOLD));
+        assert!(!is_binary(&utf16, DEFAULT_BINARY_THRESHOLD));
+        assert_eq!(
+            read_file_contents(&utf16, &[]),
+            Some(("héllo\r\n".to_string(), encoding_rs::UTF_16LE))
+        );
+
+        let no_nul: Vec<u8> = (1..32u8).chain(b"abc".iter().copied()).collect();
+        assert!(looks_binary(&no_nul, DEFAULT_BINARY_THRESHOLD));
+        assert!(!looks_binary(&no_nul, 1.0));
+    }
+
+    #[test]
+    fn duplicates_point_at_the_first_copy() {
+        let section = |dir: &str, path: &str, content: &str| FileSection {
+            path: PathBuf::from(dir).join(path),
+            relative_path: path.to_string(),
+            dir_name: dir.to_string(),
+            content: content.to_string(),
+            skipped: None,
+            tokens: estimate_tokens(content),
+            encoding: None,
+            size: content.len() as u64,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        };
+        // `(dir, path, decoded text, content after the transforms)`
+        let files = [
+            ("a", "Cargo.lock", "same\n", "same\n"),
+            ("a", "empty.py", "", ""),
+            ("b", "other.py", "", ""),
+            ("b", "vendor/Cargo.lock", "same\n", "same\n"),
+            ("b", "unique.rs", "fn b() {}\n", "fn b() {}\n"),
+            // Equal once cut to their first lines, but not as read.
+            ("b", "one.txt", "a\nb\nX\n", "a\nb\n"),
+            ("b", "two.txt", "a\nb\nY\n", "a\nb\n"),
+            ("c", "one.txt", "a\nb\nX\n", "a\nb\n"),
+        ];
+        let mut sections: Vec<FileSection> = files
+            .iter()
+            .map(|(dir, path, _, content)| section(dir, path, content))
+            .collect();
+        let sources: Vec<Option<DedupeSource>> = files
+            .iter()
+            .map(|(_, _, decoded, content)| Some(DedupeSource::new(decoded.to_string(), content)))
+            .collect();
+
+        mark_duplicates(&mut sections, &sources);
+
+        assert_eq!(sections[3].content, "[Duplicate of Cargo.lock (in a)]\n");
+        assert_eq!(sections[3].skipped, Some(SkipReason::Duplicate));
+        assert_eq!(sections[7].content, "[Duplicate of one.txt (in b)]\n");
+        assert!(sections
+            .iter()
+            .enumerate()
+            .all(|(i, s)| (i == 3 || i == 7) == s.skipped.is_some()));
+    }
+
+    #[test]
+    fn byte_order_marks_are_left_out_of_the_text() {
+        let temp_dir = TestTempDir::new("bom");
+        let utf8 = temp_dir.path.join("main.rs");
+        fs::write(&utf8, b"\xEF\xBB\xBFfn main() {}\n").unwrap();
+        let utf16 = temp_dir.path.join("notes.txt");
+        fs::write(&utf16, b"\xFF\xFEh\0i\0\n\0").unwrap();
+
+        let (text, encoding) = read_file_contents(&utf8, &[]).unwrap();
+        assert_eq!((text.chars().next(), encoding), (Some('f'), UTF_8));
+        assert_eq!(
+            read_file_contents(&utf8, &[SHIFT_JIS]),
+            Some(("fn main() {}\n".to_string(), UTF_8))
+        );
+        assert_eq!(
+            read_file_contents(&utf16, &[]),
+            Some(("hi\n".to_string(), UTF_16LE))
+        );
+
+        let streamed = scan_large_file(&utf8, &[]).unwrap();
+        let mut text = String::new();
+        decode_chunks(
+            &utf8,
+            streamed.encoding,
+            streamed.bom_len,
+            true,
+            &mut |chunk| {
+                text.push_str(chunk);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(text, "fn main() {}\n");
+        assert_eq!(streamed.len, text.len());
+    }
+
+    #[test]
+    fn encoding_chain_is_tried_in_order() {
+        let temp_dir = TestTempDir::new("encoding_chain");
+        let path = temp_dir.path.join("legacy.c");
+        let (bytes, _, _) = encoding_rs::EUC_JP.encode("// 日本語\n");
+        fs::write(&path, &bytes).unwrap();
+
+        assert_eq!(read_file_contents(&path, &[UTF_8, ISO_2022_JP]), None);
+        assert_eq!(
+            read_file_contents(&path, &[UTF_8, encoding_rs::EUC_JP]),
+            Some(("// 日本語\n".to_string(), encoding_rs::EUC_JP))
+        );
+
+        let options = ContentOptions {
+            max_size: u64::MAX,
+            max_size_for: HashMap::new(),
+            binary_threshold: DEFAULT_BINARY_THRESHOLD,
+            line_numbers: false,
+            head_lines: None,
+            tail_lines: None,
+            encodings: vec![UTF_8, encoding_rs::EUC_JP],
+            grep: None,
+            grep_exclude: None,
+            strip_comments: false,
+            squeeze_blank: false,
+            relative_to: None,
+            normalize_newlines: false,
+            trim_whitespace: false,
+            trim_markdown: false,
+            stream_threshold: None,
+            hashes: false,
+            binary_preview: 0,
+            embed_images: None,
+            notebooks: false,
+            archive_listing: None,
+            dedupe: false,
+        };
+        let section = read_file_section(&temp_dir.path, "t".to_string(), path, &options)
+            .unwrap()
+            .0;
+        assert_eq!(section.encoding, Some("EUC-JP"));
+    }
+
+    #[test]
+    fn a_single_encoding_is_forced_and_decodes_lossily() {
+        let temp_dir = TestTempDir::new("forced_encoding");
+        let cyrillic = temp_dir.path.join("ru.txt");
+        let (bytes, _, _) = encoding_rs::WINDOWS_1251.encode("привет\n");
+        fs::write(&cyrillic, &bytes).unwrap();
+        let broken = temp_dir.path.join("broken.txt");
+        fs::write(&broken, b"ok\xff\n").unwrap();
+        let utf16 = temp_dir.path.join("notes.txt");
+        let bytes: Vec<u8> = "hi\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        fs::write(&utf16, bytes).unwrap();
+
+        assert_eq!(
+            read_file_contents(&cyrillic, &[encoding_rs::WINDOWS_1251]),
+            Some(("привет\n".to_string(), encoding_rs::WINDOWS_1251))
+        );
+        assert_eq!(
+            read_file_contents(&broken, &[UTF_8]),
+            Some(("ok\u{fffd}\n".to_string(), UTF_8))
+        );
+        let options = ContentOptions {
+            max_size: u64::MAX,
+            max_size_for: HashMap::new(),
+            binary_threshold: DEFAULT_BINARY_THRESHOLD,
+            line_numbers: false,
+            head_lines: None,
+            tail_lines: None,
+            encodings: vec![UTF_16LE],
+            grep: None,
+            grep_exclude: None,
+            strip_comments: false,
+            squeeze_blank: false,
+            relative_to: None,
+            normalize_newlines: false,
+            trim_whitespace: false,
+            trim_markdown: false,
+            stream_threshold: None,
+            hashes: false,
+            binary_preview: 0,
+            embed_images: None,
+            notebooks: false,
+            archive_listing: None,
+            dedupe: false,
+        };
+        let section = read_file_section(&temp_dir.path, "t".to_string(), utf16, &options)
+            .unwrap()
+            .0;
+        assert_eq!(section.content, "hi\n");
+
+        assert!(ENCODING_NAMES
+            .iter()
+            .all(|label| check_encoding_label(label).is_ok()));
+        assert!(check_encoding_label("klingon")
+            .unwrap_err()
+            .contains("valid labels: utf-8, utf-16le, utf-16be, shift_jis"));
+    }
+
+    #[test]
+    fn legacy_encodings_are_detected_without_a_chain() {
+        let temp_dir = TestTempDir::new("encoding_detection");
+        let detect = |name: &str, bytes: &[u8]| {
+            let path = temp_dir.path.join(name);
+            fs::write(&path, bytes).unwrap();
+            read_file_contents(&path, &[]).map(|(text, encoding)| (text, encoding.name()))
+        };
+        let text = "// これは日本語のコメントです\n";
+
+        for encoding in [SHIFT_JIS, EUC_JP, ISO_2022_JP] {
+            let (bytes, _, _) = encoding.encode(text);
+            assert_eq!(
+                detect("legacy.c", &bytes),
+                Some((text.to_string(), encoding.name()))
+            );
+        }
+        assert_eq!(
+            detect("naive.txt", b"na\xefve caf\xe9\n"),
+            Some(("naïve café\n".to_string(), "windows-1252"))
+        );
+        assert_eq!(detect("noise.dat", b"\x81\x8d\x8f\x90\x9d\n"), None);
+    }
+}
@@ -0,0 +1,1415 @@
+use crate::config::DEFAULT_EXTENSIONLESS_FILENAMES;
+use crate::tree::{TreeConnectors, TreeDetail};
+use clap::ValueEnum;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use regex_automata::meta::{BuildError, Regex};
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+#[derive(Default)]
+pub(crate) struct FilterRules {
+    pub(crate) allowed: HashSet<String>,
+    pub(crate) ignore_exts: HashSet<String>,
+    pub(crate) ignore_dirs: HashSet<String>,
+    pub(crate) whitelist_filenames: FilenameWhitelist,
+    pub(crate) ignore_files: HashSet<String>,
+    pub(crate) extensionless_allowed: HashSet<String>,
+    pub(crate) gitignore: Option<GitignoreRules>,
+    pub(crate) oreuitignore: Option<OreuitignoreRules>,
+    /// Ignore files named with `--ignore-file-pattern`, such as `.dockerignore`.
+    pub(crate) ignore_file_patterns: Option<IgnoreFileRules>,
+    pub(crate) git_tracked: Option<GitTrackedRules>,
+    /// Paths changed since `--changed-since`, per scanned directory.
+    pub(crate) changed: Option<HashMap<PathBuf, ChangedPaths>>,
+    pub(crate) include_globs: Option<GlobSet>,
+    pub(crate) exclude_globs: Option<ExcludeGlobs>,
+    /// Leaves out files whose relative path matches; whitelisted filenames are kept.
+    pub(crate) exclude_regex: Option<Regex>,
+    /// Deepest level to descend into; 0 keeps only the top-level entries.
+    pub(crate) max_depth: Option<usize>,
+    /// Files smaller than this many bytes are left out entirely.
+    pub(crate) min_size: Option<u64>,
+    /// Files last modified before this time are left out entirely.
+    pub(crate) modified_since: Option<SystemTime>,
+    /// Descend into symlinked directories and collect symlinked files.
+    pub(crate) follow_symlinks: bool,
+    pub(crate) hidden: HiddenFiles,
+    /// Annotations appended to file entries in the tree.
+    pub(crate) tree_details: Vec<TreeDetail>,
+    /// Append the size and count of the files below each directory in the tree.
+    pub(crate) tree_dir_totals: bool,
+    /// How the branches of the tree are drawn.
+    pub(crate) tree_connectors: TreeConnectors,
+    /// Order of the files `collect_files` returns; trees are always alphabetical.
+    pub(crate) order: FileOrder,
+    /// Files left out by path, such as those deselected with `--interactive`.
+    pub(crate) deselected: HashSet<PathBuf>,
+}
+
+/// What to do with files and directories whose name starts with `.`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HiddenFiles {
+    /// Treat them like any other entry
+    Include,
+    /// Leave them out unless the name is whitelisted or a default extensionless filename
+    /// such as `.gitignore`
+    #[default]
+    Exclude,
+}
+
+/// Order of the files collected from each directory, for `--sort`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileOrder {
+    /// By path, alphabetically
+    #[default]
+    Path,
+    /// Smallest first
+    Size,
+    /// Largest first
+    SizeDesc,
+    /// Least recently modified first
+    Mtime,
+    /// Most recently modified first
+    MtimeDesc,
+    /// By extension, then by path; files without an extension come first
+    Extension,
+}
+
+/// Sorts `files` by path, then stably by `order`, so ties stay in path order. Metadata is
+/// read once per file; a file whose metadata cannot be read sorts as empty and oldest.
+pub(crate) fn sort_files(files: &mut [PathBuf], order: FileOrder) {
+    files.sort();
+    let size = |file: &PathBuf| fs::metadata(file).map_or(0, |m| m.len());
+    let mtime = |file: &PathBuf| {
+        fs::metadata(file)
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    };
+    match order {
+        FileOrder::Path => {}
+        FileOrder::Size => files.sort_by_cached_key(size),
+        FileOrder::SizeDesc => files.sort_by_cached_key(|file| Reverse(size(file))),
+        FileOrder::Mtime => files.sort_by_cached_key(mtime),
+        FileOrder::MtimeDesc => files.sort_by_cached_key(|file| Reverse(mtime(file))),
+        FileOrder::Extension => files.sort_by_cached_key(|file| {
+            file.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+        }),
+    }
+}
+
+/// `.gitignore` matchers loaded lazily while traversing.
+///
+/// Matchers are keyed by the absolute directory that contains the `.gitignore`.
+/// A path is checked against the closest `.gitignore` first, walking upwards until
+/// the repository root (or the scanned directory when it is not inside a repository).
+/// The first matcher with an opinion wins, so a nested `!pattern` re-includes a path
+/// that an outer `.gitignore` excluded.
+#[derive(Default)]
+pub(crate) struct GitignoreRules {
+    state: Mutex<GitignoreState>,
+}
+
+#[derive(Default)]
+struct GitignoreState {
+    /// Scanned directory -> (absolute scanned directory, topmost directory to consult)
+    roots: HashMap<PathBuf, (PathBuf, PathBuf)>,
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+    /// Parse problems not yet reported; see `take_warnings`.
+    warnings: Vec<String>,
+}
+
+impl GitignoreRules {
+    pub(crate) fn is_ignored(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let (root_abs, top) = state
+            .roots
+            .entry(root.to_path_buf())
+            .or_insert_with(|| {
+                let root_abs = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+                let top = root_abs
+                    .ancestors()
+                    .find(|dir| dir.join(".git").exists())
+                    .unwrap_or(&root_abs)
+                    .to_path_buf();
+                (root_abs, top)
+            })
+            .clone();
+        let absolute = root_abs.join(path.strip_prefix(root).unwrap_or(path));
+
+        let GitignoreState {
+            matchers, warnings, ..
+        } = &mut *state;
+        let mut current = absolute.parent();
+        while let Some(dir) = current {
+            let matcher = matchers
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| load_ignore_file(dir, ".gitignore", warnings));
+            if let Some(matcher) = matcher {
+                match matcher.matched(&absolute, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+            if dir == top {
+                break;
+            }
+            current = dir.parent();
+        }
+        false
+    }
+
+    /// Returns the `.gitignore` parse problems found since the last call.
+    pub(crate) fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.lock().unwrap().warnings)
+    }
+}
+
+/// Name of the tool-specific ignore file read from each scanned directory.
+pub(crate) const OREUITIGNORE_FILENAME: &str = ".oreuitignore";
+
+/// `.oreuitignore` matchers, one per scanned directory, loaded on first use.
+///
+/// Only the file at the root of each scanned directory is read. Its patterns use gitignore
+/// syntax and are relative to that directory.
+#[derive(Default)]
+pub(crate) struct OreuitignoreRules {
+    state: Mutex<OreuitignoreState>,
+}
+
+#[derive(Default)]
+struct OreuitignoreState {
+    /// Scanned directory -> its `.oreuitignore`, if any.
+    matchers: HashMap<PathBuf, Option<Gitignore>>,
+    /// Parse problems not yet reported; see `take_warnings`.
+    warnings: Vec<String>,
+}
+
+impl OreuitignoreRules {
+    pub(crate) fn is_ignored(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let OreuitignoreState { matchers, warnings } = &mut *state;
+        let matcher = matchers
+            .entry(root.to_path_buf())
+            .or_insert_with(|| load_ignore_file(root, OREUITIGNORE_FILENAME, warnings));
+        matcher
+            .as_ref()
+            .is_some_and(|matcher| matcher.matched(path, is_dir).is_ignore())
+    }
+
+    /// Returns the `.oreuitignore` parse problems found since the last call.
+    pub(crate) fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.lock().unwrap().warnings)
+    }
+}
+
+/// Ignore files with gitignore syntax under other names, such as `.dockerignore` and
+/// `.npmignore`, loaded lazily while traversing.
+///
+/// Every file found inside a scanned directory applies to its own subtree; nothing above
+/// the scanned directory is read. Each name is its own set of rules, checked like
+/// `.gitignore`: the closest file with an opinion wins, so a nested `!pattern` re-includes
+/// a path. A path is left out when the rules of any name exclude it.
+pub(crate) struct IgnoreFileRules {
+    names: Vec<String>,
+    state: Mutex<IgnoreFileState>,
+}
+
+#[derive(Default)]
+struct IgnoreFileState {
+    /// (Directory, index into `names`) -> that directory's ignore file, if any.
+    matchers: HashMap<(PathBuf, usize), Option<Gitignore>>,
+    /// Parse problems not yet reported; see `take_warnings`.
+    warnings: Vec<String>,
+}
+
+impl IgnoreFileRules {
+    pub(crate) fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        IgnoreFileRules {
+            names: names.into_iter().map(Into::into).collect(),
+            state: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn is_ignored(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let IgnoreFileState { matchers, warnings } = &mut *state;
+        self.names.iter().enumerate().any(|(index, name)| {
+            let mut current = path.parent();
+            while let Some(dir) = current.filter(|dir| dir.starts_with(root)) {
+                let matcher = matchers
+                    .entry((dir.to_path_buf(), index))
+                    .or_insert_with(|| load_ignore_file(dir, name, warnings));
+                if let Some(matcher) = matcher {
+                    match matcher.matched(path, is_dir) {
+                        Match::Ignore(_) => return true,
+                        Match::Whitelist(_) => return false,
+                        Match::None => {}
+                    }
+                }
+                current = dir.parent();
+            }
+            false
+        })
+    }
+
+    /// Returns the ignore file parse problems found since the last call.
+    pub(crate) fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.lock().unwrap().warnings)
+    }
+}
+
+/// Files tracked by git, listed once per scanned directory with `git ls-files`.
+///
+/// A scanned directory outside a git repository (or without a usable `git`) is scanned as
+/// usual, with a warning.
+#[derive(Default)]
+pub(crate) struct GitTrackedRules {
+    state: Mutex<GitTrackedState>,
+}
+
+#[derive(Default)]
+struct GitTrackedState {
+    /// Scanned directory -> its tracked files, or `None` when it is not in a repository.
+    listings: HashMap<PathBuf, Option<TrackedFiles>>,
+    /// Directories that fell back to the normal scan, not yet reported; see `take_warnings`.
+    warnings: Vec<String>,
+}
+
+/// Tracked paths relative to the scanned directory.
+struct TrackedFiles {
+    files: HashSet<PathBuf>,
+    /// Every directory with a tracked file below it, so untracked subtrees can be pruned.
+    dirs: HashSet<PathBuf>,
+}
+
+impl GitTrackedRules {
+    /// Whether `path` is left out for not being tracked. Directories count as tracked when
+    /// a tracked file lies below them.
+    pub(crate) fn is_untracked(&self, root: &Path, path: &Path, is_dir: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let GitTrackedState { listings, warnings } = &mut *state;
+        let listing = listings
+            .entry(root.to_path_buf())
+            .or_insert_with(|| list_tracked_files(root, warnings));
+        let Some(tracked) = listing else {
+            return false;
+        };
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        if is_dir {
+            !tracked.dirs.contains(relative)
+        } else {
+            !tracked.files.contains(relative)
+        }
+    }
+
+    /// Returns the directories found since the last call that are not in a git repository.
+    pub(crate) fn take_warnings(&self) -> Vec<String> {
+        std::mem::take(&mut self.state.lock().unwrap().warnings)
+    }
+}
+
+/// Runs `git ls-files` in `root`, whose output is relative to `root`.
+fn list_tracked_files(root: &Path, warnings: &mut Vec<String>) -> Option<TrackedFiles> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["ls-files", "-z"])
+        .output();
+    let stdout = match output {
+        Ok(output) if output.status.success() => output.stdout,
+        Ok(_) => {
+            warnings.push(format!(
+                "{} is not in a git repository; --git-tracked is ignored for it",
+                root.display()
+            ));
+            return None;
+        }
+        Err(err) => {
+            warnings.push(format!(
+                "Failed to run git for {}: {}; --git-tracked is ignored for it",
+                root.display(),
+                err
+            ));
+            return None;
+        }
+    };
+    let mut tracked = TrackedFiles {
+        files: HashSet::new(),
+        dirs: HashSet::new(),
+    };
+    for entry in stdout.split(|&b| b == 0).filter(|entry| !entry.is_empty()) {
+        let file = PathBuf::from(String::from_utf8_lossy(entry).into_owned());
+        for dir in file.ancestors().skip(1) {
+            if !tracked.dirs.insert(dir.to_path_buf()) {
+                break;
+            }
+        }
+        tracked.files.insert(file);
+    }
+    Some(tracked)
+}
+
+/// Paths that differ between a git ref and the working tree, relative to a scanned directory.
+#[derive(Debug, Default)]
+pub(crate) struct ChangedPaths {
+    /// Added, modified, and renamed files (by their new path).
+    files: HashSet<PathBuf>,
+    /// Every directory with a changed file below it.
+    dirs: HashSet<PathBuf>,
+    /// Files that no longer exist, listed in the tree with a `[deleted]` marker.
+    pub(crate) deleted: Vec<PathBuf>,
+}
+
+impl ChangedPaths {
+    /// Runs `git diff` against `reference` in `root`, covering staged and unstaged changes.
+    pub(crate) fn since(root: &Path, reference: &str) -> Result<Self, Box<dyn Error>> {
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .arg("-C")
+                .arg(root)
+                .args(args)
+                .output()
+                .map_err(|e| format!("Failed to run git for --changed-since: {}", e))
+        };
+        let inside = git(&["rev-parse", "--is-inside-work-tree"])?;
+        if !inside.status.success() {
+            return Err(format!(
+                "--changed-since: {} is not in a git repository",
+                root.display()
+            )
+            .into());
+        }
+        let diff = git(&[
+            "diff",
+            "--name-status",
+            "-z",
+            "--find-renames",
+            "--relative",
+            reference,
+            "--",
+        ])?;
+        if !diff.status.success() {
+            let stderr = String::from_utf8_lossy(&diff.stderr);
+            return Err(format!(
+                "--changed-since: cannot diff {} against '{}': {}",
+                root.display(),
+                reference,
+                stderr
+                    .lines()
+                    .next()
+                    .map_or("git diff failed", |line| line.trim_start_matches("fatal: "))
+            )
+            .into());
+        }
+
+        let mut changed = ChangedPaths::default();
+        let mut fields = diff
+            .stdout
+            .split(|&b| b == 0)
+            .map(|field| String::from_utf8_lossy(field).into_owned());
+        while let Some(status) = fields.next() {
+            if status.is_empty() {
+                continue;
+            }
+            // Renames and copies list the old path first, then the new one.
+            if status.starts_with('R') || status.starts_with('C') {
+                fields.next();
+            }
+            let Some(path) = fields.next() else {
+                break;
+            };
+            let path = PathBuf::from(path);
+            if status == "D" {
+                changed.deleted.push(path);
+                continue;
+            }
+            for dir in path.ancestors().skip(1) {
+                if !changed.dirs.insert(dir.to_path_buf()) {
+                    break;
+                }
+            }
+            changed.files.insert(path);
+        }
+        changed.deleted.sort();
+        Ok(changed)
+    }
+
+    fn is_unchanged(&self, relative: &Path, is_dir: bool) -> bool {
+        if is_dir {
+            !self.dirs.contains(relative)
+        } else {
+            !self.files.contains(relative)
+        }
+    }
+}
+
+/// Loads the gitignore-syntax file `file_name` in `dir`, with patterns relative to `dir`.
+fn load_ignore_file(dir: &Path, file_name: &str, warnings: &mut Vec<String>) -> Option<Gitignore> {
+    let path = dir.join(file_name);
+    if !path.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    if let Some(err) = builder.add(&path) {
+        warnings.push(format!("Failed to parse {}: {}", path.display(), err));
+    }
+    builder.build().ok()
+}
+
+pub(crate) fn normalize_extension(value: &str) -> Option<String> {
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        None
+    } else if value.starts_with('.') {
+        Some(value)
+    } else {
+        Some(format!(".{}", value))
+    }
+}
+
+pub(crate) fn collect_normalized_extensions<I, S>(values: I) -> HashSet<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    values
+        .into_iter()
+        .filter_map(|value| normalize_extension(value.as_ref()))
+        .collect()
+}
+
+pub(crate) fn default_extensionless_filenames() -> HashSet<String> {
+    DEFAULT_EXTENSIONLESS_FILENAMES
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Compiles one glob. A pattern without `/` matches a name at any depth, as in `.gitignore`.
+fn compile_glob(pattern: &str, option: &str) -> Result<Glob, Box<dyn Error>> {
+    let anchored = if pattern.contains('/') {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+    let glob = GlobBuilder::new(&anchored)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| format!("Invalid {} pattern '{}': {}", option, pattern, e))?;
+    Ok(glob)
+}
+
+/// Compiles comma-separated glob patterns, returning `None` when no pattern is given.
+pub(crate) fn build_globset(
+    patterns: &str,
+    option: &str,
+) -> Result<Option<GlobSet>, Box<dyn Error>> {
+    let mut builder = GlobSetBuilder::new();
+    let mut count = 0;
+    for pattern in patterns.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        builder.add(compile_glob(pattern, option)?);
+        count += 1;
+    }
+    if count == 0 {
+        return Ok(None);
+    }
+    Ok(Some(builder.build()?))
+}
+
+/// Compiled `--exclude-glob` patterns.
+pub(crate) struct ExcludeGlobs {
+    /// Matches excluded files and directories by relative path.
+    paths: GlobSet,
+    /// Matches directories whose whole subtree is excluded by a trailing `/**`.
+    subtrees: GlobSet,
+}
+
+impl ExcludeGlobs {
+    pub(crate) fn new(patterns: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        let Some(paths) = build_globset(patterns, "--exclude-glob")? else {
+            return Ok(None);
+        };
+        let mut subtrees = GlobSetBuilder::new();
+        for pattern in patterns.split(',').map(str::trim) {
+            if let Some(parent) = pattern.strip_suffix("/**") {
+                if !parent.is_empty() {
+                    subtrees.add(compile_glob(parent, "--exclude-glob")?);
+                }
+            }
+        }
+        Ok(Some(ExcludeGlobs {
+            paths,
+            subtrees: subtrees.build()?,
+        }))
+    }
+
+    pub(crate) fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        self.paths.is_match(relative) || (is_dir && self.subtrees.is_match(relative))
+    }
+}
+
+/// `--whitelist-filenames` entries, matched against file and directory names: plain names
+/// exactly, and entries with glob characters (`Dockerfile*`, `*.mk`) as patterns.
+#[derive(Debug, Default)]
+pub(crate) struct FilenameWhitelist {
+    /// Lowercased with `case_insensitive`.
+    names: HashSet<String>,
+    globs: Option<GlobSet>,
+    case_insensitive: bool,
+}
+
+impl FilenameWhitelist {
+    pub(crate) fn new(
+        entries: &HashSet<String>,
+        case_insensitive: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut names = HashSet::new();
+        let mut globs = GlobSetBuilder::new();
+        let mut has_globs = false;
+        for entry in entries {
+            if entry.contains(['*', '?', '[', '{']) {
+                let glob = GlobBuilder::new(entry)
+                    .case_insensitive(case_insensitive)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| {
+                        format!("Invalid --whitelist-filenames pattern '{}': {}", entry, e)
+                    })?;
+                globs.add(glob);
+                has_globs = true;
+            } else if case_insensitive {
+                names.insert(entry.to_lowercase());
+            } else {
+                names.insert(entry.clone());
+            }
+        }
+        Ok(FilenameWhitelist {
+            names,
+            globs: if has_globs {
+                Some(globs.build()?)
+            } else {
+                None
+            },
+            case_insensitive,
+        })
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        let listed = if self.case_insensitive {
+            self.names.contains(&name.to_lowercase())
+        } else {
+            self.names.contains(name)
+        };
+        listed
+            || self
+                .globs
+                .as_ref()
+                .is_some_and(|globs| globs.is_match(name))
+    }
+}
+
+/// Compiles regular expressions into one matcher, returning `None` when no pattern is given.
+///
+/// Each pattern is kept whole, so commas in `a{1,3}` are not treated as separators.
+pub(crate) fn build_regex(
+    patterns: &[String],
+    option: &str,
+) -> Result<Option<Regex>, Box<dyn Error>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let regex = Regex::new_many(patterns).map_err(|e| {
+        let pattern = e
+            .pattern()
+            .map_or("", |id| patterns[id.as_usize()].as_str());
+        format!(
+            "Invalid {} pattern '{}': {}",
+            option,
+            pattern,
+            regex_error(&e)
+        )
+    })?;
+    Ok(Some(regex))
+}
+
+/// The last line of a syntax error (e.g. `error: unclosed group`) without the `error: `
+/// prefix, instead of the generic `error parsing pattern 0`.
+pub(crate) fn regex_error(error: &BuildError) -> String {
+    match error.syntax_error() {
+        Some(syntax) => {
+            let message = syntax.to_string();
+            let last = message.lines().last().unwrap_or_default();
+            last.strip_prefix("error: ").unwrap_or(last).to_string()
+        }
+        None => error.to_string(),
+    }
+}
+
+/// A relative path with `/` separators on every platform, for `--exclude-regex`.
+fn slash_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Whether an entry is left out for being hidden, i.e. its name starts with `.` and it was
+/// not named explicitly.
+fn is_hidden_excluded(name: &str, filters: &FilterRules) -> bool {
+    filters.hidden == HiddenFiles::Exclude
+        && name.starts_with('.')
+        && !filters.whitelist_filenames.contains(name)
+        && !filters.extensionless_allowed.contains(name)
+}
+
+/// Decides whether a directory below the scanned root is skipped, together with its subtree,
+/// returning the reason or `None` when it is searched.
+pub(crate) fn dir_exclusion(
+    root: &Path,
+    path: &Path,
+    filters: &FilterRules,
+) -> Option<ExclusionReason> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    if is_hidden_excluded(&name, filters) {
+        return Some(ExclusionReason::Hidden);
+    }
+    if filters.ignore_dirs.contains(name.as_ref()) {
+        return Some(ExclusionReason::IgnoredDirectory);
+    }
+    if let Some(git_tracked) = &filters.git_tracked {
+        if git_tracked.is_untracked(root, path, true) {
+            return Some(ExclusionReason::Untracked);
+        }
+    }
+    if let Some(changed) = filters.changed.as_ref().and_then(|c| c.get(root)) {
+        if changed.is_unchanged(path.strip_prefix(root).unwrap_or(path), true) {
+            return Some(ExclusionReason::Unchanged);
+        }
+    }
+    if let Some(gitignore) = &filters.gitignore {
+        if gitignore.is_ignored(root, path, true) {
+            return Some(ExclusionReason::Gitignore);
+        }
+    }
+    if let Some(oreuitignore) = &filters.oreuitignore {
+        if oreuitignore.is_ignored(root, path, true) {
+            return Some(ExclusionReason::Oreuitignore);
+        }
+    }
+    if let Some(ignore_files) = &filters.ignore_file_patterns {
+        if ignore_files.is_ignored(root, path, true) {
+            return Some(ExclusionReason::IgnoreFile);
+        }
+    }
+    if let Some(exclude_globs) = &filters.exclude_globs {
+        if exclude_globs.is_excluded(path.strip_prefix(root).unwrap_or(path), true) {
+            return Some(ExclusionReason::ExcludeGlob);
+        }
+    }
+    None
+}
+
+/// Decides whether a directory passes the rules checked by `dir_exclusion`.
+pub(crate) fn is_dir_excluded(root: &Path, path: &Path, filters: &FilterRules) -> bool {
+    dir_exclusion(root, path, filters).is_some()
+}
+
+/// Why a file was left out of both the tree and the contents section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ExclusionReason {
+    /// Name starts with `.`, with `--hidden exclude`.
+    Hidden,
+    /// A directory listed in `--ignore-dirs`. Only reported for the directory itself; the
+    /// files below it are never searched.
+    IgnoredDirectory,
+    /// Not listed by `git ls-files`, with `--git-tracked`.
+    Untracked,
+    /// Not changed since the `--changed-since` ref.
+    Unchanged,
+    /// Matched by a `.gitignore` with `--respect-gitignore`.
+    Gitignore,
+    /// Matched by the scanned directory's `.oreuitignore`.
+    Oreuitignore,
+    /// Matched by an ignore file named with `--ignore-file-pattern`.
+    IgnoreFile,
+    /// Matched an `--exclude-glob` pattern.
+    ExcludeGlob,
+    /// Smaller than `--min-size`.
+    BelowMinSize,
+    /// Last modified before `--since`.
+    ModifiedBefore,
+    /// Relative path matched an `--exclude-regex` pattern.
+    ExcludeRegex,
+    /// Listed in `--ignore-files`.
+    IgnoredFilename,
+    /// Has an extension from `--ignore-extensions`.
+    IgnoredExtension,
+    /// Not matched by the extension allowlist or `--include-glob`.
+    NotAllowed,
+    /// Deselected with `--interactive`.
+    Deselected,
+}
+
+impl ExclusionReason {
+    /// Short description used in reports.
+    pub fn label(self) -> &'static str {
+        match self {
+            ExclusionReason::Hidden => "hidden",
+            ExclusionReason::IgnoredDirectory => "ignored directory",
+            ExclusionReason::Untracked => "not tracked by git",
+            ExclusionReason::Unchanged => "unchanged since --changed-since",
+            ExclusionReason::Gitignore => "ignored by .gitignore",
+            ExclusionReason::Oreuitignore => "ignored by .oreuitignore",
+            ExclusionReason::IgnoreFile => "ignored by --ignore-file-pattern",
+            ExclusionReason::ExcludeGlob => "matched --exclude-glob",
+            ExclusionReason::BelowMinSize => "smaller than --min-size",
+            ExclusionReason::ModifiedBefore => "modified before --since",
+            ExclusionReason::ExcludeRegex => "matched --exclude-regex",
+            ExclusionReason::IgnoredFilename => "ignored filename",
+            ExclusionReason::IgnoredExtension => "ignored extension",
+            ExclusionReason::NotAllowed => "extension not allowed",
+            ExclusionReason::Deselected => "deselected",
+        }
+    }
+
+    /// Short kebab-case name, e.g. `ignored-ext`, used in the `--report` file.
+    pub fn name(self) -> &'static str {
+        match self {
+            ExclusionReason::Hidden => "hidden",
+            ExclusionReason::IgnoredDirectory => "ignored-dir",
+            ExclusionReason::Untracked => "untracked",
+            ExclusionReason::Unchanged => "unchanged",
+            ExclusionReason::Gitignore => "gitignore",
+            ExclusionReason::Oreuitignore => "oreuitignore",
+            ExclusionReason::IgnoreFile => "ignore-file",
+            ExclusionReason::ExcludeGlob => "exclude-glob",
+            ExclusionReason::BelowMinSize => "min-size",
+            ExclusionReason::ModifiedBefore => "modified-before",
+            ExclusionReason::ExcludeRegex => "exclude-regex",
+            ExclusionReason::IgnoredFilename => "ignored-name",
+            ExclusionReason::IgnoredExtension => "ignored-ext",
+            ExclusionReason::NotAllowed => "not-allowed",
+            ExclusionReason::Deselected => "deselected",
+        }
+    }
+}
+
+/// Number of files left out, per reason.
+pub type ExclusionCounts = BTreeMap<ExclusionReason, usize>;
+
+/// Applies the ignore-file, glob, size, filename, and extension rules to a file, returning
+/// the reason it is left out or `None` when it is selected.
+///
+/// `root` is the scanned directory; globs and `--exclude-regex` are matched against the path
+/// relative to it.
+pub(crate) fn file_exclusion(
+    root: &Path,
+    path: &Path,
+    filters: &FilterRules,
+) -> Option<ExclusionReason> {
+    if filters.deselected.contains(path) {
+        return Some(ExclusionReason::Deselected);
+    }
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    if is_hidden_excluded(&file_name, filters) {
+        return Some(ExclusionReason::Hidden);
+    }
+    if let Some(git_tracked) = &filters.git_tracked {
+        if git_tracked.is_untracked(root, path, false) {
+            return Some(ExclusionReason::Untracked);
+        }
+    }
+    if let Some(changed) = filters.changed.as_ref().and_then(|c| c.get(root)) {
+        if changed.is_unchanged(path.strip_prefix(root).unwrap_or(path), false) {
+            return Some(ExclusionReason::Unchanged);
+        }
+    }
+    if let Some(gitignore) = &filters.gitignore {
+        if gitignore.is_ignored(root, path, false) {
+            return Some(ExclusionReason::Gitignore);
+        }
+    }
+    if let Some(oreuitignore) = &filters.oreuitignore {
+        if oreuitignore.is_ignored(root, path, false) {
+            return Some(ExclusionReason::Oreuitignore);
+        }
+    }
+    if let Some(ignore_files) = &filters.ignore_file_patterns {
+        if ignore_files.is_ignored(root, path, false) {
+            return Some(ExclusionReason::IgnoreFile);
+        }
+    }
+    if let Some(exclude_globs) = &filters.exclude_globs {
+        if exclude_globs.is_excluded(path.strip_prefix(root).unwrap_or(path), false) {
+            return Some(ExclusionReason::ExcludeGlob);
+        }
+    }
+    if let Some(min_size) = filters.min_size {
+        if fs::metadata(path).map(|m| m.len()).unwrap_or(0) < min_size {
+            return Some(ExclusionReason::BelowMinSize);
+        }
+    }
+    if let Some(since) = filters.modified_since {
+        let modified = fs::metadata(path).and_then(|m| m.modified());
+        if modified.map_or(true, |time| time < since) {
+            return Some(ExclusionReason::ModifiedBefore);
+        }
+    }
+    if filters.whitelist_filenames.contains(file_name.as_ref()) {
+        return None;
+    }
+    if let Some(exclude_regex) = &filters.exclude_regex {
+        if exclude_regex.is_match(slash_path(path.strip_prefix(root).unwrap_or(path)).as_str()) {
+            return Some(ExclusionReason::ExcludeRegex);
+        }
+    }
+    if filters.ignore_files.contains(file_name.as_ref()) {
+        return Some(ExclusionReason::IgnoredFilename);
+    }
+    let ext_formatted = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => {
+            let Some(ext_formatted) = normalize_extension(ext) else {
+                return Some(ExclusionReason::NotAllowed);
+            };
+            if filters.ignore_exts.contains(&ext_formatted) {
+                return Some(ExclusionReason::IgnoredExtension);
+            }
+            Some(ext_formatted)
+        }
+        None => None,
+    };
+    let allowed = if let Some(include_globs) = &filters.include_globs {
+        include_globs.is_match(path.strip_prefix(root).unwrap_or(path))
+    } else if filters.allowed.is_empty() {
+        true
+    } else {
+        match ext_formatted {
+            Some(ext_formatted) => filters.allowed.contains(&ext_formatted),
+            // Scripts without an extension are recognized by their `#!` line instead.
+            None => {
+                filters.extensionless_allowed.contains(file_name.as_ref())
+                    || shebang_interpreter(path).is_some()
+            }
+        }
+    };
+    if allowed {
+        None
+    } else {
+        Some(ExclusionReason::NotAllowed)
+    }
+}
+
+/// The interpreter named on a `#!` line at the start of `path`: `bash` for `#!/bin/bash`,
+/// `python3` for `#!/usr/bin/env python3`. `None` without such a line or when the file
+/// cannot be read; only the first bytes are read.
+pub(crate) fn shebang_interpreter(path: &Path) -> Option<String> {
+    let mut head = [0; 256];
+    let read = fs::File::open(path)
+        .and_then(|mut file| file.read(&mut head))
+        .ok()?;
+    let first_line = head[..read].split(|&b| b == b'\n').next()?;
+    let first_line = String::from_utf8_lossy(first_line);
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skips options such as `-S` and variable assignments.
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    (!program.is_empty()).then(|| program.to_string())
+}
+
+/// Decides whether a file passes the rules checked by `file_exclusion`.
+pub(crate) fn is_file_selected(root: &Path, path: &Path, filters: &FilterRules) -> bool {
+    file_exclusion(root, path, filters).is_none()
+}
+
+/// Whether `path` is a symbolic link whose target does not exist.
+pub(crate) fn is_broken_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) && !path.exists()
+}
+
+/// Recursively searches the specified directory and lists files that
+/// - Are not hidden, unless `--hidden include` is set or the name is whitelisted
+/// - Match allowed extensions (or `--include-glob` patterns) OR are whitelisted filenames
+/// - Do not have ignored extensions
+/// - Are not ignored filenames
+/// - Are not ignored by `.gitignore` when `--respect-gitignore` is set
+/// - Do not match `--exclude-glob` patterns
+///
+/// Files left out are counted per reason in `excluded`. Files within ignored directories
+/// are not searched, so they are not counted. These are the files of `scan_directory`,
+/// whose walk the tree is built from too.
+#[cfg(test)]
+pub(crate) fn collect_files(
+    directory: &Path,
+    filters: &FilterRules,
+    excluded: &mut ExclusionCounts,
+) -> Vec<PathBuf> {
+    crate::tree::scan_directory(directory, filters, &mut |_, reason, is_dir| {
+        if !is_dir {
+            *excluded.entry(reason).or_insert(0) += 1;
+        }
+    })
+    .files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_ALLOWED_EXTENSIONS;
+    use crate::test_support::TestTempDir;
+    use crate::tree::build_tree;
+
+    fn whitelist(names: &[&str]) -> FilenameWhitelist {
+        let names = names.iter().map(|name| name.to_string()).collect();
+        FilenameWhitelist::new(&names, false).unwrap()
+    }
+
+    #[test]
+    fn extensions_are_normalized() {
+        assert_eq!(normalize_extension(".rs"), Some(".rs".to_string()));
+        assert_eq!(normalize_extension("rs"), Some(".rs".to_string()));
+        assert_eq!(normalize_extension(" RS "), Some(".rs".to_string()));
+
+        let normalized = collect_normalized_extensions([".rs", "rs", " RS ", ".PY", " py "]);
+
+        assert!(normalized.contains(".rs"));
+        assert!(normalized.contains(".py"));
+        assert_eq!(normalized.len(), 2);
+    }
+
+    #[test]
+    fn whitelist_filename_overrides_blacklist_file() {
+        let temp_dir = TestTempDir::new("whitelist_overrides_blacklist");
+        let target_file = temp_dir.write_file("config.toml", "name = 'oreuit'\n");
+
+        let filters = FilterRules {
+            whitelist_filenames: whitelist(&["config.toml"]),
+            ignore_files: HashSet::from(["config.toml".to_string()]),
+            ..Default::default()
+        };
+
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+
+        assert_eq!(files, vec![target_file]);
+    }
+
+    #[test]
+    fn nested_gitignore_negation_reincludes_file() {
+        let temp_dir = TestTempDir::new("nested_gitignore");
+        temp_dir.write_file(".gitignore", "*.log\nscratch/\n");
+        temp_dir.write_file("keep/.gitignore", "!important.log\n");
+        temp_dir.write_file("debug.log", "noise\n");
+        temp_dir.write_file("scratch/notes.txt", "draft\n");
+        let kept_log = temp_dir.write_file("keep/important.log", "keep me\n");
+        temp_dir.write_file("keep/other.log", "noise\n");
+        let main_rs = temp_dir.write_file("src/main.rs", "fn main() {}\n");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string(), ".log".to_string()]),
+            gitignore: Some(GitignoreRules::default()),
+            ..Default::default()
+        };
+
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files, vec![kept_log, main_rs]);
+
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(tree.contains("important.log"));
+        assert!(!tree.contains("other.log"));
+        assert!(!tree.contains("debug.log"));
+        assert!(!tree.contains("scratch"));
+    }
+
+    #[test]
+    fn include_globs_replace_extension_allowlist() {
+        let temp_dir = TestTempDir::new("include_globs");
+        let nested_rs = temp_dir.write_file("src/a/b.rs", "fn b() {}\n");
+        let migration = temp_dir.write_file("migrations/001.sql", "SELECT 1;\n");
+        let dockerfile = temp_dir.write_file("Dockerfile", "FROM scratch\n");
+        temp_dir.write_file("migrations/nested/002.sql", "SELECT 2;\n");
+        temp_dir.write_file("README.md", "# readme\n");
+
+        let filters = FilterRules {
+            allowed: collect_normalized_extensions(DEFAULT_ALLOWED_EXTENSIONS),
+            whitelist_filenames: whitelist(&["Dockerfile"]),
+            include_globs: build_globset("src/**/*.rs, migrations/*.sql", "--include-glob")
+                .unwrap(),
+            ..Default::default()
+        };
+
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files, vec![dockerfile, migration, nested_rs]);
+
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(tree.contains("001.sql"));
+        assert!(!tree.contains("002.sql"));
+        assert!(!tree.contains("README.md"));
+    }
+
+    #[test]
+    fn whitelist_globs_and_case_insensitivity_override_ignored_extensions() {
+        let temp_dir = TestTempDir::new("whitelist_globs");
+        let dev = temp_dir.write_file("Dockerfile.dev", "FROM scratch\n");
+        let rules = temp_dir.write_file("build/rules.mk", "all:\n");
+        let lower = temp_dir.write_file("dockerfile", "FROM scratch\n");
+        temp_dir.write_file("notes.txt", "notes\n");
+
+        let entries = HashSet::from(["Dockerfile*".to_string(), "*.mk".to_string()]);
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            ignore_exts: HashSet::from([".dev".to_string(), ".mk".to_string()]),
+            whitelist_filenames: FilenameWhitelist::new(&entries, false).unwrap(),
+            ..Default::default()
+        };
+
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files, vec![dev.clone(), rules.clone()]);
+        assert!(build_tree(&temp_dir.path, &filters).contains("rules.mk"));
+
+        filters.whitelist_filenames = FilenameWhitelist::new(&entries, true).unwrap();
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files, vec![dev, rules, lower]);
+
+        let exact = HashSet::from(["DOCKERFILE".to_string()]);
+        assert!(FilenameWhitelist::new(&exact, true)
+            .unwrap()
+            .contains("Dockerfile"));
+        assert!(!whitelist(&["DOCKERFILE"]).contains("Dockerfile"));
+        let invalid = HashSet::from(["Docker[".to_string()]);
+        assert!(FilenameWhitelist::new(&invalid, false)
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid --whitelist-filenames pattern 'Docker['"));
+    }
+
+    #[test]
+    fn invalid_glob_is_reported() {
+        let err = build_globset("src/[", "--include-glob").unwrap_err();
+
+        assert!(err
+            .to_string()
+            .contains("Invalid --include-glob pattern 'src/['"));
+        assert!(build_globset(" , ", "--include-glob").unwrap().is_none());
+    }
+
+    #[test]
+    fn exclude_globs_prune_directories_and_beat_whitelist() {
+        let temp_dir = TestTempDir::new("exclude_globs");
+        let kept = temp_dir.write_file("src/main.ts", "main();\n");
+        temp_dir.write_file("src/api.generated.ts", "generated();\n");
+        temp_dir.write_file("src/vendor/lib.ts", "vendor();\n");
+        temp_dir.write_file("tests/fixtures/case/input.ts", "fixture();\n");
+        temp_dir.write_file("tests/fixtures/Dockerfile", "FROM scratch\n");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".ts".to_string()]),
+            whitelist_filenames: whitelist(&["Dockerfile"]),
+            include_globs: build_globset("**/*.ts", "--include-glob").unwrap(),
+            exclude_globs: ExcludeGlobs::new("**/fixtures/**, *.generated.ts, src/vendor/*")
+                .unwrap(),
+            ..Default::default()
+        };
+
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files, vec![kept]);
+
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(!tree.contains("fixtures"));
+        assert!(!tree.contains("generated"));
+        assert!(tree.contains("vendor"));
+        assert!(!tree.contains("lib.ts"));
+    }
+
+    #[test]
+    fn exclude_regex_matches_relative_paths_but_not_whitelisted_names() {
+        let temp_dir = TestTempDir::new("exclude_regex");
+        let kept = temp_dir.write_file("src/main.rs", "fn main() {}\n");
+        temp_dir.write_file("src/generated/api.rs", "fn api() {}\n");
+        temp_dir.write_file("src/schema_generated.rs", "fn schema() {}\n");
+        let whitelisted = temp_dir.write_file("src/generated/Makefile", "all:\n");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            whitelist_filenames: whitelist(&["Makefile"]),
+            exclude_regex: build_regex(
+                &[
+                    "^src/generated/".to_string(),
+                    "_generated\\.rs$".to_string(),
+                ],
+                "--exclude-regex",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let mut excluded = ExclusionCounts::new();
+        let files = collect_files(&temp_dir.path, &filters, &mut excluded);
+        assert_eq!(files, vec![whitelisted, kept]);
+        assert_eq!(excluded.get(&ExclusionReason::ExcludeRegex), Some(&2));
+
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(
+            tree.ends_with("\n└── src\n    ├── generated\n    │   └── Makefile\n    └── main.rs")
+        );
+
+        let err = build_regex(&["ok".to_string(), "(".to_string()], "--exclude-regex")
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("Invalid --exclude-regex pattern '('"));
+        assert!(build_regex(&[], "--exclude-regex").unwrap().is_none());
+    }
+
+    #[test]
+    fn sort_orders_files_but_not_the_tree() {
+        let temp_dir = TestTempDir::new("sort_order");
+        let big = temp_dir.write_file("a/big.rs", &"x".repeat(300));
+        let small = temp_dir.write_file("b.txt", "x");
+        let medium = temp_dir.write_file("c.rs", &"x".repeat(20));
+        let tie = temp_dir.write_file("d.txt", "y");
+        let collect = |order| {
+            let filters = FilterRules {
+                order,
+                ..Default::default()
+            };
+            collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new())
+        };
+
+        assert_eq!(
+            collect(FileOrder::Path),
+            vec![big.clone(), small.clone(), medium.clone(), tie.clone()]
+        );
+        assert_eq!(
+            collect(FileOrder::Size),
+            vec![small.clone(), tie.clone(), medium.clone(), big.clone()]
+        );
+        assert_eq!(
+            collect(FileOrder::SizeDesc),
+            vec![big.clone(), medium.clone(), small.clone(), tie.clone()]
+        );
+        assert_eq!(collect(FileOrder::Extension), vec![big, medium, small, tie]);
+
+        let filters = FilterRules {
+            order: FileOrder::SizeDesc,
+            ..Default::default()
+        };
+        assert!(build_tree(&temp_dir.path, &filters)
+            .ends_with("\n├── a\n│   └── big.rs\n├── b.txt\n├── c.rs\n└── d.txt"));
+    }
+
+    #[test]
+    fn min_size_drops_small_files_from_tree_and_contents() {
+        let temp_dir = TestTempDir::new("min_size");
+        temp_dir.write_file("pkg/__init__.py", "");
+        temp_dir.write_file(".keep", "");
+        let module = temp_dir.write_file("pkg/module.py", "x = 1\n");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".py".to_string()]),
+            whitelist_filenames: whitelist(&[".keep"]),
+            min_size: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new()),
+            vec![module]
+        );
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(!tree.contains("__init__.py"));
+        assert!(!tree.contains(".keep"));
+    }
+
+    #[test]
+    fn oreuitignore_applies_to_its_scanned_directory() {
+        let temp_dir = TestTempDir::new("oreuitignore");
+        temp_dir.write_file(".oreuitignore", "docs/\n*.md\n!README.md\n");
+        temp_dir.write_file("docs/guide.rs", "fn guide() {}\n");
+        temp_dir.write_file("CHANGELOG.md", "changes\n");
+        let readme = temp_dir.write_file("README.md", "readme\n");
+        let lib = temp_dir.write_file("src/lib.rs", "pub fn f() {}\n");
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string(), ".md".to_string()]),
+            oreuitignore: Some(OreuitignoreRules::default()),
+            ..Default::default()
+        };
+
+        let mut excluded = ExclusionCounts::new();
+        let files = collect_files(&temp_dir.path, &filters, &mut excluded);
+        assert_eq!(files, vec![readme, lib.clone()]);
+        assert_eq!(excluded.get(&ExclusionReason::Oreuitignore), Some(&1));
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(!tree.contains("docs"));
+        assert!(!tree.contains("CHANGELOG.md"));
+
+        // Scanning a subdirectory does not pick up the parent's file.
+        assert_eq!(
+            collect_files(
+                &temp_dir.path.join("src"),
+                &filters,
+                &mut ExclusionCounts::new()
+            ),
+            vec![lib]
+        );
+
+        filters.oreuitignore = None;
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files.len(), 4);
+    }
+
+    #[test]
+    fn ignore_file_patterns_apply_each_name_to_its_subtree() {
+        let temp_dir = TestTempDir::new("ignore_file_patterns");
+        temp_dir.write_file(".dockerignore", "*.log\nbuild/\n");
+        temp_dir.write_file("web/.dockerignore", "!keep.log\n");
+        temp_dir.write_file("web/.npmignore", "*.md\n");
+        let app = temp_dir.write_file("app.rs", "fn app() {}\n");
+        temp_dir.write_file("debug.log", "log\n");
+        temp_dir.write_file("build/out.rs", "fn out() {}\n");
+        let notes = temp_dir.write_file("notes.md", "notes\n");
+        let index = temp_dir.write_file("web/index.rs", "fn index() {}\n");
+        let keep = temp_dir.write_file("web/keep.log", "keep\n");
+        temp_dir.write_file("web/README.md", "readme\n");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string(), ".md".to_string(), ".log".to_string()]),
+            ignore_file_patterns: Some(IgnoreFileRules::new([".dockerignore", ".npmignore"])),
+            ..Default::default()
+        };
+        let mut excluded = ExclusionCounts::new();
+        let files = collect_files(&temp_dir.path, &filters, &mut excluded);
+        assert_eq!(files, vec![app, notes, index, keep]);
+        assert_eq!(excluded.get(&ExclusionReason::IgnoreFile), Some(&2));
+        assert!(!build_tree(&temp_dir.path, &filters).contains("build"));
+    }
+
+    #[test]
+    fn extensionless_scripts_are_kept_by_their_shebang() {
+        let temp_dir = TestTempDir::new("shebang");
+        let deploy = temp_dir.write_file("bin/deploy", "#!/bin/bash\nset -e\n");
+        let serve = temp_dir.write_file("bin/serve", "#!/usr/bin/env -S python3 -u\n");
+        temp_dir.write_file("bin/data", "not a script\n");
+        temp_dir.write_file("bin/run.sh", "#!/bin/sh\n");
+        let lib = temp_dir.write_file("lib.rs", "pub fn f() {}\n");
+
+        assert_eq!(shebang_interpreter(&deploy).as_deref(), Some("bash"));
+        assert_eq!(shebang_interpreter(&serve).as_deref(), Some("python3"));
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            extensionless_allowed: default_extensionless_filenames(),
+            ..Default::default()
+        };
+        let mut excluded = ExclusionCounts::new();
+        let files = collect_files(&temp_dir.path, &filters, &mut excluded);
+        assert_eq!(files, vec![deploy, serve, lib]);
+        assert_eq!(excluded.get(&ExclusionReason::NotAllowed), Some(&2));
+    }
+
+    #[test]
+    fn hidden_entries_need_the_flag_or_a_whitelisted_name() {
+        let temp_dir = TestTempDir::new("hidden");
+        temp_dir.write_file(".envrc", "export A=1\n");
+        temp_dir.write_file(".cache/data.rs", "fn data() {}\n");
+        let workflow = temp_dir.write_file(".github/workflow.rs", "fn ci() {}\n");
+        let gitignore = temp_dir.write_file(".gitignore", "target\n");
+        let lib = temp_dir.write_file("lib.rs", "pub fn f() {}\n");
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            extensionless_allowed: default_extensionless_filenames(),
+            ..Default::default()
+        };
+        let mut excluded = ExclusionCounts::new();
+        let files = collect_files(&temp_dir.path, &filters, &mut excluded);
+        assert_eq!(files, vec![gitignore.clone(), lib.clone()]);
+        assert_eq!(excluded.get(&ExclusionReason::Hidden), Some(&1));
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(!tree.contains(".github"));
+        assert!(!tree.contains(".envrc"));
+
+        filters.whitelist_filenames = whitelist(&[".github"]);
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(
+            files,
+            vec![workflow.clone(), gitignore.clone(), lib.clone()]
+        );
+        assert!(build_tree(&temp_dir.path, &filters).contains(".github"));
+
+        filters.hidden = HiddenFiles::Include;
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files.len(), 4);
+        assert!(build_tree(&temp_dir.path, &filters).contains(".cache"));
+    }
+
+    #[test]
+    fn git_tracked_keeps_files_from_git_ls_files() {
+        let temp_dir = TestTempDir::new("git_tracked");
+        let lib = temp_dir.write_file("repo/src/lib.rs", "pub fn f() {}\n");
+        temp_dir.write_file("repo/src/notes.txt", "tracked but not allowed\n");
+        temp_dir.write_file("repo/src/scratch.rs", "fn scratch() {}\n");
+        temp_dir.write_file("repo/tmp/try.rs", "fn try_it() {}\n");
+        temp_dir.write_file("repo/Makefile", "all:\n");
+        let plain = temp_dir.write_file("plain/main.rs", "fn main() {}\n");
+        let repo = temp_dir.path.join("repo");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["add", "src/lib.rs", "src/notes.txt"]);
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            whitelist_filenames: whitelist(&["Makefile"]),
+            git_tracked: Some(GitTrackedRules::default()),
+            ..Default::default()
+        };
+        let mut excluded = ExclusionCounts::new();
+        assert_eq!(collect_files(&repo, &filters, &mut excluded), vec![lib]);
+        assert_eq!(excluded.get(&ExclusionReason::Untracked), Some(&2));
+        assert_eq!(build_tree(&repo, &filters), "repo\n└── src\n    └── lib.rs");
+
+        let plain_dir = temp_dir.path.join("plain");
+        assert_eq!(
+            collect_files(&plain_dir, &filters, &mut ExclusionCounts::new()),
+            vec![plain]
+        );
+        let warnings = filters.git_tracked.as_ref().unwrap().take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("is not in a git repository"));
+    }
+}
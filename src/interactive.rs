@@ -0,0 +1,86 @@
+use oreuit::{format_size, PlannedFile};
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+/// Shows the files of a dry run as a checkbox list on stderr and reads toggles and `/TEXT`
+/// filters from stdin until an empty line, returning the paths that were deselected.
+pub(crate) fn pick_files(files: &[PlannedFile]) -> io::Result<HashSet<PathBuf>> {
+    let mut selected = vec![true; files.len()];
+    let mut filter = String::new();
+    let mut input = io::stdin().lock();
+    let mut err = io::stderr().lock();
+    loop {
+        let shown: Vec<usize> = (0..files.len())
+            .filter(|&index| fuzzy_match(&filter, &files[index].relative_path))
+            .collect();
+        for &index in &shown {
+            let file = &files[index];
+            writeln!(
+                err,
+                "{:>4} [{}] {} (in {})  {}",
+                index + 1,
+                if selected[index] { 'x' } else { ' ' },
+                file.relative_path,
+                file.dir_name,
+                format_size(file.size)
+            )?;
+        }
+        if !filter.is_empty() {
+            writeln!(
+                err,
+                "Showing {} of {} files matching `/{}`; `/` shows all.",
+                shown.len(),
+                files.len(),
+                filter
+            )?;
+        }
+        write!(
+            err,
+            "{} of {} selected. Toggle numbers or ranges (e.g. `2 5-7`), `/text` to filter, `a` all, `n` none, Enter to continue: ",
+            selected.iter().filter(|&&keep| keep).count(),
+            files.len()
+        )?;
+        err.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        for token in line.split_whitespace() {
+            match token {
+                "a" => shown.iter().for_each(|&index| selected[index] = true),
+                "n" => shown.iter().for_each(|&index| selected[index] = false),
+                _ if token.starts_with('/') => filter = token[1..].to_string(),
+                _ => match parse_range(token, files.len()) {
+                    Some(range) => range.for_each(|index| selected[index] = !selected[index]),
+                    None => writeln!(err, "Ignoring `{}`: not a file number or range", token)?,
+                },
+            }
+        }
+    }
+    Ok(files
+        .iter()
+        .zip(selected)
+        .filter(|(_, keep)| !keep)
+        .map(|(file, _)| file.path.clone())
+        .collect())
+}
+
+/// Whether the characters of `query` appear in `path` in order, ignoring case, as fzf
+/// matches: `mnrs` matches `src/main.rs`.
+fn fuzzy_match(query: &str, path: &str) -> bool {
+    let mut path = path.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|wanted| path.any(|c| c == wanted))
+}
+
+/// Parses a 1-based file number or `FROM-TO` range into 0-based indexes below `len`.
+fn parse_range(token: &str, len: usize) -> Option<RangeInclusive<usize>> {
+    let (start, end) = token.split_once('-').unwrap_or((token, token));
+    let start: usize = start.parse().ok()?;
+    let end: usize = end.parse().ok()?;
+    (1 <= start && start <= end && end <= len).then(|| start - 1..=end - 1)
+}
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// The last commit that touched a file, noted in its header with `--git-blame-summary`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LastCommit {
+    /// Abbreviated commit hash.
+    pub hash: String,
+    pub author: String,
+    /// Author date as `YYYY-MM-DD`.
+    pub date: String,
+}
+
+/// Finds the last commit of each of `files`, given relative to `root`. Files git does not
+/// track are left out of the map.
+///
+/// A single `git log` walks the history from the newest commit and is stopped as soon as
+/// every tracked file has been seen. Errors are messages for a warning: `root` is not in
+/// a git repository, or git could not be run.
+pub(crate) fn last_commits(
+    root: &Path,
+    files: &[PathBuf],
+) -> Result<HashMap<PathBuf, LastCommit>, String> {
+    let git = || {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(root);
+        command
+    };
+    let listed = git()
+        .args(["ls-files", "-z"])
+        .output()
+        .map_err(|e| format!("Failed to run git for {}: {}", root.display(), e))?;
+    if !listed.status.success() {
+        return Err(format!("{} is not in a git repository", root.display()));
+    }
+    let tracked: HashSet<&[u8]> = listed.stdout.split(|&b| b == 0).collect();
+    let mut wanted: HashSet<&PathBuf> = files
+        .iter()
+        .filter(|file| tracked.contains(file.to_string_lossy().as_bytes()))
+        .collect();
+
+    let mut found = HashMap::new();
+    if wanted.is_empty() {
+        return Ok(found);
+    }
+    let mut child = git()
+        .args(["-c", "core.quotePath=false", "log"])
+        .args(["--name-only", "--relative", "--date=short"])
+        .arg("--format=\u{1}%h%x09%an%x09%ad")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run git for {}: {}", root.display(), e))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let mut commit: Option<LastCommit> = None;
+    for line in BufReader::new(stdout).lines() {
+        let Ok(line) = line else { break };
+        if let Some(header) = line.strip_prefix('\u{1}') {
+            let mut fields = header.splitn(3, '\t');
+            commit = Some(LastCommit {
+                hash: fields.next().unwrap_or_default().to_string(),
+                author: fields.next().unwrap_or_default().to_string(),
+                date: fields.next().unwrap_or_default().to_string(),
+            });
+        } else if let Some(commit) = commit.as_ref().filter(|_| !line.is_empty()) {
+            if let Some(file) = wanted.take(&PathBuf::from(line)) {
+                found.insert(file.clone(), commit.clone());
+                if wanted.is_empty() {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestTempDir;
+
+    #[test]
+    fn last_commit_of_each_tracked_file() {
+        let temp_dir = TestTempDir::new("last_commit");
+        temp_dir.write_file("repo/src/lib.rs", "pub fn f() {}\n");
+        temp_dir.write_file("repo/README.md", "# repo\n");
+        temp_dir.write_file("repo/src/scratch.rs", "fn scratch() {}\n");
+        let repo = temp_dir.path.join("repo");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&repo)
+                .args(["-c", "user.name=Dana", "-c", "user.email=dana@example.com"])
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["add", "src/lib.rs", "README.md"]);
+        git(&["commit", "-q", "-m", "first", "--date=2024-05-01T12:00:00"]);
+        temp_dir.write_file("repo/src/lib.rs", "pub fn g() {}\n");
+        git(&[
+            "commit",
+            "-q",
+            "-am",
+            "second",
+            "--date=2024-06-02T12:00:00",
+        ]);
+
+        let src = repo.join("src");
+        let files = [PathBuf::from("lib.rs"), PathBuf::from("scratch.rs")];
+        let commits = last_commits(&src, &files).unwrap();
+        assert_eq!(commits.len(), 1);
+        let lib = &commits[Path::new("lib.rs")];
+        assert_eq!(
+            (lib.author.as_str(), lib.date.as_str()),
+            ("Dana", "2024-06-02")
+        );
+        let readme =
+            &last_commits(&repo, &[PathBuf::from("README.md")]).unwrap()[Path::new("README.md")];
+        assert_eq!(readme.date, "2024-05-01");
+        assert_ne!(readme.hash, lib.hash);
+
+        let plain = temp_dir.path.join("plain");
+        std::fs::create_dir_all(&plain).unwrap();
+        let error = last_commits(&plain, &files).unwrap_err();
+        assert!(error.contains("is not in a git repository"));
+    }
+}
@@ -0,0 +1,1667 @@
+//! Core of oreuit: collects files from directory trees, reads their contents, and
+//! renders the report that the `oreuit` binary writes out.
+//!
+//! ```no_run
+//! use oreuit::{OutputFormat, RenderOptions, Summarizer, SummaryOptions};
+//!
+//! let options = SummaryOptions::new()
+//!     .directories(["src", "tests"])
+//!     .allowed_extensions([".rs", ".toml"])
+//!     .max_tokens(Some(50_000));
+//! let summary = Summarizer::new(options)?.run()?;
+//! let text = summary.render(OutputFormat::Markdown, &RenderOptions::default());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod archive;
+mod comments;
+mod config;
+mod content;
+mod filter;
+mod last_commit;
+mod notebook;
+mod output;
+mod remote;
+mod render;
+mod sha256;
+mod stats;
+mod tokens;
+mod tree;
+
+pub use config::{
+    BlacklistConfig, Config, ProjectConfig, WhitelistConfig, DEFAULT_ALLOWED_EXTENSIONS,
+    DEFAULT_EXTENSIONLESS_FILENAMES, DEFAULT_IGNORE_DIRS, DEFAULT_IGNORE_EXTENSIONS,
+    DEFAULT_WHITELIST_FILENAMES, PROJECT_CONFIG_FILENAME,
+};
+pub use content::{
+    check_encoding_label, FileSection, PlannedFile, SkipReason, StreamedContent,
+    DEFAULT_BINARY_THRESHOLD, DEFAULT_STREAM_THRESHOLD, ENCODING_NAMES,
+};
+pub use filter::{ExclusionCounts, ExclusionReason, FileOrder, HiddenFiles};
+pub use last_commit::LastCommit;
+pub use output::{ReportOutput, WrittenReport};
+pub use remote::{GitHubRepo, RemoteCheckout};
+pub use render::{
+    skip_report_json, HeaderStyle, OutputFormat, RenderOptions, SplitLimit, TocPlacement,
+    XmlLayout, XmlSkipped,
+};
+pub use stats::{ExtensionStats, SummaryStats};
+pub use tokens::{
+    estimate_tokens, estimate_tokens_from_size, format_size, format_thousands, TrimStrategy,
+    TrimSummary,
+};
+pub use tree::{TreeDetail, TreeSection};
+
+use archive::ARCHIVE_EXTENSIONS;
+use content::{
+    encoding_for_label, mark_duplicates, plan_file, read_file_section, ContentOptions,
+    DedupeSource, IMAGE_EXTENSIONS,
+};
+use encoding_rs::Encoding;
+use filter::{
+    build_globset, build_regex, collect_normalized_extensions, default_extensionless_filenames,
+    normalize_extension, regex_error, ChangedPaths, ExcludeGlobs, FilenameWhitelist, FilterRules,
+    GitTrackedRules, GitignoreRules, IgnoreFileRules, OreuitignoreRules,
+};
+use output::LineCounter;
+use rayon::prelude::*;
+use regex_automata::meta::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::SystemTime;
+use tree::{build_tree_from_paths, scan_directory, DirScan, TreeConnectors};
+
+/// Default value of `SummaryOptions::max_size`, in bytes.
+pub const DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Settings for a `Summarizer`, built with chained setters.
+///
+/// `SummaryOptions::new()` starts from the same defaults as the command line.
+#[derive(Clone, Debug)]
+pub struct SummaryOptions {
+    directories: Vec<PathBuf>,
+    files: Option<Vec<PathBuf>>,
+    allowed_extensions: HashSet<String>,
+    ignore_extensions: HashSet<String>,
+    ignore_dirs: HashSet<String>,
+    whitelist_filenames: HashSet<String>,
+    ignore_files: HashSet<String>,
+    respect_gitignore: bool,
+    oreuitignore: bool,
+    include_globs: Vec<String>,
+    exclude_globs: Vec<String>,
+    max_depth: Option<usize>,
+    min_size: Option<u64>,
+    modified_since: Option<SystemTime>,
+    follow_symlinks: bool,
+    hidden: HiddenFiles,
+    max_size: u64,
+    binary_threshold: f64,
+    line_numbers: bool,
+    head_lines: Option<usize>,
+    tail_lines: Option<usize>,
+    max_tokens: Option<usize>,
+    trim_strategy: TrimStrategy,
+    jobs: Option<usize>,
+    dedupe: bool,
+    encodings: Vec<String>,
+    grep: Option<String>,
+    grep_exclude: Vec<String>,
+    tree_details: Vec<TreeDetail>,
+    tree_dir_totals: bool,
+    ascii_tree: bool,
+    exclude_regex: Vec<String>,
+    git_tracked: bool,
+    changed_since: Option<String>,
+    strip_comments: bool,
+    directory_names: HashMap<PathBuf, String>,
+    squeeze_blank: bool,
+    relative_to: Option<PathBuf>,
+    order: FileOrder,
+    whitelist_case_insensitive: bool,
+    normalize_newlines: bool,
+    trim_whitespace: bool,
+    trim_markdown: bool,
+    max_size_for: HashMap<String, u64>,
+    deselected: HashSet<PathBuf>,
+    pinned: Vec<PathBuf>,
+    stream_threshold: Option<u64>,
+    git_blame_summary: bool,
+    ignore_file_patterns: Vec<String>,
+    hashes: bool,
+    binary_preview: usize,
+    embed_images: Option<u64>,
+    notebooks: bool,
+    archive_listing: Option<usize>,
+}
+
+impl Default for SummaryOptions {
+    fn default() -> Self {
+        SummaryOptions {
+            directories: vec![PathBuf::from(".")],
+            files: None,
+            allowed_extensions: collect_normalized_extensions(DEFAULT_ALLOWED_EXTENSIONS),
+            ignore_extensions: collect_normalized_extensions(DEFAULT_IGNORE_EXTENSIONS),
+            ignore_dirs: DEFAULT_IGNORE_DIRS.iter().map(|s| s.to_string()).collect(),
+            whitelist_filenames: DEFAULT_WHITELIST_FILENAMES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            ignore_files: HashSet::new(),
+            respect_gitignore: false,
+            oreuitignore: true,
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            max_depth: None,
+            min_size: None,
+            modified_since: None,
+            follow_symlinks: false,
+            hidden: HiddenFiles::default(),
+            max_size: DEFAULT_MAX_SIZE,
+            binary_threshold: DEFAULT_BINARY_THRESHOLD,
+            line_numbers: false,
+            head_lines: None,
+            tail_lines: None,
+            max_tokens: None,
+            trim_strategy: TrimStrategy::default(),
+            jobs: None,
+            dedupe: false,
+            encodings: Vec::new(),
+            grep: None,
+            grep_exclude: Vec::new(),
+            tree_details: Vec::new(),
+            tree_dir_totals: false,
+            ascii_tree: false,
+            exclude_regex: Vec::new(),
+            git_tracked: false,
+            changed_since: None,
+            strip_comments: false,
+            directory_names: HashMap::new(),
+            squeeze_blank: false,
+            relative_to: None,
+            order: FileOrder::default(),
+            whitelist_case_insensitive: false,
+            normalize_newlines: false,
+            trim_whitespace: false,
+            trim_markdown: false,
+            max_size_for: HashMap::new(),
+            deselected: HashSet::new(),
+            pinned: Vec::new(),
+            stream_threshold: Some(DEFAULT_STREAM_THRESHOLD),
+            git_blame_summary: false,
+            ignore_file_patterns: Vec::new(),
+            hashes: false,
+            binary_preview: 0,
+            embed_images: None,
+            notebooks: false,
+            archive_listing: None,
+        }
+    }
+}
+
+fn collect_strings<I, S>(values: I) -> HashSet<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    values
+        .into_iter()
+        .map(|value| value.as_ref().trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+impl SummaryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Directories to scan, each producing its own tree.
+    ///
+    /// A file may be given in place of a directory. It is read without applying the filters
+    /// (the size and binary checks still apply), labelled with its parent directory, and
+    /// gets a tree of its own with just that file.
+    pub fn directories<I, P>(mut self, directories: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.directories = directories.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Summarizes exactly these files instead of walking `directories`.
+    ///
+    /// The filters are not applied; the tree is rebuilt from the paths under the name `.`.
+    pub fn files<I, P>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.files = Some(files.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Extension allowlist. An empty list makes every non-ignored file eligible.
+    pub fn allowed_extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.allowed_extensions = collect_normalized_extensions(extensions);
+        self
+    }
+
+    pub fn ignore_extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignore_extensions = collect_normalized_extensions(extensions);
+        self
+    }
+
+    /// Directory names that are never descended into.
+    pub fn ignore_dirs<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignore_dirs = collect_strings(names);
+        self
+    }
+
+    /// Basenames that are always included. Entries with glob characters, such as
+    /// `Dockerfile*` or `*.mk`, are matched as patterns against the basename.
+    pub fn whitelist_filenames<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.whitelist_filenames = collect_strings(names);
+        self
+    }
+
+    /// Basenames that are excluded unless whitelisted.
+    pub fn ignore_files<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignore_files = collect_strings(names);
+        self
+    }
+
+    /// Replaces the filename and extension filters with the ones from a TOML config.
+    pub fn config(self, config: Config) -> Self {
+        self.allowed_extensions(config.whitelist.extensions)
+            .ignore_extensions(config.blacklist.extensions)
+            .ignore_dirs(config.blacklist.directories)
+            .whitelist_filenames(config.whitelist.files)
+            .ignore_files(config.blacklist.files)
+    }
+
+    pub fn respect_gitignore(mut self, respect: bool) -> Self {
+        self.respect_gitignore = respect;
+        self
+    }
+
+    /// Applies the `.oreuitignore` at the root of each scanned directory (on by default).
+    pub fn oreuitignore(mut self, enabled: bool) -> Self {
+        self.oreuitignore = enabled;
+        self
+    }
+
+    /// Names of ignore files with gitignore syntax, such as `.dockerignore`, to apply while
+    /// scanning. Each file applies to its own subtree within the scanned directory.
+    pub fn ignore_file_patterns<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.ignore_file_patterns = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Globs for files to include; when non-empty they replace the extension allowlist.
+    pub fn include_globs<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.include_globs = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Globs for files and directories to exclude.
+    pub fn exclude_globs<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude_globs = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Deepest level to descend into; `Some(0)` keeps only the top-level entries.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Files smaller than this many bytes are left out entirely.
+    pub fn min_size(mut self, min_size: Option<u64>) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Files last modified before this time are left out entirely.
+    pub fn modified_since(mut self, since: Option<SystemTime>) -> Self {
+        self.modified_since = since;
+        self
+    }
+
+    /// Follows symlinked directories and files, skipping links back to an ancestor and
+    /// files already reached through another path.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Whether files and directories whose name starts with `.` are collected. Whitelisted
+    /// names and the default extensionless filenames are kept either way. Defaults to
+    /// `HiddenFiles::Exclude`.
+    pub fn hidden(mut self, hidden: HiddenFiles) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Files larger than this many bytes are replaced with a placeholder.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Files larger than this many bytes are not held in memory: their text is read again,
+    /// a chunk at a time, when `Summary::write` writes the report. `None` reads every file
+    /// in full. Options that need a file's whole text, such as `grep` or `line_numbers`,
+    /// read it in full whatever its size.
+    pub fn stream_threshold(mut self, bytes: Option<u64>) -> Self {
+        self.stream_threshold = bytes;
+        self
+    }
+
+    /// Sets `FileSection::last_commit` of each file tracked by git to the last commit that
+    /// touched it. A directory outside a git repository, or a missing `git`, is reported
+    /// with a `Progress::Warning` and its files get no commit.
+    pub fn git_blame_summary(mut self, enabled: bool) -> Self {
+        self.git_blame_summary = enabled;
+        self
+    }
+
+    /// Sets `FileSection::sha256` of each file to the SHA-256 of its bytes. Files read in
+    /// full are hashed from the same bytes; binary, oversized, and streamed files are read
+    /// once more for the hash. Broken symlinks get none.
+    pub fn hashes(mut self, enabled: bool) -> Self {
+        self.hashes = enabled;
+        self
+    }
+
+    /// Share of control bytes in a file's first 1024 bytes above which it is treated as
+    /// binary. A NUL byte always marks a file as binary unless it starts with a UTF-16 BOM.
+    pub fn binary_threshold(mut self, threshold: f64) -> Self {
+        self.binary_threshold = threshold;
+        self
+    }
+
+    /// Replaces `[Binary file skipped]` with the file's size and a `hexdump -C`-style dump
+    /// of its first `bytes` bytes; 0 keeps the placeholder.
+    pub fn binary_preview(mut self, bytes: usize) -> Self {
+        self.binary_preview = bytes;
+        self
+    }
+
+    /// Embeds images (`.png`, `.jpg`, `.jpeg`, `.gif`, `.webp`, `.bmp`) of up to `max_bytes`
+    /// as a `data:image/png;base64,...` line instead of ignoring them; larger ones get an
+    /// `[image too large: 4.2 MB]` placeholder. Image extensions are then allowed and no
+    /// longer ignored, and the MIME type follows the magic bytes over the extension.
+    pub fn embed_images(mut self, max_bytes: Option<u64>) -> Self {
+        self.embed_images = max_bytes;
+        self
+    }
+
+    /// Includes Jupyter notebooks, even though `.ipynb` is ignored by default, as their
+    /// markdown cells and fenced code cells; outputs are dropped. A file that is not valid
+    /// notebook JSON gets a `[Could not parse notebook]` placeholder.
+    pub fn notebooks(mut self, notebooks: bool) -> Self {
+        self.notebooks = notebooks;
+        self
+    }
+
+    /// Lists the members of archives (`.zip`, `.jar`, `.war`, `.ear`, `.tar`, `.tar.gz`,
+    /// `.tgz`, `.gz`) with their sizes, like `unzip -l`, instead of ignoring them; at most
+    /// `max_entries` members are shown. Nothing is extracted and nested archives are not
+    /// opened. An archive that cannot be read gets a `[Could not read archive]` placeholder.
+    pub fn archive_listing(mut self, max_entries: Option<usize>) -> Self {
+        self.archive_listing = max_entries;
+        self
+    }
+
+    /// Prefixes each content line with its line number, e.g. ` 42 | `.
+    pub fn line_numbers(mut self, line_numbers: bool) -> Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Keeps only the first lines of each file; `Some(0)` leaves just the headers.
+    pub fn head_lines(mut self, head_lines: Option<usize>) -> Self {
+        self.head_lines = head_lines;
+        self
+    }
+
+    /// Keeps only the last lines of each file; combined with `head_lines`, both ends are kept.
+    pub fn tail_lines(mut self, tail_lines: Option<usize>) -> Self {
+        self.tail_lines = tail_lines;
+        self
+    }
+
+    /// Estimated token budget for the whole report.
+    pub fn max_tokens(mut self, max_tokens: Option<usize>) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn trim_strategy(mut self, strategy: TrimStrategy) -> Self {
+        self.trim_strategy = strategy;
+        self
+    }
+
+    /// Number of threads used to read files; `None` or `Some(0)` uses one per CPU.
+    pub fn jobs(mut self, jobs: Option<usize>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Encoding labels (as understood by the WHATWG Encoding Standard, e.g. `euc-jp`,
+    /// `big5`, `latin1`) tried in order when decoding a file. Empty, the default, tries
+    /// UTF-8 and otherwise detects ISO-2022-JP, Shift_JIS, EUC-JP, or windows-1252. Files
+    /// with a UTF-8 or UTF-16 byte order mark are always decoded with that encoding.
+    pub fn encodings<I, S>(mut self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.encodings = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Replaces files whose text repeats an earlier file's with a `[Duplicate of …]`
+    /// placeholder.
+    pub fn dedupe(mut self, dedupe: bool) -> Self {
+        self.dedupe = dedupe;
+        self
+    }
+
+    /// Keeps only files whose decoded text matches this regular expression. Files that would
+    /// get a placeholder are dropped, and the trees list only the matching files.
+    pub fn grep(mut self, pattern: Option<String>) -> Self {
+        self.grep = pattern;
+        self
+    }
+
+    /// Drops files whose decoded text matches any of these regular expressions, even when
+    /// they match `grep`. The trees list only the files that are kept.
+    pub fn grep_exclude<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.grep_exclude = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Appends the chosen details, such as ` (14.2 KB, 2024-05-02)`, to each file in the
+    /// trees. Files that cannot be stat'ed are listed by name only.
+    pub fn tree_details<I>(mut self, details: I) -> Self
+    where
+        I: IntoIterator<Item = TreeDetail>,
+    {
+        self.tree_details = details.into_iter().collect();
+        self
+    }
+
+    /// Appends the total size and number of selected files below each directory, such as
+    /// ` (3.4 MB, 212 files)`, to the directory lines of the trees.
+    pub fn tree_dir_totals(mut self, enabled: bool) -> Self {
+        self.tree_dir_totals = enabled;
+        self
+    }
+
+    /// Draws the trees with ASCII connectors (`|-- `, `` `-- ``, `|   `) and `...` instead of
+    /// box-drawing characters and `…`.
+    pub fn ascii_tree(mut self, ascii: bool) -> Self {
+        self.ascii_tree = ascii;
+        self
+    }
+
+    /// Leaves out files whose path relative to the scanned directory (with `/` separators)
+    /// matches any of these regular expressions. Whitelisted filenames are still kept.
+    pub fn exclude_regex<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude_regex = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Keeps only the files listed by `git ls-files` in each scanned directory; the other
+    /// filters still apply. A directory outside a git repository is scanned as usual and
+    /// reported with a `Progress::Warning`.
+    pub fn git_tracked(mut self, enabled: bool) -> Self {
+        self.git_tracked = enabled;
+        self
+    }
+
+    /// Keeps only the files that differ between the git ref `reference` and the working
+    /// tree (staged or not) in each scanned directory. Deleted files are listed in the trees
+    /// with a `[deleted]` marker. `Summarizer::new` fails when a directory is not in a git
+    /// repository or the ref cannot be resolved.
+    pub fn changed_since(mut self, reference: Option<String>) -> Self {
+        self.changed_since = reference;
+        self
+    }
+
+    /// Removes line and block comments from Rust, C, C++, Java, Go, JavaScript, TypeScript,
+    /// Python, Ruby, and TOML files before they are numbered or truncated. Other files are
+    /// emitted unchanged.
+    pub fn strip_comments(mut self, enabled: bool) -> Self {
+        self.strip_comments = enabled;
+        self
+    }
+
+    /// Names `directory` `name` in headings and file labels instead of its last path
+    /// component, e.g. `owner/repo` for a temporary clone.
+    pub fn directory_name(
+        mut self,
+        directory: impl Into<PathBuf>,
+        name: impl Into<String>,
+    ) -> Self {
+        self.directory_names.insert(directory.into(), name.into());
+        self
+    }
+
+    /// Collapses runs of blank lines in file contents into one and drops blank lines at the
+    /// start and end of each file, after comments are stripped and before lines are
+    /// numbered.
+    pub fn squeeze_blank(mut self, enabled: bool) -> Self {
+        self.squeeze_blank = enabled;
+        self
+    }
+
+    /// Makes file header paths relative to `base` instead of to each scanned directory, so
+    /// files from several directories under one parent get comparable paths such as
+    /// `crate-a/src/lib.rs`. Files outside `base` are shown with their absolute path.
+    /// `Summarizer::new` fails when `base` cannot be resolved.
+    pub fn relative_to(mut self, base: Option<PathBuf>) -> Self {
+        self.relative_to = base;
+        self
+    }
+
+    /// Order of the files within each scanned directory, which is the order of the
+    /// contents. Defaults to `FileOrder::Path`; the trees stay alphabetical either way.
+    pub fn order(mut self, order: FileOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Match `whitelist_filenames` entries, plain names and glob patterns alike, without
+    /// regard to case, so `Dockerfile` also keeps `dockerfile`.
+    pub fn whitelist_case_insensitive(mut self, enabled: bool) -> Self {
+        self.whitelist_case_insensitive = enabled;
+        self
+    }
+
+    /// Converts `\r\n` and lone `\r` line endings in file contents to `\n`, right after
+    /// decoding. Placeholders, headers, and trees are not affected.
+    pub fn normalize_newlines(mut self, enabled: bool) -> Self {
+        self.normalize_newlines = enabled;
+        self
+    }
+
+    /// Removes trailing whitespace from each line of file contents and collapses runs of
+    /// three or more blank lines into one. Markdown files are left alone, since trailing
+    /// spaces there are hard line breaks, unless `trim_markdown` is set.
+    pub fn trim_whitespace(mut self, enabled: bool) -> Self {
+        self.trim_whitespace = enabled;
+        self
+    }
+
+    /// Applies `trim_whitespace` to `.md` and `.markdown` files too.
+    pub fn trim_markdown(mut self, enabled: bool) -> Self {
+        self.trim_markdown = enabled;
+        self
+    }
+
+    /// Per-extension limits that replace `max_size` for matching files, such as
+    /// `(".lock", 10_000)`. Extensions are normalized like `allowed_extensions`; files with
+    /// other extensions keep `max_size`.
+    pub fn max_size_for<I, S>(mut self, limits: I) -> Self
+    where
+        I: IntoIterator<Item = (S, u64)>,
+        S: AsRef<str>,
+    {
+        self.max_size_for = limits
+            .into_iter()
+            .filter_map(|(ext, limit)| Some((normalize_extension(ext.as_ref())?, limit)))
+            .collect();
+        self
+    }
+
+    /// Leaves these files out of both the trees and the contents, whatever the filters say.
+    /// Paths are compared as walked, i.e. a directory joined with the path below it, or as
+    /// given to `files`; `Summarizer::dry_run` lists them in that form. Calling it again
+    /// leaves out more files.
+    pub fn deselect<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.deselected.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Puts these files first in the contents, in the given order and ahead of `order`,
+    /// and marks their headers with `[PINNED]`. Paths are compared like `deselect`,
+    /// ignoring `./` components. A pinned file is still read like any other, so it can get
+    /// a placeholder; one that is not selected is reported as a warning. Calling it again
+    /// pins more files.
+    pub fn pin<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.pinned.extend(paths.into_iter().map(Into::into));
+        self
+    }
+}
+
+/// Collects and reads files according to a `SummaryOptions`.
+pub struct Summarizer {
+    options: SummaryOptions,
+    filters: FilterRules,
+    encodings: Vec<&'static Encoding>,
+    grep: Option<Regex>,
+    grep_exclude: Option<Regex>,
+    relative_to: Option<PathBuf>,
+}
+
+impl Summarizer {
+    /// Validates the options, reporting invalid glob patterns, unknown encoding labels, an
+    /// invalid `exclude_regex`, `grep`, or `grep_exclude` pattern, and a `changed_since` ref
+    /// that git cannot diff against, and a `relative_to` base that does not exist as an
+    /// error.
+    pub fn new(mut options: SummaryOptions) -> Result<Self, Box<dyn Error>> {
+        // Files given by name are not filtered, so deselected ones are dropped here.
+        if let Some(files) = &mut options.files {
+            files.retain(|file| !options.deselected.contains(file));
+        }
+        let deselected = &options.deselected;
+        options.directories.retain(|dir| !deselected.contains(dir));
+        let mut pinned = Vec::new();
+        for path in options.pinned.iter().map(|path| without_cur_dir(path)) {
+            if !pinned.contains(&path) {
+                pinned.push(path);
+            }
+        }
+        options.pinned = pinned;
+        let mut allowed = options.allowed_extensions.clone();
+        let mut ignore_exts = options.ignore_extensions.clone();
+        if options.embed_images.is_some() {
+            for (ext, _) in IMAGE_EXTENSIONS {
+                allowed.insert(ext.to_string());
+                ignore_exts.remove(*ext);
+            }
+        }
+        if options.archive_listing.is_some() {
+            for ext in ARCHIVE_EXTENSIONS {
+                allowed.insert(ext.to_string());
+                ignore_exts.remove(*ext);
+            }
+        }
+        if options.notebooks {
+            allowed.insert(".ipynb".to_string());
+            ignore_exts.remove(".ipynb");
+        }
+        let filters = FilterRules {
+            allowed,
+            ignore_exts,
+            ignore_dirs: options.ignore_dirs.clone(),
+            whitelist_filenames: FilenameWhitelist::new(
+                &options.whitelist_filenames,
+                options.whitelist_case_insensitive,
+            )?,
+            ignore_files: options.ignore_files.clone(),
+            extensionless_allowed: default_extensionless_filenames(),
+            gitignore: options.respect_gitignore.then(GitignoreRules::default),
+            oreuitignore: options.oreuitignore.then(OreuitignoreRules::default),
+            ignore_file_patterns: (!options.ignore_file_patterns.is_empty())
+                .then(|| IgnoreFileRules::new(options.ignore_file_patterns.iter().cloned())),
+            git_tracked: options.git_tracked.then(GitTrackedRules::default),
+            changed: match &options.changed_since {
+                Some(reference) => Some(
+                    options
+                        .directories
+                        .iter()
+                        .filter(|dir| !dir.is_file())
+                        .map(|dir| Ok((dir.clone(), ChangedPaths::since(dir, reference)?)))
+                        .collect::<Result<_, Box<dyn Error>>>()?,
+                ),
+                None => None,
+            },
+            include_globs: build_globset(&options.include_globs.join(","), "--include-glob")?,
+            exclude_globs: ExcludeGlobs::new(&options.exclude_globs.join(","))?,
+            exclude_regex: build_regex(&options.exclude_regex, "--exclude-regex")?,
+            max_depth: options.max_depth,
+            min_size: options.min_size,
+            modified_since: options.modified_since,
+            follow_symlinks: options.follow_symlinks,
+            hidden: options.hidden,
+            tree_details: options.tree_details.clone(),
+            tree_dir_totals: options.tree_dir_totals,
+            tree_connectors: if options.ascii_tree {
+                TreeConnectors::ASCII
+            } else {
+                TreeConnectors::UNICODE
+            },
+            order: options.order,
+            deselected: options.deselected.clone(),
+        };
+        let encodings = options
+            .encodings
+            .iter()
+            .map(|label| encoding_for_label(label))
+            .collect::<Result<_, _>>()?;
+        let grep = match &options.grep {
+            Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                format!("Invalid --grep pattern '{}': {}", pattern, regex_error(&e))
+            })?),
+            None => None,
+        };
+        // One matcher for all patterns, so a file is scanned once and the search stops at the
+        // first match of any of them.
+        let grep_exclude = build_regex(&options.grep_exclude, "--grep-exclude")?;
+        let relative_to = match &options.relative_to {
+            Some(base) => Some(fs::canonicalize(base).map_err(|e| {
+                format!("--relative-to: cannot resolve '{}': {}", base.display(), e)
+            })?),
+            None => None,
+        };
+        Ok(Summarizer {
+            options,
+            filters,
+            encodings,
+            grep,
+            grep_exclude,
+            relative_to,
+        })
+    }
+
+    /// Builds the trees, reads every selected file, and applies the token budget.
+    ///
+    /// Warnings (such as an unparsable `.gitignore`) are printed to stderr; use
+    /// `run_with_progress` to handle them yourself.
+    pub fn run(&self) -> Result<Summary, Box<dyn Error>> {
+        self.run_with_progress(|event| {
+            if let Progress::Warning(message) = event {
+                eprintln!("Warning: {}", message);
+            }
+        })
+    }
+
+    /// Same as `run`, reporting each phase to `progress`. `Reading` events come from the
+    /// reader threads, in completion order.
+    pub fn run_with_progress<F>(&self, progress: F) -> Result<Summary, Box<dyn Error>>
+    where
+        F: Fn(Progress) + Sync,
+    {
+        // Content patterns decide which files stay, so their trees are built after reading.
+        let filters_content = self.grep.is_some() || self.grep_exclude.is_some();
+        let scans = self.scan(&mut ExclusionCounts::new(), &progress);
+        let trees = if !filters_content {
+            self.trees(&scans)
+        } else {
+            Vec::new()
+        };
+        let pending = self.pending_files(scans, &progress);
+        self.report_warnings(&progress);
+
+        let content_options = self.content_options();
+        let pool = self.thread_pool()?;
+        let counts = ReadCounts::new(pending.len());
+        let dirs: Vec<PathBuf> = pending.iter().map(|(dir, _, _)| dir.clone()).collect();
+        let (mut sections, sources): (Vec<Option<FileSection>>, Vec<Option<DedupeSource>>) = self
+            .read_pending(&pool, pending, &content_options, &counts, &progress)
+            .into_iter()
+            .unzip();
+        if self.options.git_blame_summary {
+            add_last_commits(&dirs, &mut sections, &progress);
+        }
+        let trees = if filters_content {
+            self.matching_trees(&dirs, &sections)
+        } else {
+            trees
+        };
+        let (mut files, sources): (Vec<FileSection>, Vec<Option<DedupeSource>>) = sections
+            .into_iter()
+            .zip(sources)
+            .filter_map(|(section, source)| section.map(|section| (section, source)))
+            .unzip();
+        if self.options.dedupe {
+            mark_duplicates(&mut files, &sources);
+        }
+
+        let trimmed = match self.options.max_tokens {
+            Some(max_tokens) => tokens::apply_token_budget(
+                &trees,
+                &mut files,
+                max_tokens,
+                self.options.trim_strategy,
+            ),
+            None => TrimSummary::default(),
+        };
+        for file in &files {
+            report_placeholder(file, &progress);
+        }
+
+        Ok(Summary {
+            trees,
+            files,
+            trimmed,
+        })
+    }
+
+    /// Builds the trees without reading any file, for a report of the directory structure
+    /// alone. `grep` and `grep_exclude` need the contents and are not applied, and the
+    /// summary has no files; render it with `RenderOptions::tree_only`.
+    pub fn run_tree_only<F>(&self, progress: F) -> Summary
+    where
+        F: Fn(Progress),
+    {
+        let scans = self.scan(&mut ExclusionCounts::new(), &progress);
+        let trees = self.trees(&scans);
+        self.report_warnings(&progress);
+        Summary {
+            trees,
+            files: Vec::new(),
+            trimmed: TrimSummary::default(),
+        }
+    }
+
+    /// Writes the report as `OutputFormat::Jsonl` to `out` while the files are read: the
+    /// tree line first, then each file's line once it and the files before it are read, so
+    /// the contents are never all held in memory. Returns the estimated token count of the
+    /// written text.
+    ///
+    /// `grep`, `grep_exclude`, `dedupe`, and `max_tokens` need every file before the first
+    /// line can be written; with any of them this runs `run_with_progress` and writes the
+    /// same lines afterwards.
+    pub fn write_jsonl<W, F>(&self, out: &mut W, progress: F) -> Result<usize, Box<dyn Error>>
+    where
+        W: Write + ?Sized,
+        F: Fn(Progress) + Sync,
+    {
+        let needs_all_files = self.grep.is_some()
+            || self.grep_exclude.is_some()
+            || self.options.dedupe
+            || self.options.max_tokens.is_some();
+        if needs_all_files {
+            let summary = self.run_with_progress(progress)?;
+            return Ok(summary.write(OutputFormat::Jsonl, &RenderOptions::default(), out)?);
+        }
+
+        let scans = self.scan(&mut ExclusionCounts::new(), &progress);
+        let line = render::jsonl_tree_line(&self.trees(&scans));
+        out.write_all(line.as_bytes())?;
+        let mut tokens = estimate_tokens(&line);
+        let pending = self.pending_files(scans, &progress);
+        self.report_warnings(&progress);
+
+        let content_options = self.content_options();
+        let pool = self.thread_pool()?;
+        let counts = ReadCounts::new(pending.len());
+        // A few files per thread at a time keeps every thread busy without holding more.
+        let batch_size = pool.current_num_threads() * 4;
+        let mut pending = pending.into_iter();
+        loop {
+            let batch: Vec<_> = pending.by_ref().take(batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+            let read = self.read_pending(&pool, batch, &content_options, &counts, &progress);
+            for section in read.into_iter().filter_map(|(section, _)| section) {
+                report_placeholder(&section, &progress);
+                tokens += render::write_jsonl_file_line(&section, out)?;
+            }
+        }
+        out.flush()?;
+        Ok(tokens)
+    }
+
+    /// Same as `write_jsonl`, writing to `output` instead; its split limit is not applied.
+    /// Also returns the number of file lines, which leaves out the tree line.
+    pub fn write_jsonl_to<F>(
+        &self,
+        output: &ReportOutput,
+        progress: F,
+    ) -> Result<(WrittenReport, usize), Box<dyn Error>>
+    where
+        F: Fn(Progress) + Sync,
+    {
+        let mut lines: usize = 0;
+        let written = output.write_with(OutputFormat::Jsonl, |out| {
+            let mut out = LineCounter {
+                inner: out,
+                lines: 0,
+            };
+            let tokens = self.write_jsonl(&mut out, progress);
+            lines = out.lines;
+            tokens
+        })?;
+        Ok((written, lines.saturating_sub(1)))
+    }
+
+    /// Lists the files `run` would read, with their sizes and the placeholder each would
+    /// get, without reading any contents. Only the first bytes are sampled for binary
+    /// detection.
+    pub fn dry_run(&self) -> DryRun {
+        let mut excluded = ExclusionCounts::new();
+        let content_options = self.content_options();
+        let scans = self.scan(&mut excluded, &|_| {});
+        let print_warning = |event: Progress| {
+            if let Progress::Warning(message) = event {
+                eprintln!("Warning: {}", message);
+            }
+        };
+        let pending = self.pending_files(scans, &print_warning);
+        self.report_warnings(&print_warning);
+        let files = pending
+            .into_iter()
+            .map(|(dir, dir_name, file)| plan_file(&dir, dir_name, file, &content_options))
+            .collect();
+        DryRun { files, excluded }
+    }
+
+    /// The paths of the files `run` would read, in output order, found without opening any
+    /// file. Warnings from ignore files are dropped, since the scan is repeated while
+    /// watching for changes.
+    pub fn selected_files(&self) -> Vec<PathBuf> {
+        let scans = self.scan(&mut ExclusionCounts::new(), &|_| {});
+        let pending = self.pending_files(scans, &|_| {});
+        self.report_warnings(&|_| {});
+        pending.into_iter().map(|(_, _, file)| file).collect()
+    }
+
+    fn thread_pool(&self) -> Result<rayon::ThreadPool, rayon::ThreadPoolBuildError> {
+        let mut pool = rayon::ThreadPoolBuilder::new();
+        if let Some(jobs) = self.options.jobs {
+            pool = pool.num_threads(jobs);
+        }
+        pool.build()
+    }
+
+    /// Reads `pending` on `pool`, reporting each file to `progress`, and pairs each section
+    /// with its `DedupeSource` when `dedupe` is set.
+    ///
+    /// Reading is the slow part; rayon keeps the given order, so the output stays identical
+    /// to a sequential run.
+    fn read_pending(
+        &self,
+        pool: &rayon::ThreadPool,
+        pending: Vec<(PathBuf, String, PathBuf)>,
+        content_options: &ContentOptions,
+        counts: &ReadCounts,
+        progress: &(dyn Fn(Progress) + Sync),
+    ) -> Vec<(Option<FileSection>, Option<DedupeSource>)> {
+        pool.install(|| {
+            pending
+                .into_par_iter()
+                .map(|(dir, dir_name, file)| {
+                    let path = file.clone();
+                    let (mut section, source) =
+                        read_file_section(&dir, dir_name, file, content_options)
+                            .map_or((None, None), |(section, source)| (Some(section), source));
+                    if let Some(section) = section.as_mut() {
+                        section.pinned = self.is_pinned(&path);
+                    }
+                    let read = section
+                        .as_ref()
+                        .filter(|section| section.skipped.is_none())
+                        .map_or(0, |section| section.text_len() as u64);
+                    progress(Progress::Reading {
+                        done: counts.done.fetch_add(1, Ordering::Relaxed) + 1,
+                        total: counts.total,
+                        bytes: counts.bytes.fetch_add(read, Ordering::Relaxed) + read,
+                        path: &path,
+                    });
+                    (section, source)
+                })
+                .collect()
+        })
+    }
+
+    fn report_warnings(&self, progress: &dyn Fn(Progress)) {
+        let mut warnings = Vec::new();
+        if let Some(gitignore) = &self.filters.gitignore {
+            warnings.extend(gitignore.take_warnings());
+        }
+        if let Some(oreuitignore) = &self.filters.oreuitignore {
+            warnings.extend(oreuitignore.take_warnings());
+        }
+        if let Some(ignore_files) = &self.filters.ignore_file_patterns {
+            warnings.extend(ignore_files.take_warnings());
+        }
+        if let Some(git_tracked) = &self.filters.git_tracked {
+            warnings.extend(git_tracked.take_warnings());
+        }
+        for message in warnings {
+            progress(Progress::Warning(&message));
+        }
+    }
+
+    fn content_options(&self) -> ContentOptions {
+        ContentOptions {
+            max_size: self.options.max_size,
+            max_size_for: self.options.max_size_for.clone(),
+            binary_threshold: self.options.binary_threshold,
+            line_numbers: self.options.line_numbers,
+            head_lines: self.options.head_lines,
+            tail_lines: self.options.tail_lines,
+            encodings: self.encodings.clone(),
+            grep: self.grep.clone(),
+            grep_exclude: self.grep_exclude.clone(),
+            strip_comments: self.options.strip_comments,
+            squeeze_blank: self.options.squeeze_blank,
+            normalize_newlines: self.options.normalize_newlines,
+            trim_whitespace: self.options.trim_whitespace,
+            trim_markdown: self.options.trim_markdown,
+            relative_to: self.relative_to.clone(),
+            // Duplicates are found by hashing the whole text.
+            stream_threshold: self
+                .options
+                .stream_threshold
+                .filter(|_| !self.options.dedupe),
+            hashes: self.options.hashes,
+            binary_preview: self.options.binary_preview,
+            embed_images: self.options.embed_images,
+            notebooks: self.options.notebooks,
+            archive_listing: self.options.archive_listing,
+            dedupe: self.options.dedupe,
+        }
+    }
+
+    /// Walks each scanned directory once, for both its tree and its files; `None` for a
+    /// file given in `directories`. Files left out are counted in `excluded` and reported
+    /// to `progress` along with the directories that were not searched. With
+    /// `follow_symlinks`, a file reached through several paths keeps only the first.
+    fn scan(
+        &self,
+        excluded: &mut ExclusionCounts,
+        progress: &dyn Fn(Progress),
+    ) -> Vec<Option<DirScan>> {
+        if self.options.files.is_some() {
+            return Vec::new();
+        }
+        let mut scans = Vec::new();
+        for dir in &self.options.directories {
+            if self.file_entry(dir).is_some() {
+                scans.push(None);
+                continue;
+            }
+            progress(Progress::Scanning(dir));
+            scans.push(Some(scan_directory(
+                dir,
+                &self.filters,
+                &mut |path, reason, directory| {
+                    if !directory {
+                        *excluded.entry(reason).or_insert(0) += 1;
+                    }
+                    progress(Progress::Skipped {
+                        path,
+                        name: reason.name(),
+                        reason: reason.label(),
+                        directory,
+                    });
+                },
+            )));
+        }
+        if self.options.follow_symlinks {
+            let mut seen = HashSet::new();
+            for scan in scans.iter_mut().flatten() {
+                scan.drop_aliases(&mut seen);
+            }
+        }
+        scans
+    }
+
+    fn trees(&self, scans: &[Option<DirScan>]) -> Vec<TreeSection> {
+        match &self.options.files {
+            Some(files) => vec![TreeSection {
+                dir_name: ".".to_string(),
+                tree: build_tree_from_paths(
+                    ".",
+                    Path::new("."),
+                    files,
+                    &[],
+                    &self.options.tree_details,
+                    self.options.tree_dir_totals,
+                    self.filters.tree_connectors,
+                ),
+            }],
+            None => self
+                .options
+                .directories
+                .iter()
+                .zip(scans)
+                .map(|(dir, scan)| {
+                    let Some(scan) = scan else {
+                        let (parent, dir_name) =
+                            self.file_entry(dir).expect("only files are not scanned");
+                        return TreeSection {
+                            tree: self.file_entry_tree(Some(dir), &parent, &dir_name),
+                            dir_name,
+                        };
+                    };
+                    let dir_name = self.dir_name(dir);
+                    // Deleted files are not on disk, so the tree is built from the changed
+                    // paths instead of the walk.
+                    let tree = match &self.filters.changed {
+                        Some(_) => {
+                            let files: Vec<PathBuf> = scan
+                                .files
+                                .iter()
+                                .map(|file| file.strip_prefix(dir).unwrap_or(file).to_path_buf())
+                                .collect();
+                            build_tree_from_paths(
+                                &dir_name,
+                                dir,
+                                &files,
+                                self.deleted_files(dir),
+                                &self.options.tree_details,
+                                self.options.tree_dir_totals,
+                                self.filters.tree_connectors,
+                            )
+                        }
+                        None => scan.tree(&dir_name, &self.filters),
+                    };
+                    TreeSection { dir_name, tree }
+                })
+                .collect(),
+        }
+    }
+
+    /// Trees listing only the files kept by `grep` and `grep_exclude`, given the scanned directory of each
+    /// section.
+    fn matching_trees(
+        &self,
+        dirs: &[PathBuf],
+        sections: &[Option<FileSection>],
+    ) -> Vec<TreeSection> {
+        let matched = |dir: Option<&PathBuf>| -> Vec<PathBuf> {
+            dirs.iter()
+                .zip(sections)
+                .filter(|(section_dir, _)| dir.map_or(true, |dir| dir == *section_dir))
+                .filter_map(|(_, section)| section.as_ref())
+                .map(|section| PathBuf::from(&section.relative_path))
+                .collect()
+        };
+        match &self.options.files {
+            Some(_) => vec![TreeSection {
+                dir_name: ".".to_string(),
+                tree: build_tree_from_paths(
+                    ".",
+                    Path::new("."),
+                    &matched(None),
+                    &[],
+                    &self.options.tree_details,
+                    self.options.tree_dir_totals,
+                    self.filters.tree_connectors,
+                ),
+            }],
+            None => self
+                .options
+                .directories
+                .iter()
+                .map(|dir| {
+                    if let Some((parent, dir_name)) = self.file_entry(dir) {
+                        let kept = sections.iter().flatten().any(|s| s.path == *dir);
+                        return TreeSection {
+                            tree: self.file_entry_tree(kept.then_some(&**dir), &parent, &dir_name),
+                            dir_name,
+                        };
+                    }
+                    let dir_name = self.dir_name(dir);
+                    TreeSection {
+                        tree: build_tree_from_paths(
+                            &dir_name,
+                            dir,
+                            &matched(Some(dir)),
+                            self.deleted_files(dir),
+                            &self.options.tree_details,
+                            self.options.tree_dir_totals,
+                            self.filters.tree_connectors,
+                        ),
+                        dir_name,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Name of a scanned directory in headings and file labels; see `directory_name`.
+    fn dir_name(&self, dir: &Path) -> String {
+        match self.options.directory_names.get(dir) {
+            Some(name) => name.clone(),
+            None => display_name(dir),
+        }
+    }
+
+    /// For a file given in `directories`, its parent directory (`.` for a bare name) and
+    /// the parent's name, which the file is labelled with.
+    fn file_entry(&self, entry: &Path) -> Option<(PathBuf, String)> {
+        if !entry.is_file() {
+            return None;
+        }
+        let parent = match entry.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let name = self.dir_name(&parent);
+        Some((parent, name))
+    }
+
+    /// The tree of a file given in `directories`: its parent with the file as the only
+    /// entry, or with no entries when the file was left out.
+    fn file_entry_tree(&self, file: Option<&Path>, parent: &Path, parent_name: &str) -> String {
+        let names: Vec<PathBuf> = file
+            .and_then(Path::file_name)
+            .map(PathBuf::from)
+            .into_iter()
+            .collect();
+        build_tree_from_paths(
+            parent_name,
+            parent,
+            &names,
+            &[],
+            &self.options.tree_details,
+            self.options.tree_dir_totals,
+            self.filters.tree_connectors,
+        )
+    }
+
+    /// Files under `dir` removed since `changed_since`, relative to it.
+    fn deleted_files(&self, dir: &Path) -> &[PathBuf] {
+        self.filters
+            .changed
+            .as_ref()
+            .and_then(|changed| changed.get(dir))
+            .map_or(&[], |changed| &changed.deleted)
+    }
+
+    /// Files to read as `(scanned directory, directory name, file)`, in output order: the
+    /// pinned files first, then the rest. A pin that matches no file is reported as a
+    /// warning.
+    fn pending_files(
+        &self,
+        scans: Vec<Option<DirScan>>,
+        progress: &dyn Fn(Progress),
+    ) -> Vec<(PathBuf, String, PathBuf)> {
+        let mut pending = self.selected_in_order(scans);
+        if self.options.pinned.is_empty() {
+            return pending;
+        }
+        let mut pinned = Vec::new();
+        for pin in &self.options.pinned {
+            match pending
+                .iter()
+                .position(|(_, _, file)| without_cur_dir(file) == *pin)
+            {
+                Some(i) => pinned.push(pending.remove(i)),
+                None => progress(Progress::Warning(&format!(
+                    "--pin {} matches no selected file",
+                    pin.display()
+                ))),
+            }
+        }
+        pinned.extend(pending);
+        pinned
+    }
+
+    /// Whether `path` is one of `SummaryOptions::pin`.
+    fn is_pinned(&self, path: &Path) -> bool {
+        !self.options.pinned.is_empty() && self.options.pinned.contains(&without_cur_dir(path))
+    }
+
+    /// Files to read before pinning, in `order` within each scanned directory.
+    fn selected_in_order(&self, scans: Vec<Option<DirScan>>) -> Vec<(PathBuf, String, PathBuf)> {
+        let mut pending = Vec::new();
+        if let Some(files) = &self.options.files {
+            let root = PathBuf::from(".");
+            for file in files {
+                pending.push((root.clone(), ".".to_string(), file.clone()));
+            }
+            return pending;
+        }
+        for (dir, scan) in self.options.directories.iter().zip(scans) {
+            // A file given in place of a directory is read whatever the filters say.
+            let Some(scan) = scan else {
+                let (parent, name) = self.file_entry(dir).expect("only files are not scanned");
+                pending.push((parent, name, dir.clone()));
+                continue;
+            };
+            let dir_name = self.dir_name(dir);
+            for file in scan.files {
+                pending.push((dir.clone(), dir_name.clone(), file));
+            }
+        }
+        pending
+    }
+}
+
+/// Counts behind `Progress::Reading`, shared by the reader threads.
+struct ReadCounts {
+    total: usize,
+    done: AtomicUsize,
+    /// Bytes of decoded text read so far.
+    bytes: AtomicU64,
+}
+
+impl ReadCounts {
+    fn new(total: usize) -> Self {
+        ReadCounts {
+            total,
+            done: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Reports a file whose content was replaced with a placeholder as `Progress::Skipped`.
+fn report_placeholder(section: &FileSection, progress: &dyn Fn(Progress)) {
+    if let Some(reason) = section.skipped {
+        progress(Progress::Skipped {
+            path: &section.path,
+            name: reason.name(),
+            reason: reason.label(),
+            directory: false,
+        });
+    }
+}
+
+/// Sets `last_commit` of the read files, with one `git log` per scanned directory. `dirs`
+/// holds the scanned directory of each entry of `sections`.
+fn add_last_commits(
+    dirs: &[PathBuf],
+    sections: &mut [Option<FileSection>],
+    progress: &dyn Fn(Progress),
+) {
+    let mut by_dir: Vec<(&PathBuf, Vec<usize>)> = Vec::new();
+    for (i, dir) in dirs.iter().enumerate() {
+        match by_dir.iter_mut().find(|(seen, _)| *seen == dir) {
+            Some((_, indices)) => indices.push(i),
+            None => by_dir.push((dir, vec![i])),
+        }
+    }
+    for (dir, indices) in by_dir {
+        let files: Vec<(usize, PathBuf)> = indices
+            .into_iter()
+            .filter_map(|i| {
+                let section = sections[i].as_ref()?;
+                Some((i, section.path.strip_prefix(dir).ok()?.to_path_buf()))
+            })
+            .collect();
+        let relative: Vec<PathBuf> = files.iter().map(|(_, path)| path.clone()).collect();
+        match last_commit::last_commits(dir, &relative) {
+            Ok(mut commits) => {
+                for (i, path) in files {
+                    if let Some(section) = sections[i].as_mut() {
+                        section.last_commit = commits.remove(&path);
+                    }
+                }
+            }
+            Err(message) => progress(Progress::Warning(&format!(
+                "{}; --git-blame-summary is ignored for it",
+                message
+            ))),
+        }
+    }
+}
+
+/// `path` without its `.` components, so `./src/main.rs` and `src/main.rs` compare equal.
+fn without_cur_dir(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Name used for a scanned directory in headings and file labels.
+fn display_name(dir: &Path) -> String {
+    match dir.file_name().and_then(|s| s.to_str()) {
+        Some(s) => s.to_string(),
+        None => dir.to_string_lossy().into_owned(),
+    }
+}
+
+/// Events passed to the callback of `Summarizer::run_with_progress`.
+#[derive(Clone, Copy, Debug)]
+pub enum Progress<'a> {
+    /// Walking a directory, for both its tree and its files.
+    Scanning(&'a Path),
+    /// `path` has been read; `done` of `total` files are finished.
+    Reading {
+        done: usize,
+        total: usize,
+        /// Bytes of decoded text read so far.
+        bytes: u64,
+        path: &'a Path,
+    },
+    /// A problem that does not stop the run.
+    Warning(&'a str),
+    /// `path` is left out by the filters, or its content is replaced with a placeholder;
+    /// `reason` says why, and `name` is its short kebab-case name, e.g. `ignored-ext`. A
+    /// `directory` is pruned together with everything below it.
+    Skipped {
+        path: &'a Path,
+        name: &'static str,
+        reason: &'static str,
+        directory: bool,
+    },
+}
+
+/// A `Progress::Skipped` event kept for `render::skip_report_json`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedEntry {
+    pub path: PathBuf,
+    pub name: &'static str,
+    pub reason: &'static str,
+    pub directory: bool,
+}
+
+/// Result of `Summarizer::dry_run`.
+#[derive(Clone, Debug)]
+pub struct DryRun {
+    /// Every selected file in output order.
+    pub files: Vec<PlannedFile>,
+    /// Files left out by the filters, per reason.
+    pub excluded: ExclusionCounts,
+}
+
+impl DryRun {
+    /// Writes the listing `--dry-run` prints: each file with its size and the running
+    /// total, then the totals, the exclusions, and the placeholders.
+    pub fn write_listing<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{:>14}  {:>14}  PATH", "SIZE", "TOTAL")?;
+        let mut total = 0;
+        let mut tokens = 0;
+        let mut placeholders = BTreeMap::new();
+        for file in &self.files {
+            total += file.size;
+            if file.skipped.is_none() {
+                tokens += estimate_tokens_from_size(file.size);
+            }
+            let note = match file.skipped {
+                Some(SkipReason::TooLarge) => " [too large]",
+                Some(SkipReason::Binary) => " [binary]",
+                Some(SkipReason::BrokenSymlink) => " [broken symlink]",
+                _ => "",
+            };
+            if !note.is_empty() {
+                *placeholders.entry(note.trim()).or_insert(0) += 1;
+            }
+            writeln!(
+                out,
+                "{:>14}  {:>14}  {} (in {}){}",
+                format_thousands(file.size as usize),
+                format_thousands(total as usize),
+                file.relative_path,
+                file.dir_name,
+                note
+            )?;
+        }
+        writeln!(
+            out,
+            "Total: {} file(s), {} bytes, ~{} tokens",
+            format_thousands(self.files.len()),
+            format_thousands(total as usize),
+            format_thousands(tokens)
+        )?;
+        let excluded: Vec<String> = self
+            .excluded
+            .iter()
+            .map(|(reason, count)| format!("{} {}", format_thousands(*count), reason.label()))
+            .collect();
+        let placeholders: Vec<String> = placeholders
+            .iter()
+            .map(|(note, count)| format!("{} {}", format_thousands(*count), note))
+            .collect();
+        for (heading, counts) in [("Excluded", excluded), ("Placeholders", placeholders)] {
+            if counts.is_empty() {
+                writeln!(out, "{}: none", heading)?;
+            } else {
+                writeln!(out, "{}: {}", heading, counts.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The collected trees and file contents, ready to render.
+#[derive(Clone, Debug)]
+pub struct Summary {
+    /// One tree per scanned directory, in the order they were given.
+    pub trees: Vec<TreeSection>,
+    /// Every selected file in output order.
+    pub files: Vec<FileSection>,
+    /// What the token budget dropped; all zero without `max_tokens`.
+    pub trimmed: TrimSummary,
+}
+
+impl Summary {
+    /// Totals over `files`, as shown in the `stats` footer.
+    pub fn stats(&self) -> SummaryStats {
+        SummaryStats::from_sections(self.trees.len(), &self.files)
+    }
+
+    /// Renders the report in the given format.
+    pub fn render(&self, format: OutputFormat, options: &RenderOptions) -> String {
+        match format {
+            OutputFormat::Text => render::render_text(&self.trees, &self.files, options),
+            OutputFormat::Markdown => render::render_markdown(&self.trees, &self.files, options),
+            OutputFormat::Xml => render::render_xml(&self.trees, &self.files, options),
+            OutputFormat::Jsonl => render::render_jsonl(&self.trees, &self.files, options),
+            OutputFormat::Html => render::render_html(&self.trees, &self.files, options),
+        }
+    }
+
+    /// Writes the report to `out` as `render` would give it, reading the text of streamed
+    /// files a chunk at a time instead of holding it, and returns the estimated token
+    /// count of the written text.
+    pub fn write<W: Write + ?Sized>(
+        &self,
+        format: OutputFormat,
+        options: &RenderOptions,
+        out: &mut W,
+    ) -> io::Result<usize> {
+        render::write_report(format, &self.trees, &self.files, options, out)
+    }
+
+    /// Renders the report as parts within `limit` each, never splitting a file's block.
+    /// Every part starts with a `Part X of Y` line; the trees are in the first part.
+    pub fn render_parts(
+        &self,
+        format: OutputFormat,
+        options: &RenderOptions,
+        limit: SplitLimit,
+    ) -> Vec<String> {
+        render::render_parts(format, &self.trees, &self.files, options, limit)
+    }
+
+    /// Writes the report to `output`: streamed into one file or stdout, or, when `output`
+    /// is split, rendered whole with `render_parts` and written as numbered parts.
+    pub fn write_to(
+        &self,
+        format: OutputFormat,
+        options: &RenderOptions,
+        output: &ReportOutput,
+    ) -> Result<WrittenReport, Box<dyn Error>> {
+        match output.split_limit() {
+            Some(limit) => output.write_parts(&self.render_parts(format, options, limit)),
+            None => output.write_with(format, |out| Ok(self.write(format, options, out)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_support {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    pub(crate) struct TestTempDir {
+        pub(crate) path: PathBuf,
+    }
+
+    impl TestTempDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let unique = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos();
+            let path =
+                env::temp_dir().join(format!("oreuit_{}_{}_{}", name, process::id(), unique));
+            fs::create_dir_all(&path).unwrap();
+            Self { path }
+        }
+
+        pub(crate) fn write_file(&self, relative: &str, content: &str) -> PathBuf {
+            let path = self.path.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).unwrap();
+            }
+            fs::write(&path, content).unwrap();
+            path
+        }
+    }
+
+    impl Drop for TestTempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
@@ -1,15 +1,31 @@
-use clap::Parser;
-use encoding_rs::SHIFT_JIS;
-use serde::{Deserialize, Serialize};
+mod interactive;
+mod progress_line;
+mod report;
+mod watch;
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use globset::GlobBuilder;
+use interactive::pick_files;
+use oreuit::{
+    check_encoding_label, Config, FileOrder, GitHubRepo, HeaderStyle, HiddenFiles, OutputFormat,
+    ProjectConfig, RemoteCheckout, ReportOutput, SplitLimit, Summarizer, SummaryOptions,
+    TocPlacement, TreeDetail, TrimStrategy, XmlLayout, XmlSkipped, DEFAULT_ALLOWED_EXTENSIONS,
+    DEFAULT_IGNORE_DIRS, PROJECT_CONFIG_FILENAME,
+};
+use report::write_report;
 use std::collections::HashSet;
+use std::env;
 use std::error::Error;
+use std::ffi::OsString;
+use std::fmt;
 use std::fs;
-use std::io::Read;
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
-#[macro_use]
-extern crate lazy_static;
-
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
 use walkdir::WalkDir;
+use watch::watch;
 
 const SHORT_ABOUT: &str = "Generate a text snapshot of directory trees and file contents.";
 const LONG_ABOUT: &str = r#"oreuit scans one or more directories and produces a plain-text report with two sections:
@@ -24,36 +40,173 @@ Use `--help` for filtering precedence, config-mode behavior, default lists, plac
 const SHORT_AFTER_HELP: &str = r#"Run `oreuit --help` for filtering precedence, config-mode behavior,
 default lists, placeholder outputs, and usage examples."#;
 const LONG_AFTER_HELP: &str = r#"Selection precedence (highest first):
-  1. Whitelisted filenames are always included.
-  2. Ignored filenames are excluded if they were not whitelisted.
-  3. Ignored extensions are excluded.
-  4. If an extension allowlist is active, only those extensions are kept.
-  5. If no extension allowlist is active (for example, an empty `whitelist.extensions`
+  1. Paths matching `--exclude-glob` are excluded, even if their filename is whitelisted.
+  2. Whitelisted filenames are always included.
+  3. Paths matching `--exclude-regex` are excluded if their filename was not whitelisted.
+  4. Ignored filenames are excluded if they were not whitelisted.
+  5. Ignored extensions are excluded.
+  6. If `--include-glob` is given, only files matching one of the globs are kept and
+     the extension allowlist below is not consulted.
+  7. If an extension allowlist is active, only those extensions are kept.
+  8. If no extension allowlist is active (for example, an empty `whitelist.extensions`
      in a TOML config), all non-ignored extensions and all extensionless files are eligible.
 
 Matching rules:
   - `--ignore-files` and `--whitelist-filenames` match basenames only, not relative paths.
+    Whitelist entries may be globs such as `Dockerfile*`, and `--whitelist-case-insensitive`
+    ignores their case.
   - `--ignore-dirs` and `blacklist.directories` match directory names only.
+  - Files and directories whose name starts with `.` are skipped unless `--hidden include`
+    is given or the name is whitelisted (`--whitelist-filenames`, or one of the default
+    extensionless filenames below).
   - Extension strings are normalized, so `rs`, `.rs`, and ` RS ` all mean `.rs`.
+  - `--include-glob` and `--exclude-glob` match paths relative to each scanned directory.
+    Both can be repeated and take comma-separated lists; a pattern without `/` matches
+    names at any depth.
+  - `--exclude-regex` matches the same relative path, with `/` separators, anywhere in the
+    path unless anchored with `^` or `$`. Directories stay in the tree.
   - When an extension allowlist is active, these extensionless filenames are also eligible
     by default: `.gitignore`, `.gitattributes`, `Dockerfile`, `LICENSE`, `Makefile`,
-    `README`, `justfile`.
+    `README`, `justfile`. So is any other extensionless file whose first line is a `#!`
+    line, such as `bin/deploy` starting with `#!/bin/bash`; Markdown tags it by the
+    interpreter (`bash`).
+  - `--respect-gitignore` additionally skips paths matched by `.gitignore` files, including
+    nested ones; `!pattern` re-includes paths the same way git does.
+  - A `.oreuitignore` at the root of a scanned directory is always applied with the same
+    syntax, unless `--no-oreuitignore` is given.
+  - `--ignore-file-pattern .dockerignore,.npmignore` applies other ignore files with the
+    same syntax, each to its own subtree.
+  - `--git-tracked` keeps only files listed by `git ls-files`, before any rule above; an
+    untracked file is left out even if its name is whitelisted. `--changed-since REF`
+    likewise keeps only files changed since REF.
+
+Remote repositories:
+  - `-d https://github.com/owner/repo[#branch]` shallow-clones the repository into a
+    temporary directory (authenticating with `GITHUB_TOKEN` if set), labels it
+    `owner/repo`, and deletes the clone when oreuit exits.
 
 Output behavior:
-  - Files larger than `--max-size` produce `[File size exceeds limit; skipped]`.
-  - Files with a NUL byte in the first 1024 bytes produce `[Binary file skipped]`.
-  - oreuit reads UTF-8 first, then falls back to Shift_JIS.
+  - Files larger than `--max-size` produce `[File size exceeds limit; skipped]`;
+    `--max-size-for .json=65536,.lock=10000` sets other limits for some extensions.
+  - Files larger than `--stream-threshold` (4 MiB) are read in chunks while the report is
+    written instead of being held in memory; `0` turns this off.
+  - Files whose first 1024 bytes contain a NUL byte, or more than 10% control characters,
+    produce `[Binary file skipped]`; `--binary-preview 64` shows their first 64 bytes as a
+    hex dump instead.
+  - `--embed-images` includes `.png`, `.jpg`, and other images of up to 256 KiB as a
+    `data:image/png;base64,...` line; larger ones produce `[image too large: 4.2 MB]`.
+  - `--notebooks` includes `.ipynb` files as their markdown and fenced code cells, without
+    outputs; a notebook that cannot be parsed produces `[Could not parse notebook]`.
+  - `--archive-listing` lists the members and sizes of `.zip`, `.tar.gz`, and similar
+    archives without extracting them; unreadable ones produce `[Could not read archive]`.
+  - oreuit reads UTF-8 first; other files are read as ISO-2022-JP, Shift_JIS, EUC-JP, or
+    Latin-1, whichever looks most plausible. `--encoding` sets a fixed chain instead.
+    Files starting with a UTF-16 byte order mark are read as UTF-16, and `--show-encoding`
+    names the encoding used in each file header.
   - If decoding still fails, oreuit emits `[Cannot decode file content]`.
+  - With `--follow-symlinks`, links whose target is missing produce `[Broken symlink]`.
+  - File headers end with an estimated token count (for example `~1,243 tokens`) unless
+    `--no-token-counts` is given; the estimated total is printed to stderr.
+  - Files smaller than `--min-size`, or last modified before `--since 7d`, are omitted
+    without a placeholder.
+  - `--line-numbers` prefixes each content line with its number, e.g. ` 42 | `.
+  - `--head-lines N` keeps the first N lines of each file and appends
+    `[... truncated, M more lines]`; `--tail-lines N` keeps the last N lines after a
+    `[... first M lines omitted]` banner. Together they keep both ends around a gap marker;
+    `--max-lines-per-file N` splits N between the two.
+  - With `--grep` (or `--contains`), only files whose text matches the pattern are kept, in
+    both the tree and the contents; `--grep-exclude` drops files whose text matches, and
+    wins over `--grep`.
+  - With `--dedupe`, a file with the same text as an earlier one produces
+    `[Duplicate of <path> (in <dir>)]`.
+  - With `--max-tokens`, files dropped to fit the estimated budget produce
+    `[omitted: token budget exceeded]`.
+  - `--format markdown` emits fenced code blocks tagged by language instead of dashed headers.
+  - `--format jsonl` writes one JSON object per line, the trees first and then one per
+    file, as the files are read.
+  - `--format html` writes a standalone page with a collapsible section per file.
+  - `--header-style ascii` replaces the full-width brackets with `<...>`; `minimal` drops the
+    separators and puts `## path (in dir)` before each file.
   - The final report is written to `--output`, unless `-c/--clipboard` is used successfully.
+    `--append` adds it after the file's content, behind a separator line, instead of
+    replacing it.
+  - `-o -` or `--stdout` writes the report to stdout, byte for byte as it would be written
+    to a file, and prints no `Output completed` message.
+  - `--split-size BYTES` writes numbered parts (`summary.001.txt`, ...) that each start with
+    `Part X of Y`, without splitting any file's content. `--split-tokens N` does the same
+    with a limit in estimated tokens.
+  - `--tree-details size,mtime` appends e.g. ` (14.2 KB, 2024-05-02)` to files in the tree.
+  - `--tree-dir-totals` appends e.g. ` (3.4 MB, 212 files)` to directories in the tree.
+  - `--tree-stats` does both, with sizes and line counts: `├── main.rs (3.2 KB, 142 lines)`.
+  - `--ascii-tree` draws the tree with `|-- `, `` `-- ``, and `|   ` for terminals and fonts
+    without box-drawing characters.
+  - `--strip-comments` removes comments from Rust, C-family, Go, JS/TS, Python, Ruby, and
+    TOML files, leaving string literals alone.
+  - `--normalize-newlines` converts CRLF and lone CR line endings in file contents to LF.
+  - `--squeeze-blank` collapses runs of blank lines in file contents into one and trims
+    blank lines at the start and end of each file.
+  - `--trim-whitespace` strips trailing whitespace and collapses runs of three or more blank
+    lines into one, leaving Markdown files alone unless `--trim-markdown` is given.
+  - `--lang-map .gotmpl=go-template,.hcl=hcl` extends the language tags of Markdown code
+    fences; `--show-language` also notes them in file headers.
+  - `--relative-to PATH` makes file header paths relative to PATH instead of to each
+    scanned directory.
+  - `--git-blame-summary` notes the last commit of each file tracked by git in its header,
+    e.g. `main.rs (in src) (1a2b3c4 by Dana, 2024-05-01)`.
+  - `--hashes` notes the SHA-256 of each file's bytes in its header, e.g.
+    `main.rs (in src) [sha256:3f1c9a0b7d2e]`; `--hash-manifest PATH` also writes the full
+    hashes to PATH in `sha256sum` format.
+  - Files are emitted in path order within each directory; `--sort size-desc` (or `size`,
+    `mtime`, `mtime-desc`, `extension`) changes that order but not the tree.
+    `--pin PATH` puts a file first, marked `[PINNED]` in its header.
+  - `--toc` lists the included files with their size and line count before the trees
+    (`--toc after` puts the list after them).
+  - `--dry-run` lists the selected files with their sizes instead of writing a report.
+  - `--interactive` shows the same list on the terminal and lets you deselect files before
+    the report is written; `/TEXT` narrows the list with a fuzzy match.
+    `--print-selection` prints the chosen paths instead, for `--files-from`.
+  - `--watch` writes the report again each time a selected file changes, until Ctrl-C.
+  - `--tree-only` (or `--no-content`) writes only the directory structure without reading
+    any file; `--bare` drops the headings as well. `--no-tree` leaves out the directory
+    structure and writes only the file contents.
+  - `-v/--verbose` logs each skipped file or directory to stderr with the reason, e.g.
+    `Skipped src/logo.png: ignored extension`; `--report PATH` writes the same list to
+    PATH as JSON.
+  - While scanning and reading, a progress line is drawn on stderr when it is a terminal;
+    `-q/--quiet` turns it off and `--progress` turns it back on. Warnings are still printed.
+  - `--stats` appends a footer with file, byte, line, and token totals after the contents.
+    The same totals are printed to stderr after the report is written, e.g.
+    `Stats: 1 dir(s), 14 file(s), 48,210 bytes, ...`, unless `--no-stats` is given.
+  - `--gzip` compresses the report and appends `.gz` to the output file name.
+  - `--tee` writes `--output` and also copies the report to the clipboard; when copying
+    fails, the file is kept.
   - `-c/--clipboard` requires a binary built with `--features clipboard`. Without that
     feature, oreuit prints an explanatory error to stderr and does not write a file.
 
+Exit status:
+  - 0 on success; 1 for invalid arguments or config, a failed clone, or no valid directory.
+  - 2 when no file matched the filters. The report is still written, without contents;
+    `--dry-run`, `--print-selection`, and `--interactive` exit with 2 as well when they end
+    up with no file.
+  - 3 when the report could not be written or copied to the clipboard.
+  - Errors and warnings go to stderr.
+
 Config mode:
-  - `--config` replaces the filter-related CLI flags `--extensions`,
+  - Without `--config`, `.oreuit.toml` in the first `--directory` is loaded if it exists,
+    else `.oreuit.toml` or `oreuit.toml` in the current directory.
+  - Precedence, highest first: flags given on the command line, then the config file, then
+    the built-in defaults. This holds for every option except the filter flags that
+    `[whitelist]` / `[blacklist]` tables replace (below). A key that conflicts with a flag
+    on the command line, such as `no_stats` with `--stats`, is ignored; the other values
+    are checked like the flags they stand for.
+  - Top-level keys are named like the long flags in snake_case (`max_size = 1048576`,
+    `format = "markdown"`, `append = true`); repeatable options take arrays
+    (`pin = ["README.md"]`). `extensions_add` and `ignore_dirs_add` are the `+,` forms of
+    `--extensions` and `--ignore-dirs`.
+  - `[whitelist]` / `[blacklist]` tables replace the filter-related CLI flags `--extensions`,
     `--ignore-extensions`, `--ignore-dirs`, `--ignore-files`, and
     `--whitelist-filenames`.
-  - These options still work with `--config`: `--directory`, `--output`, `--max-size`,
-    and `--clipboard`.
+  - Unknown keys and values of the wrong type are errors that quote the offending line.
   - `--generate-config` prints the built-in defaults as TOML to stdout and exits
     immediately, before directory validation or scanning.
 
@@ -78,41 +231,6 @@ Examples:
   oreuit --generate-config > oreuit.toml
   oreuit --config oreuit.toml -d . -o summary_from_config.txt"#;
 
-#[derive(Debug, Deserialize, Serialize, Default)]
-struct WhitelistConfig {
-    #[serde(default)]
-    extensions: Vec<String>,
-    #[serde(default)]
-    files: Vec<String>,
-}
-
-#[derive(Debug, Deserialize, Serialize, Default)]
-struct BlacklistConfig {
-    #[serde(default)]
-    extensions: Vec<String>,
-    #[serde(default)]
-    files: Vec<String>,
-    #[serde(default)]
-    directories: Vec<String>,
-}
-
-#[derive(Debug, Deserialize, Serialize, Default)]
-struct Config {
-    #[serde(default)]
-    whitelist: WhitelistConfig,
-    #[serde(default)]
-    blacklist: BlacklistConfig,
-}
-
-struct FilterRules {
-    allowed: HashSet<String>,
-    ignore_exts: HashSet<String>,
-    ignore_dirs: HashSet<String>,
-    whitelist_filenames: HashSet<String>,
-    ignore_files: HashSet<String>,
-    extensionless_allowed: HashSet<String>,
-}
-
 /// Tool to summarize directory structure and file contents
 #[derive(Parser, Debug)]
 #[clap(
@@ -128,8 +246,8 @@ struct Args {
         short = 'd',
         long = "directory",
         default_value = ".",
-        help = "Comma-separated directories to scan",
-        long_help = "Comma-separated directories to scan.\n\nEach entry is trimmed before use.\nNon-existent paths and non-directory paths are skipped with a warning.\nIf every entry is invalid, oreuit prints an error and exits without generating output."
+        help = "Comma-separated directories (or files) to scan",
+        long_help = "Comma-separated directories to scan.\n\nEach entry is trimmed before use.\nNon-existent paths and paths that are neither a directory nor a file are skipped with a warning.\nIf every entry is invalid, oreuit prints an error and exits without generating output.\n\nFiles:\n  -d src,Cargo.toml,README.md\n\n  - A file entry is included even if the extension, ignore, or glob rules would leave it\n    out; the size and binary checks still apply.\n  - It is labelled with its parent directory, e.g. `Cargo.toml (in .)`, and gets a tree\n    of its own with just that file.\n\nRemote repositories:\n  -d https://github.com/owner/repo\n  -d https://github.com/owner/repo#branch\n\n  - The repository is shallow-cloned into a temporary directory, which is removed when\n    oreuit exits, also after an error.\n  - The tree and file headers show `owner/repo` instead of the temporary path.\n  - `GITHUB_TOKEN`, when set, is used to authenticate, so private repositories work.\n  - A failed clone is an error.\n\nGlobs:\n  -d 'crates/*'\n  -d 'packages/*/src,tools/**/fixtures'\n\n  - An entry with `*`, `?`, `[`, or `{` that is not an existing path is expanded to the\n    directories it matches, in path order. `*` and `?` stay within one path component;\n    `**` matches any number of directories. Hidden directories are not matched.\n  - A glob matching no directory is skipped with a warning.\n  - A directory given more than once, by overlapping globs or otherwise, is scanned once."
     )]
     directories: String,
 
@@ -137,7 +255,7 @@ struct Args {
         short = 'e',
         long = "extensions",
         help = "Allowed extensions. `+,` adds to defaults; otherwise replaces them",
-        long_help = "Allowed extensions, separated by commas.\n\nExamples:\n  --extensions .rs,.toml\n  --extensions +,.json,.vue\n\nRules:\n  - Prefix with `+,` to add to the built-in allowlist.\n  - Without `+,`, the provided list replaces the built-in allowlist.\n  - Extension strings are normalized, so `rs`, `.rs`, and ` RS ` are treated as `.rs`.\n  - If this option is omitted, oreuit uses the built-in allowlist.\n  - Extensionless files are controlled by a separate built-in rule and are not listed here.\n  - This option is ignored when `--config` is used."
+        long_help = "Allowed extensions, separated by commas.\n\nExamples:\n  --extensions .rs,.toml\n  --extensions +,.json,.vue\n\nRules:\n  - Prefix with `+,` to add to the built-in allowlist.\n  - Without `+,`, the provided list replaces the built-in allowlist.\n  - Extension strings are normalized, so `rs`, `.rs`, and ` RS ` are treated as `.rs`.\n  - If this option is omitted, oreuit uses the built-in allowlist.\n  - Extensionless files are controlled by a separate built-in rule and are not listed here.\n  - This option is ignored when the config file has `[whitelist]` or `[blacklist]` tables."
     )]
     extensions: Option<String>,
 
@@ -146,7 +264,7 @@ struct Args {
         long = "ignore-extensions",
         default_value = ".bin,.zip,.tar,.gz,.7z,.rar,.exe,.dll,.so,.dylib,.a,.lib,.obj,.o,.class,.jar,.war,.ear,.ipynb,.jpg,.jpeg,.png,.gif",
         help = "Extensions to exclude after normalization",
-        long_help = "Extensions to exclude after normalization.\n\nExamples:\n  --ignore-extensions .lock,.svg\n  --ignore-extensions ''\n\nRules:\n  - Values are normalized the same way as `--extensions`.\n  - The built-in ignore list is used by default.\n  - Passing an empty value disables extension-based excludes.\n  - Ignored extensions are still overridden by whitelisted filenames.\n  - This option is ignored when `--config` is used."
+        long_help = "Extensions to exclude after normalization.\n\nExamples:\n  --ignore-extensions .lock,.svg\n  --ignore-extensions ''\n\nRules:\n  - Values are normalized the same way as `--extensions`.\n  - The built-in ignore list is used by default.\n  - Passing an empty value disables extension-based excludes.\n  - Ignored extensions are still overridden by whitelisted filenames.\n  - This option is ignored when the config file has `[whitelist]` or `[blacklist]` tables."
     )]
     ignore_extensions: String,
 
@@ -154,7 +272,7 @@ struct Args {
         long = "ignore-files",
         default_value = "",
         help = "Basename-only file exclusions. Whitelist still wins",
-        long_help = "Comma-separated filenames to ignore. Matching is by basename only, not by relative path.\n\nExamples:\n  --ignore-files Cargo.lock,summary.txt_example\n\nRules:\n  - This check runs before extension allowlisting.\n  - Whitelisted filenames still win over ignored filenames.\n  - This option is ignored when `--config` is used."
+        long_help = "Comma-separated filenames to ignore. Matching is by basename only, not by relative path.\n\nExamples:\n  --ignore-files Cargo.lock,summary.txt_example\n\nRules:\n  - This check runs before extension allowlisting.\n  - Whitelisted filenames still win over ignored filenames.\n  - This option is ignored when the config file has `[whitelist]` or `[blacklist]` tables."
     )]
     ignore_files: String,
 
@@ -162,11 +280,19 @@ struct Args {
         short = 'o',
         long = "output",
         default_value = "summary.txt",
-        help = "Write the final report to this file",
-        long_help = "Write the final report to this file.\n\nRules:\n  - The default output path is `summary.txt`.\n  - This option is ignored when `--generate-config` is used, because that mode writes TOML to stdout.\n  - This option is also bypassed when `--clipboard` succeeds."
+        help = "Write the final report to this file (`-` for stdout)",
+        long_help = "Write the final report to this file.\n\nRules:\n  - The default output path is `summary.txt`.\n  - `-` writes the report to stdout, exactly as it would be written to a file, and skips the `Output completed` message, so the report can be piped. `-` cannot be combined with `--clipboard`.\n  - This option is ignored when `--generate-config` is used, because that mode writes TOML to stdout.\n  - This option is also bypassed when `--clipboard` succeeds."
     )]
     output: String,
 
+    #[clap(
+        long = "append",
+        conflicts_with_all = ["clipboard", "split_size", "split_tokens"],
+        help = "Append the report to `--output` instead of replacing it",
+        long_help = "Append the report to `--output` instead of replacing it, to collect several runs in\none file.\n\nRules:\n  - When the file already has content, a separator goes before the new report: a line of\n    `=` (text), a `---` rule (markdown), or an `<!-- oreuit: appended run -->` comment\n    (xml and html). JSON Lines records need none.\n  - A missing file is created as usual.\n  - With `--gzip`, each run is a gzip member of its own; `gzip -d` reads them back as one\n    text.\n  - Writing to stdout is not affected.\n  - This option cannot be combined with `--clipboard`, `--split-size`, or\n    `--split-tokens`."
+    )]
+    append: bool,
+
     #[clap(
         long = "max-size",
         default_value = "10485760",
@@ -183,11 +309,19 @@ struct Args {
     )]
     clipboard: bool,
 
+    #[clap(
+        long = "tee",
+        conflicts_with_all = ["clipboard", "stdout", "split_size", "split_tokens"],
+        help = "Write the report to `--output` and also copy it to the clipboard",
+        long_help = "Write the report to `--output` and also copy it to the clipboard.\n\nExample:\n  oreuit -d src -o summary.txt --tee\n    Output completed: summary.txt\n    Output content has been copied to the clipboard.\n\nRules:\n  - Requires a binary built with `--features clipboard` for the clipboard half.\n  - The file is written first and is kept when copying fails; the error then says the\n    report was still written, and the exit status is 3.\n  - The clipboard gets the plain text, also with `--gzip`; with `--append` it gets only\n    this run's report.\n  - This option cannot be combined with `--clipboard`, `-o -`, `--stdout`,\n    `--split-size`, or `--split-tokens`, nor with `--format jsonl` unless `--tree-only`\n    is given."
+    )]
+    tee: bool,
+
     #[clap(
         short = 'I',
         long = "ignore-dirs",
         help = "Directory-name exclusions. `+,` adds to defaults",
-        long_help = "Directory names to ignore, separated by commas.\n\nExamples:\n  --ignore-dirs build\n  --ignore-dirs +,temp,.serena\n\nRules:\n  - Matching is by directory name only, not by relative path.\n  - Prefix with `+,` to add to the built-in ignore list.\n  - Without `+,`, the provided list replaces the built-in ignore list.\n  - Ignored directories are excluded from both tree output and file-content collection.\n  - This option is ignored when `--config` is used."
+        long_help = "Directory names to ignore, separated by commas.\n\nExamples:\n  --ignore-dirs build\n  --ignore-dirs +,temp,.serena\n\nRules:\n  - Matching is by directory name only, not by relative path.\n  - Prefix with `+,` to add to the built-in ignore list.\n  - Without `+,`, the provided list replaces the built-in ignore list.\n  - Ignored directories are excluded from both tree output and file-content collection.\n  - This option is ignored when the config file has `[whitelist]` or `[blacklist]` tables."
     )]
     ignore_dirs: Option<String>,
 
@@ -196,14 +330,14 @@ struct Args {
         long = "whitelist-filenames",
         default_value = "Dockerfile,Makefile,justfile",
         help = "Basename-only files that are always included",
-        long_help = "Comma-separated filenames to always include. Matching is by basename only, not by relative path.\n\nExamples:\n  --whitelist-filenames Dockerfile,Makefile,justfile\n\nRules:\n  - Whitelisted filenames are included even if their extension is not in the allowlist.\n  - Whitelisted filenames also override `--ignore-files`.\n  - Ignored directories still prevent traversal into that directory.\n  - This option is ignored when `--config` is used."
+        long_help = "Comma-separated filenames to always include. Matching is by basename only, not by relative path.\n\nExamples:\n  --whitelist-filenames Dockerfile,Makefile,justfile\n  --whitelist-filenames 'Dockerfile*,*.mk'\n\nRules:\n  - Entries with `*`, `?`, `[`, or `{` are glob patterns matched against the basename;\n    other entries must match exactly (see `--whitelist-case-insensitive`).\n  - Whitelisted filenames are included even if their extension is not in the allowlist\n    or is ignored.\n  - Whitelisted filenames also override `--ignore-files`.\n  - Ignored directories still prevent traversal into that directory.\n  - This option is ignored when the config file has `[whitelist]` or `[blacklist]` tables."
     )]
     whitelist_filenames: String,
 
     #[clap(
         long = "config",
-        help = "Load whitelist/blacklist filters from a TOML file",
-        long_help = "Load whitelist/blacklist filters from a TOML file.\n\nRules:\n  - `--config` replaces the filter-related CLI flags `--extensions`, `--ignore-extensions`, `--ignore-dirs`, `--ignore-files`, and `--whitelist-filenames`.\n  - `--directory`, `--output`, `--max-size`, and `--clipboard` still apply.\n  - `whitelist.extensions` is normalized the same way as CLI extensions.\n  - `whitelist.files` and `blacklist.files` match basenames only.\n  - `blacklist.directories` matches directory names only.\n  - If `whitelist.extensions` is empty, oreuit does not apply an extension allowlist.\n  - Missing files and TOML parse errors are reported with different error messages."
+        help = "Load option defaults and filters from a TOML file (default: ./.oreuit.toml)",
+        long_help = "Load option defaults and whitelist/blacklist filters from a TOML file.\n\nExample `.oreuit.toml`:\n  extensions_add = [\".vue\"]\n  ignore_dirs_add = [\"coverage\"]\n  ignore_files = [\"Cargo.lock\"]\n  max_size = 1048576\n  format = \"markdown\"\n  output = \"summary.md\"\n\nRules:\n  - Without this option, `.oreuit.toml` in the first `--directory` entry is used if present,\n    then `.oreuit.toml` or `oreuit.toml` in the current directory.\n  - Top-level keys are option long names in snake_case; lists are TOML arrays. Options given\n    on the command line override them.\n  - `extensions_add` and `ignore_dirs_add` add to the built-in lists, like the `+,` prefix;\n    with `extensions` or `ignore_dirs` they extend that list instead. A list starting with\n    \"+\" (`extensions = [\"+\", \".vue\"]`) adds to the built-in list as well.\n  - `[whitelist]` / `[blacklist]` tables (as printed by `--generate-config`) replace the filter-related CLI flags `--extensions`, `--ignore-extensions`, `--ignore-dirs`, `--ignore-files`, and `--whitelist-filenames`.\n  - `whitelist.extensions` is normalized the same way as CLI extensions.\n  - `whitelist.files` and `blacklist.files` match basenames only.\n  - `blacklist.directories` matches directory names only.\n  - If `whitelist.extensions` is empty, oreuit does not apply an extension allowlist.\n  - Missing files and TOML parse errors are reported with different error messages; parse\n    errors and unknown keys quote the offending line."
     )]
     config: Option<String>,
 
@@ -213,424 +347,967 @@ struct Args {
         long_help = "Print the built-in filter defaults as TOML to stdout and exit.\n\nRules:\n  - This happens before directory validation, config loading, scanning, output-file writing, or clipboard handling.\n  - Use this to bootstrap a config file for `--config`."
     )]
     generate_config: bool,
+
+    #[clap(
+        long = "respect-gitignore",
+        help = "Skip paths ignored by .gitignore files",
+        long_help = "Skip paths ignored by .gitignore files.\n\nRules:\n  - Every `.gitignore` found while scanning is applied to its own subtree.\n  - `.gitignore` files between the scanned directory and the repository root also apply.\n  - Nested files and negation patterns (`!pattern`) follow git semantics.\n  - This composes with `--ignore-dirs`, `--ignore-files`, and the extension filters."
+    )]
+    respect_gitignore: bool,
+
+    #[clap(
+        long = "include-glob",
+        value_name = "PATTERNS",
+        help = "Globs for files to include, relative to each directory (repeatable)",
+        long_help = "Comma-separated glob patterns for files to include, matched against the path relative to each scanned directory.\n\nExamples:\n  --include-glob 'src/**/*.rs,migrations/*.sql'\n  --include-glob 'src/**/*.rs' --include-glob 'migrations/*.sql'\n\nRules:\n  - The option can be repeated; all patterns are combined.\n  - When given, the patterns replace the extension allowlist and the extensionless-file rule.\n  - `*` and `?` do not cross `/`; use `**` to match any number of directories.\n  - A pattern without `/` (for example `*.rs`) matches file names at any depth.\n  - Character classes such as `[ab]` and `[!a]` are supported.\n  - Whitelisted filenames are still included; ignored filenames and extensions are still excluded.\n  - Invalid patterns are reported as an error before scanning starts."
+    )]
+    include_glob: Vec<String>,
+
+    #[clap(
+        long = "format",
+        value_enum,
+        default_value = "text",
+        help = "Output format",
+        long_help = "Output format.\n\nValues:\n  - `text`: the default report with `＜Directory Structure＞` and dashed-line file headers.\n  - `markdown`: trees in plain fenced blocks and each file as a `### path` heading followed by a fenced block tagged with a language derived from the extension (for example `.rs` -> `rust`). Unknown extensions get an untagged fence.\n  - `xml`: `<documents><document index=\"1\"><source>…</source><document_contents>…</document_contents></document>…</documents>`. The trees come first as the document with source `DIRECTORY_TREE`; `&`, `<`, and `>` are escaped. See `--xml-skipped` and `--xml-layout`.\n  - `jsonl`: JSON Lines, written while the files are read instead of after. The first line\n    is `{\"trees\":[{\"directory\":…,\"tree\":…}]}`, then one line per file:\n      {\"path\":\"src/lib.rs\",\"directory\":\"crate\",\"size\":1234,\"content\":\"…\"}\n    `size` is the size on disk; a file with a placeholder as `content` also has `\"skipped\"`\n    (`binary`, `too-large`, ...). Token counts, `--toc`, and `--stats` do not apply.\n    With `--grep`, `--grep-exclude`, `--dedupe`, `--max-tokens`, or a split option the\n    lines are written after reading as with the other formats; split parts have no\n    `Part X of Y` line. This format cannot be combined with `--clipboard`.\n  - `html`: a standalone page with a small inline stylesheet and no scripts. The trees\n    are in `<pre>` blocks and each file is a collapsed\n    `<details><summary>path (size)</summary><pre>…</pre></details>` block with an `id`\n    made from its path, e.g. `#src-main-rs`; characters other than letters, digits,\n    `-`, and `_` become `-`. Contents are HTML-escaped. Split parts are complete pages."
+    )]
+    format: OutputFormat,
+
+    #[clap(
+        long = "exclude-glob",
+        value_name = "PATTERNS",
+        help = "Globs for paths to exclude, relative to each directory (repeatable)",
+        long_help = "Comma-separated glob patterns for files and directories to exclude, matched against the path relative to each scanned directory.\n\nExamples:\n  --exclude-glob '**/fixtures/**,*.generated.ts,src/vendor/*'\n  --include-glob 'src/**/*.rs' --exclude-glob '**/*_test.rs'\n\nRules:\n  - The option can be repeated; all patterns are combined.\n  - Excluded files disappear from both the tree and the contents section.\n  - A matching directory is pruned together with its subtree; `dir/**` also prunes `dir` itself.\n  - Exclude globs are checked after `--include-glob` and before whitelisted filenames, so they win over both.\n  - Glob syntax and error handling match `--include-glob`."
+    )]
+    exclude_glob: Vec<String>,
+
+    #[clap(
+        long = "max-tokens",
+        help = "Stop adding file contents once the estimated token count reaches this budget",
+        long_help = "Stop adding file contents once the estimated token count reaches this budget.\n\nRules:\n  - Tokens are estimated with a whitespace-plus-punctuation heuristic, not a real tokenizer.\n  - The directory trees are always included and count toward the budget.\n  - Dropped files keep their header and position, but their content becomes the single line\n    `[omitted: token budget exceeded]`. Which files are dropped is set by `--trim-strategy`.\n  - The number of dropped files is printed to stderr, followed by the estimated token count\n    of the final report, as on every run."
+    )]
+    max_tokens: Option<usize>,
+
+    #[clap(
+        short = 'j',
+        long = "jobs",
+        visible_alias = "threads",
+        help = "Number of threads used to read files (default: number of CPUs)",
+        long_help = "Number of threads used to read files. `--threads` is an alias.\n\nRules:\n  - Defaults to the number of logical CPUs; `0` also selects the default.\n  - Size checks, binary detection, and decoding run in parallel; file contents and\n    placeholders are always emitted in the same sorted order, regardless of thread count."
+    )]
+    jobs: Option<usize>,
+
+    #[clap(
+        long = "max-depth",
+        help = "Limit how deep the tree and file collection descend (0 = top level only)",
+        long_help = "Limit how deep the tree and file collection descend below each scanned directory.\n\nRules:\n  - `0` keeps only the top-level entries of each directory.\n  - Files deeper than the limit are not collected.\n  - Directories at the limit still appear in the tree; when selected files were elided\n    below them they end with `… (N files below)`."
+    )]
+    max_depth: Option<usize>,
+
+    #[clap(
+        long = "xml-skipped",
+        value_enum,
+        default_value = "placeholder",
+        help = "How `--format xml` emits binary, oversized, or undecodable files",
+        long_help = "How `--format xml` emits files whose content was skipped (binary, oversized, undecodable, or past `--max-tokens`).\n\nValues:\n  - `placeholder`: keep the document with the placeholder text as its body.\n  - `omit`: leave the document out; the remaining indexes stay consecutive.\n\nWith `--xml-layout summary`, `placeholder` emits an empty `<file>` with a `skipped`\nattribute and `omit` leaves the `<file>` out."
+    )]
+    xml_skipped: XmlSkipped,
+
+    #[clap(
+        long = "files-from",
+        value_name = "PATH",
+        help = "Read the files to summarize from PATH, one per line (`-` for stdin)",
+        long_help = "Read the files to summarize from PATH, one per line. Use `-` to read from stdin.\n\nExamples:\n  git diff --name-only main | oreuit --files-from -\n\nRules:\n  - The directory walk is skipped entirely; `--directory` and the filter options are not used.\n  - Listed files still go through the size, binary, and decoding checks.\n  - Paths are relative to the current directory and are shown as `path (in .)`.\n  - The tree is built from the listed paths only, with parent directories reconstructed.\n  - Blank lines are ignored; missing files are skipped with a warning.\n  - With `-0`, paths are separated by NUL bytes instead, as printed by `find -print0` or\n    `fd -0`."
+    )]
+    files_from: Option<String>,
+
+    #[clap(
+        short = '0',
+        long = "null",
+        requires = "files_from",
+        help = "Paths in `--files-from` are separated by NUL bytes instead of newlines",
+        long_help = "Paths in `--files-from` are separated by NUL bytes instead of newlines, so paths\ncontaining newlines work.\n\nExamples:\n  fd -0 -e rs | oreuit --files-from - -0\n  git ls-files -z src | oreuit --files-from - -0\n\nRules:\n  - Paths are used exactly as given; surrounding whitespace is not trimmed.\n  - Empty entries, such as one after a trailing NUL, are ignored."
+    )]
+    null: bool,
+
+    #[clap(
+        long = "stdout",
+        conflicts_with = "clipboard",
+        help = "Write the report to stdout; same as `-o -`",
+        long_help = "Write the report to stdout; same as `-o -`.\n\nRules:\n  - No `Output completed` message is printed, so stdout carries only the report; the\n    estimated token count and warnings still go to stderr.\n  - This option cannot be combined with `--clipboard`."
+    )]
+    stdout: bool,
+
+    #[clap(
+        long = "no-token-counts",
+        help = "Do not annotate file headers with estimated token counts",
+        long_help = "Do not annotate file headers with estimated token counts.\n\nBy default each file header ends with an estimate such as `src/main.rs (in .): ~1,243 tokens`.\nWith this flag the headers are byte-identical to earlier releases (`src/main.rs (in .):`).\nThe estimated total for the whole report is printed to stderr either way."
+    )]
+    no_token_counts: bool,
+
+    #[clap(
+        long = "min-size",
+        help = "Minimum file size to include, in bytes",
+        long_help = "Minimum file size to include, in bytes.\n\nRules:\n  - Files smaller than this limit are left out of both the tree and the contents section,\n    the same way ignored extensions are; no placeholder is emitted.\n  - `--min-size 1` drops empty files.\n  - This applies to whitelisted filenames too."
+    )]
+    min_size: Option<u64>,
+
+    #[clap(
+        long = "since",
+        value_name = "WHEN",
+        help = "Only include files modified since WHEN (`7d`, `24h`, or `2024-05-01`)",
+        long_help = "Only include files whose modification time is at or after WHEN.\n\nExamples:\n  --since 7d\n  --since 24h\n  --since 2024-05-01\n  --since 2024-05-01T09:30\n\nRules:\n  - A duration counts back from now: a number followed by `s`, `m`, `h`, `d`, or `w`.\n  - A date is `YYYY-MM-DD`, optionally followed by `THH:MM` or `THH:MM:SS`, in UTC.\n  - Older files are left out of both the tree and the contents section, like files below\n    `--min-size`; no placeholder is emitted.\n  - This applies to whitelisted filenames too."
+    )]
+    since: Option<String>,
+
+    #[clap(
+        long = "trim-strategy",
+        value_enum,
+        default_value = "tail",
+        help = "Which files `--max-tokens` drops first",
+        long_help = "Which files `--max-tokens` drops first when the report does not fit.\n\nValues:\n  - `tail`: keep the longest prefix of the sorted file list that fits; every later file is dropped.\n  - `largest`: drop the files with the most estimated tokens first until the rest fits.\n\nEither way the output keeps its sorted order and the trees are never dropped."
+    )]
+    trim_strategy: TrimStrategy,
+
+    #[clap(
+        long = "line-numbers",
+        help = "Prefix each content line with its line number",
+        long_help = "Prefix each content line with its right-aligned line number and ` | `, for example ` 42 | let x = 5;`.\n\nRules:\n  - Numbering starts at 1 in every file; the width fits the file's line count.\n  - Numbers are added after decoding, so UTF-8 and Shift_JIS text are numbered the same way.\n  - The directory tree is never numbered.\n  - CRLF line endings are written as LF, so no `\\r` is left in the numbered lines.\n  - Placeholders such as `[Binary file skipped]` are not numbered.\n  - The numbers count toward the token estimates and `--max-tokens`."
+    )]
+    line_numbers: bool,
+
+    #[clap(
+        long = "head-lines",
+        value_name = "N",
+        help = "Keep only the first N lines of each file",
+        long_help = "Keep only the first N lines of each file.\n\nRules:\n  - Longer files end with a marker such as `[... truncated, 18,654 more lines]`.\n  - Lines are counted after decoding, so multi-byte text is never cut mid-character.\n  - `0` keeps only the file headers, with no content.\n  - With `--line-numbers`, the kept lines keep their original numbers.\n  - Placeholders for skipped files are not affected."
+    )]
+    head_lines: Option<usize>,
+
+    #[clap(
+        long = "tail-lines",
+        value_name = "N",
+        help = "Keep only the last N lines of each file",
+        long_help = "Keep only the last N lines of each file, for logs and changelogs.\n\nRules:\n  - Longer files start with a banner such as `[... first 1,200 lines omitted]`.\n  - With `--head-lines`, both the first and the last lines are kept and a\n    `... [M lines omitted] ...` marker separates them.\n  - A last line without a trailing newline is kept as it is.\n  - With `--line-numbers`, the kept lines keep their original numbers.\n  - Placeholders for skipped files are not affected."
+    )]
+    tail_lines: Option<usize>,
+
+    #[clap(
+        long = "binary-threshold",
+        hide = true,
+        default_value = "0.1",
+        help = "Share of control bytes above which a file is treated as binary"
+    )]
+    binary_threshold: f64,
+
+    #[clap(
+        long = "binary-preview",
+        value_name = "BYTES",
+        default_value_t = 0,
+        help = "Show the first BYTES bytes of binary files as a hex dump",
+        long_help = "Show the first BYTES bytes of each binary file as a hex dump, instead of only\n`[Binary file skipped]`, e.g. to see its magic bytes.\n\nExample:\n  --binary-preview 32 gives\n    [Binary file: 20,480 bytes, first 32 shown]\n    00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|\n    00000010  00 00 00 40 00 00 00 40  08 06 00 00 00 aa 69 71  |...@...@......iq|\n\nRules:\n  - Rows follow `hexdump -C`: the offset, 16 bytes in hex, and the same bytes as ASCII\n    with `.` for bytes that are not printable.\n  - `0` (the default) keeps the `[Binary file skipped]` placeholder.\n  - The file still counts as binary in `--stats`, `--toc` (which shows the first line),\n    and the `skipped` attribute of XML; `--xml-layout summary` leaves its body empty."
+    )]
+    binary_preview: usize,
+
+    #[clap(
+        long = "embed-images",
+        value_name = "MAX_BYTES",
+        num_args = 0..=1,
+        default_missing_value = "262144",
+        help = "Embed images up to MAX_BYTES (default 262144) as base64 data URIs",
+        long_help = "Embed images as a base64 data URI instead of ignoring them, for models that read\nimages.\n\nExample:\n  --embed-images gives, for logo.png,\n    data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAA...\n\nRules:\n  - Applies to `.png`, `.jpg`, `.jpeg`, `.gif`, `.webp`, and `.bmp` files, which are then\n    included even though the default `--ignore-extensions` lists some of them.\n  - MAX_BYTES defaults to 262144 (256 KiB) and replaces `--max-size` for images; larger\n    images get a placeholder such as `[image too large: 4.2 MB]`.\n  - The MIME type comes from the file's magic bytes, or else its extension.\n  - `--line-numbers` and the other content options leave the data URI alone, and `--grep`\n    never matches an image."
+    )]
+    embed_images: Option<u64>,
+
+    #[clap(
+        long = "notebooks",
+        help = "Include Jupyter notebooks as their markdown and code cells",
+        long_help = "Include Jupyter notebooks (`.ipynb`) as their cells instead of ignoring them.\n\nExample:\n  A notebook with a markdown cell and a code cell becomes\n    # Loading data\n\n    ```python\n    df = pd.read_csv(\"data.csv\")\n    ```\n\nRules:\n  - `.ipynb` is included even though the default `--ignore-extensions` lists it.\n  - Markdown cells are kept as they are and code cells are fenced with the kernel's\n    language, in notebook order; outputs (with their base64 images), attachments, and raw\n    cells are dropped.\n  - A notebook that is not valid nbformat 4 JSON gets `[Could not parse notebook]`.\n  - The other content options (`--line-numbers`, `--grep`, ...) apply to the cells."
+    )]
+    notebooks: bool,
+
+    #[clap(
+        long = "archive-listing",
+        value_name = "MAX_ENTRIES",
+        num_args = 0..=1,
+        default_missing_value = "1000",
+        help = "List the members of .zip and .tar.gz archives instead of ignoring them",
+        long_help = "List the member paths and sizes of archives, like `unzip -l`, instead of ignoring\nthem.\n\nExample:\n  --archive-listing gives, for vendor.zip,\n      Length  Name\n    --------  ----\n         812  src/lib.rs\n        4096  data/table.bin\n    --------  ----\n        4908  2 entries\n\nRules:\n  - Applies to `.zip`, `.jar`, `.war`, `.ear`, `.tar`, `.tar.gz`, `.tgz`, and `.gz`\n    files, which are then included even though the default `--ignore-extensions` lists\n    some of them. A `.gz` file without a tar archive inside lists its one file.\n  - Nothing is extracted to disk, and archives inside the archive are listed, not opened.\n  - At most MAX_ENTRIES members (1000 by default) are listed, followed by\n    `[... N more entries]`; the totals count every member.\n  - Archives are listed whatever their size, and an archive that cannot be read gets\n    `[Could not read archive]`."
+    )]
+    archive_listing: Option<usize>,
+
+    #[clap(
+        long = "follow-symlinks",
+        help = "Descend into symlinked directories and include symlinked files",
+        long_help = "Descend into symlinked directories and include symlinked files.\n\nRules:\n  - Without this flag, symlinked directories are listed in the tree but not expanded,\n    and symlinked files are not read.\n  - Symlinked files are read as their targets; a link whose target is missing is listed\n    and produces `[Broken symlink]`.\n  - A link that points back to a directory being scanned is listed but not followed,\n    so cycles terminate.\n  - A file reachable through several paths is listed in the tree and emitted once,\n    under the first path in sorted order, even across multiple `--directory` entries."
+    )]
+    follow_symlinks: bool,
+
+    #[clap(
+        long = "gzip",
+        conflicts_with = "clipboard",
+        help = "Compress the report with gzip, appending `.gz` to the output name",
+        long_help = "Compress the report with gzip.\n\nRules:\n  - When writing to a file, `.gz` is appended to `--output` unless it already ends with `.gz`.\n  - With `-o -` or `--stdout`, the compressed stream is written to stdout as is.\n  - The compression level is set by `--gzip-level`.\n  - This option cannot be combined with `--clipboard`."
+    )]
+    gzip: bool,
+
+    #[clap(
+        long = "gzip-level",
+        value_name = "LEVEL",
+        default_value = "6",
+        value_parser = clap::value_parser!(u32).range(0..=9),
+        help = "Compression level for `--gzip`, from 0 (none) to 9 (best)"
+    )]
+    gzip_level: u32,
+
+    #[clap(
+        long = "dry-run",
+        help = "List the files that would be included, with sizes, and exit",
+        long_help = "List the files that would be included and exit without reading their contents or writing any output.\n\nRules:\n  - Each line shows the byte size, a running total, and the `path (in dir)` label.\n  - Files that would get a placeholder are marked `[too large]` or `[binary]`; binary\n    detection samples only the first bytes.\n  - The total line adds a token estimate from the sizes (about four bytes per token),\n    leaving out files that would get a placeholder.\n  - The last lines count the files left out by each filter rule and the placeholders.\n  - Files inside ignored directories are not visited, so they are not counted.\n  - Every other option applies as in a real run, except that `--grep` and\n    `--grep-exclude` need the contents and are not applied.\n  - The exit status is non-zero when no file matched, so scripts can detect an empty\n    selection."
+    )]
+    dry_run: bool,
+
+    #[clap(
+        short = 'q',
+        long = "quiet",
+        help = "Do not show the progress indicator on stderr",
+        long_help = "Do not show the progress indicator on stderr.\n\nRules:\n  - The indicator shows `scanning <dir>` and then `reading files X/Y, <bytes read> (current: <path>)`\n    on a single line that is cleared when reading finishes.\n  - It is only drawn when stderr is a terminal, so redirected stderr never contains it.\n  - Warnings and the final status messages are printed either way."
+    )]
+    quiet: bool,
+
+    #[clap(
+        long = "progress",
+        conflicts_with = "quiet",
+        help = "Show the progress indicator even if the config sets `quiet = true`",
+        long_help = "Show the progress indicator on stderr, even if the project config sets `quiet = true`.\n\nRules:\n  - The indicator is on by default; this flag only overrides a config that turns it off.\n  - It is still drawn only when stderr is a terminal, so CI logs stay clean.\n  - This option cannot be combined with `-q/--quiet`."
+    )]
+    progress: bool,
+
+    #[clap(
+        long = "stats",
+        help = "Append a statistics footer after the file contents",
+        long_help = "Append a statistics footer after the file contents.\n\nThe footer lists the number of scanned directories, the number of files whose content was\nincluded, their total bytes, lines, and estimated tokens, a per-extension breakdown (files\nand bytes), and how many files were replaced with a placeholder because they were too\nlarge, binary, or undecodable.\n\nRules:\n  - Sizes are those of the emitted text, after `--line-numbers` and truncation.\n  - `--format text` adds a `＜Statistics＞` section, `markdown` a `# Statistics` list, and\n    `xml` a last document with source `STATISTICS` (a `<statistics>` element with\n    `--xml-layout summary`).\n  - The same totals are printed to stderr on one line after the report is written, with\n    or without this flag.\n  - Without this flag the report is unchanged."
+    )]
+    stats: bool,
+
+    #[clap(
+        long = "no-stats",
+        conflicts_with = "stats",
+        help = "Print no statistics, on stderr or in the report",
+        long_help = "Print no statistics: no `Stats:` line on stderr after the report is written, and no\nfooter even if the config file sets `stats = true`.\n\nRules:\n  - Cannot be combined with `--stats` on the command line."
+    )]
+    no_stats: bool,
+
+    #[clap(
+        long = "header-style",
+        value_enum,
+        default_value = "default",
+        help = "Section headings and file separators of the text format",
+        long_help = "Section headings and file separators of `--format text`.\n\nValues:\n  - `default`: `＜Directory Structure＞` / `＜File Contents＞` headings and dashed lines\n    around each `path (in dir):` header.\n  - `ascii`: the same layout with ASCII brackets (`<Directory Structure>`).\n  - `minimal`: `# Directory Structure` / `# File Contents` headings and `## path (in dir)`\n    before each file, without separator lines.\n\nThe Markdown and XML formats are not affected."
+    )]
+    header_style: HeaderStyle,
+
+    #[clap(
+        long = "dedupe",
+        help = "Show files with identical content only once",
+        long_help = "Show files with identical content only once.\n\nRules:\n  - Each file's text is hashed while it is read; a file whose text matches an earlier\n    file in output order gets `[Duplicate of <path> (in <dir>)]` instead of its content.\n  - Files are compared by their decoded text before `--head-lines`, `--tail-lines`,\n    `--max-lines-per-file`, `--line-numbers`, and the other transforms, so files that\n    only share their first lines are not duplicates.\n  - The first occurrence is always shown in full, and files from different `--directory`\n    entries are compared as well.\n  - Empty files and files replaced by other placeholders are never marked as duplicates.\n  - Duplicates are removed before `--max-tokens` is applied, so they do not use up the budget.\n  - The statistics (`--stats` and the `Stats:` line on stderr) count the duplicates and\n    the bytes they saved, measured as their size on disk."
+    )]
+    dedupe: bool,
+
+    #[clap(
+        long = "no-oreuitignore",
+        help = "Do not apply `.oreuitignore` files",
+        long_help = "Do not apply `.oreuitignore` files.\n\nBy default, a `.oreuitignore` at the root of each scanned directory excludes paths from\nboth the tree and the contents section, without touching `.gitignore`.\n\nRules for `.oreuitignore`:\n  - The syntax is the same as `.gitignore`, including `dir/`, `**`, and `!pattern`.\n  - Patterns are relative to the directory that contains the file.\n  - Only the file at the root of each `--directory` entry is read; nested ones are not.\n  - It applies on top of `--ignore-dirs`, the other filters, and `--respect-gitignore`."
+    )]
+    no_oreuitignore: bool,
+
+    #[clap(
+        long = "encoding",
+        value_name = "LABELS",
+        value_parser = parse_encoding_labels,
+        help = "Encodings to try in order when decoding files (default: detected)",
+        long_help = "Comma-separated encoding labels tried in order when decoding a file.\n\nExamples:\n  --encoding windows-1251\n  --encoding utf-8,euc-jp\n  --encoding utf-8,big5,latin1\n\nRules:\n  - Labels follow the WHATWG Encoding Standard (`utf-8`, `utf-16le`, `shift_jis`, `euc-jp`,\n    `big5`, `gbk`, `euc-kr`, `windows-1251`, `latin1`, ...) and are matched\n    case-insensitively.\n  - A single label forces that encoding for every file, without trying UTF-8 first; bytes\n    it cannot decode become U+FFFD replacement characters instead of failing the file.\n    A forced `utf-16le` or `utf-16be` also turns off binary detection.\n  - With several labels, the first encoding that decodes the whole file without errors is\n    used; if none does, the file becomes `[Cannot decode file content]`.\n  - Without this option, oreuit detects the encoding: ISO-2022-JP by its escape sequences,\n    otherwise UTF-8, and then the most plausible of Shift_JIS, EUC-JP, and Latin-1\n    (windows-1252). Latin-1 is only used when the text looks like accented Latin text.\n  - Files starting with a UTF-8 or UTF-16 byte order mark are always decoded accordingly,\n    and the mark itself is left out of the text.\n  - `latin1` (windows-1252) accepts any bytes, so it only makes sense as the last entry.\n  - Unknown labels are rejected when the arguments are parsed, with the list of valid\n    labels (from the config file, before scanning starts)."
+    )]
+    encoding: Option<String>,
+
+    #[clap(
+        long = "show-encoding",
+        help = "Note the encoding in the header of files that were not UTF-8",
+        long_help = "Note the encoding in the header of files that were not UTF-8, for example\n`legacy.c (in src) [EUC-JP]:`.\n\nThe XML format does not use headers and is not affected."
+    )]
+    show_encoding: bool,
+
+    #[clap(
+        long = "hidden",
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        default_value = "exclude",
+        default_missing_value = "include",
+        help = "Whether to include files and directories whose name starts with `.`",
+        long_help = "Whether to include files and directories whose name starts with `.`.\n\nValues:\n  - `exclude` (default): hidden entries are left out of both the tree and the contents.\n  - `include`: hidden entries are treated like any other. `--hidden` alone means this.\n\nRules:\n  - Names listed in `--whitelist-filenames` (for example `.github` or `.envrc`) and the\n    default extensionless filenames (`.gitignore`, `.gitattributes`) are always kept.\n  - `--ignore-dirs` still applies, so `.git` stays excluded in both modes."
+    )]
+    hidden: HiddenFiles,
+
+    #[clap(
+        long = "grep",
+        visible_alias = "contains",
+        value_name = "PATTERN",
+        help = "Include only files whose content matches this regular expression",
+        long_help = "Include only files whose content matches this regular expression. `--contains` is an\nalias.\n\nExamples:\n  --grep FeatureFlag\n  --grep 'fn (main|run)\\('\n\nRules:\n  - The pattern is applied after the extension, filename, and ignore rules, to the\n    decoded text before `--line-numbers` and truncation.\n  - Files that do not match are left out of both the tree and the contents; the tree\n    lists only the matching files and their parent directories.\n  - Oversized, binary, and undecodable files never match and are not read in full.\n  - `--dry-run` does not read contents, so it lists every candidate.\n  - Every candidate file has to be read before the report is written, including files\n    that end up left out, and matching files are held in memory until then. Narrow the\n    candidates with `--extensions` or `--include-glob` on large trees.\n  - An invalid pattern is reported as an error before scanning starts."
+    )]
+    grep: Option<String>,
+
+    #[clap(
+        long = "split-size",
+        value_name = "BYTES",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Split the report into numbered files of about this many bytes",
+        long_help = "Split the report into numbered files of about this many bytes.\n\nExamples:\n  --split-size 1000000 -o summary.txt   writes summary.001.txt, summary.002.txt, ...\n\nRules:\n  - The part number goes before the extension of `--output`; with `--gzip` each part\n    is compressed and gets `.gz` appended.\n  - A new part starts before the file whose block would take the current part past the\n    limit, so a file's content is never split; a single larger file gets a part of its own.\n  - The tree section is always in part 001, and `--stats` goes at the end of the last part.\n  - Each part starts with a `Part X of Y` line (`<!-- Part X of Y -->` with `--format xml`,\n    where every part is a complete `<documents>` element; with `--xml-layout summary`\n    each `<directory>` goes whole into one part).\n  - This option cannot be combined with `--clipboard`, `-o -`, or `--stdout`."
+    )]
+    split_size: Option<u64>,
+
+    #[clap(
+        long = "split-tokens",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "split_size",
+        help = "Split the report into numbered files of about this many estimated tokens",
+        long_help = "Split the report into numbered files of about this many estimated tokens.\n\nExamples:\n  --split-tokens 100000 -o summary.txt   writes summary.001.txt, summary.002.txt, ...\n\nRules:\n  - Works like `--split-size`, with the limit counted in estimated tokens (the same\n    heuristic as `--max-tokens`) instead of bytes.\n  - A file's content is never split; a single larger file gets a part of its own.\n  - This option cannot be combined with `--split-size`, `--clipboard`, `-o -`, or\n    `--stdout`."
+    )]
+    split_tokens: Option<u64>,
+
+    #[clap(
+        long = "grep-exclude",
+        value_name = "PATTERN",
+        help = "Leave out files whose content matches this regular expression (repeatable)",
+        long_help = "Leave out files whose content matches this regular expression.\n\nExamples:\n  --grep-exclude '@generated'\n  --grep-exclude 'DO NOT SUBMIT' --grep-exclude '^// Code generated .* DO NOT EDIT\\.$'\n\nRules:\n  - The option can be repeated; a file matching any of the patterns is left out. Commas\n    are part of the pattern (as in `a{1,3}`), so give one pattern per option.\n  - Patterns are applied to the decoded text, like `--grep`, and the search stops at the\n    first match in a file.\n  - Excluded files disappear from both the tree and the contents.\n  - With `--grep`, a file is kept only if it matches `--grep` and none of these patterns.\n  - Oversized, binary, and undecodable files are not searched and are kept with their\n    placeholder.\n  - Invalid patterns are reported as an error before scanning starts."
+    )]
+    grep_exclude: Vec<String>,
+
+    #[clap(
+        long = "max-lines-per-file",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["head_lines", "tail_lines"],
+        help = "Keep the first and last N/2 lines of files longer than N lines",
+        long_help = "Keep the first and last N/2 lines of files longer than N lines.\n\nRules:\n  - Same as `--head-lines` with the larger half of N and `--tail-lines` with the other\n    half; the dropped middle is replaced with a `... [M lines omitted] ...` marker.\n  - Files with at most N lines are not changed, and N must be at least 1.\n  - Lines are counted after decoding, so UTF-8 and Shift_JIS files are cut at the same\n    line boundaries.\n  - This option cannot be combined with `--head-lines` or `--tail-lines`."
+    )]
+    max_lines_per_file: Option<usize>,
+
+    #[clap(
+        long = "tree-details",
+        value_name = "DETAILS",
+        value_enum,
+        value_delimiter = ',',
+        help = "Annotate files in the tree with `size`, `mtime`, and/or `lines`",
+        long_help = "Annotate each file in the tree with the chosen details, comma-separated.\n\nExamples:\n  --tree-details size          ├── parser.rs (14.2 KB)\n  --tree-details size,mtime    ├── parser.rs (14.2 KB, 2024-05-02)\n  --tree-details size,lines    ├── parser.rs (14.2 KB, 412 lines)\n\nRules:\n  - `size` uses 1024-byte units with one decimal (`B`, `KB`, `MB`, `GB`, `TB`).\n  - `mtime` is the last modification date as `YYYY-MM-DD`, in local time.\n  - `lines` counts the lines of the file on disk; it is left out for files with NUL\n    bytes, which are binary.\n  - Details appear in the order given. Directories are not annotated.\n  - A file that cannot be stat'ed is listed by name only."
+    )]
+    tree_details: Vec<TreeDetail>,
+
+    #[clap(
+        long = "tree-dir-totals",
+        help = "Annotate directories in the tree with the size and count of files below",
+        long_help = "Annotate each directory in the tree with the total size and number of the files\nbelow it, e.g. `├── src (3.4 MB, 212 files)`.\n\nRules:\n  - Only files that pass filtering are counted; a directory whose files are all filtered\n    out shows `(0 files)`.\n  - Totals are summed while the tree is built, so each directory is read only once.\n  - The root line carries the totals of the whole tree.\n  - Directories cut off by `--max-depth` show their totals as `… (1.2 MB, 40 files below)`.\n  - Sizes use the same units as `--tree-details size`."
+    )]
+    tree_dir_totals: bool,
+
+    #[clap(
+        long = "exclude-regex",
+        value_name = "PATTERN",
+        help = "Leave out files whose relative path matches this regular expression (repeatable)",
+        long_help = "Leave out files whose path relative to each scanned directory matches this regular\nexpression.\n\nExamples:\n  --exclude-regex generated\n  --exclude-regex '^src/proto/' --exclude-regex '_test\\.go$'\n\nRules:\n  - The option can be repeated; a file matching any of the patterns is left out. Commas\n    are part of the pattern, so give one pattern per option.\n  - Paths use `/` separators on every platform, e.g. `src/generated/api.rs`. A pattern\n    matches anywhere in the path unless anchored with `^` or `$`.\n  - Excluded files disappear from both the tree and the contents. Directories are not\n    pruned, so whitelisted files below a matching directory are still listed.\n  - Whitelisted filenames win: they are kept even when their path matches.\n  - Invalid patterns are reported as an error before scanning starts."
+    )]
+    exclude_regex: Vec<String>,
+
+    #[clap(
+        long = "git-tracked",
+        help = "Only include files tracked by git",
+        long_help = "Only include files tracked by git.\n\nRules:\n  - For each scanned directory, the files listed by `git ls-files` (run in that\n    directory) are the candidates; untracked files and directories without tracked files\n    disappear from both the tree and the contents.\n  - Files staged with `git add` count as tracked; ignored and untracked files do not.\n  - The extension, ignore, glob, and size filters still apply to the tracked files, and an\n    untracked file is left out even if its name is whitelisted.\n  - A directory that is not inside a git repository (or when `git` cannot be run) is\n    scanned as usual, with a warning on stderr."
+    )]
+    git_tracked: bool,
+
+    #[clap(
+        long = "toc",
+        value_enum,
+        value_name = "PLACEMENT",
+        num_args = 0..=1,
+        default_missing_value = "before",
+        help = "List the included files with their size and line count before (or after) the trees",
+        long_help = "List every included file with its size and line count in the directory structure\nsection.\n\nValues:\n  - `before`: between the directory structure heading and the trees. `--toc` alone\n    means this.\n  - `after`: after the trees, before the file contents.\n\nExample:\n  Files (2):\n  src/lib.rs (in crate): 1,234 bytes, 56 lines\n  logo.png (in crate): [Binary file skipped]\n\nRules:\n  - Files are listed in the same order as the contents, and sizes are those of the\n    emitted content (after `--head-lines` and similar options).\n  - Skipped files show their placeholder instead of a size.\n  - `--format markdown` uses a `## Files (N)` heading and a bullet list; `--format xml`\n    puts the list in the `DIRECTORY_TREE` document, or a `<toc>` element with\n    `--xml-layout summary`; `--format html` links each entry to the file's section."
+    )]
+    toc: Option<TocPlacement>,
+
+    #[clap(
+        long = "changed-since",
+        value_name = "REF",
+        conflicts_with = "files_from",
+        help = "Only include files changed in git since REF (e.g. `main`)",
+        long_help = "Only include files that differ between the git ref REF and the working tree.\n\nExamples:\n  --changed-since main\n  --changed-since HEAD~3\n\nRules:\n  - Both staged and unstaged changes count; untracked files that were never added do not.\n  - Each scanned directory is compared on its own, and only changes below it are listed.\n  - Renamed files appear under their new path.\n  - Deleted files are listed in the tree as `name [deleted]` and get no contents section.\n  - The other filters still apply to the changed files that exist.\n  - A directory outside a git repository, or a REF git cannot resolve, is reported as an\n    error before scanning starts.\n  - This option cannot be combined with `--files-from`."
+    )]
+    changed_since: Option<String>,
+
+    #[clap(
+        long = "strip-comments",
+        help = "Remove comments from source files in known languages",
+        long_help = "Remove line and block comments from source files before they are emitted.\n\nLanguages (by extension):\n  - `//` and `/* */`: rs, c, h, cpp, cc, cxx, hpp, hh, java, go, js, mjs, cjs, jsx, ts, tsx\n    (Rust block comments may nest).\n  - `#`: py, rb, toml. A `#!` line at the start of the file is kept.\n\nRules:\n  - Comment markers inside string literals are kept, e.g. `\"http://...\"`, Rust raw strings,\n    and Python triple-quoted strings (so docstrings stay).\n  - A line that held only a comment is removed; whitespace before a trailing comment is\n    trimmed. Blank lines in the original are kept.\n  - Files with other extensions are emitted unchanged.\n  - Comments are removed before `--line-numbers` and `--head-lines`/`--tail-lines`, so line\n    numbers refer to the stripped text. `--grep` still searches the original text."
+    )]
+    strip_comments: bool,
+
+    #[clap(
+        long = "squeeze-blank",
+        help = "Collapse runs of blank lines in file contents into one",
+        long_help = "Collapse runs of blank lines in file contents into one, like `cat -s`.\n\nRules:\n  - Two or more consecutive blank lines become a single blank line; lines holding only\n    spaces or tabs count as blank.\n  - Blank lines at the start of a file are removed, and a file ends with at most one\n    newline.\n  - Only file contents change; the tree and placeholders are left as they are.\n  - Applied after decoding and `--strip-comments`, and before `--line-numbers` and\n    `--head-lines`/`--tail-lines`, so line numbers refer to the squeezed text."
+    )]
+    squeeze_blank: bool,
+
+    #[clap(
+        long = "xml-layout",
+        value_enum,
+        default_value = "documents",
+        help = "Element layout of `--format xml`",
+        long_help = "Element layout of `--format xml`.\n\nValues:\n  - `documents` (default): `<documents>` with numbered `<document>` elements; see `--format`.\n  - `summary`: one `<directory>` per scanned directory with its `<tree>` and a `<file>`\n    per file:\n      <summary>\n      <directory name=\"crate\">\n      <tree>…</tree>\n      <file path=\"src/lib.rs\" size=\"1234\">…</file>\n      <file path=\"logo.png\" size=\"20480\" skipped=\"binary\"></file>\n      </directory>\n      </summary>\n\nRules (summary):\n  - `path` is relative to the directory and `size` is the file's size on disk in bytes.\n  - Skipped files have an empty body and a `skipped` attribute: `too-large`, `binary`,\n    `decode-failed`, `token-budget`, `duplicate`, or `broken-symlink`.\n  - `&`, `<`, `>`, `\"`, and `'` are escaped in text and attribute values.\n  - `--toc` and `--stats` add `<toc>` and `<statistics>` elements."
+    )]
+    xml_layout: XmlLayout,
+
+    #[clap(
+        long = "relative-to",
+        value_name = "PATH",
+        help = "Show file header paths relative to PATH instead of each scanned directory",
+        long_help = "Show file header paths relative to PATH instead of each scanned directory, so files\nfrom several directories get comparable paths.\n\nExamples:\n  -d crate-a,crate-b --relative-to .\n    crate-a/src/lib.rs (in crate-a)\n    crate-b/src/lib.rs (in crate-b)\n\nRules:\n  - `(in dir)` still names the scanned directory, and the trees are unchanged.\n  - A file that is not under PATH is shown with its absolute path.\n  - Symlinked directories are resolved before comparing with PATH.\n  - A PATH that does not exist is reported as an error before scanning starts."
+    )]
+    relative_to: Option<String>,
+
+    #[clap(
+        long = "sort",
+        value_enum,
+        default_value = "path",
+        value_name = "KEY",
+        help = "Order of the files in the contents section",
+        long_help = "Order of the files in the contents section.\n\nValues:\n  - `path` (default): alphabetically by path.\n  - `size` / `size-desc`: smallest or largest first.\n  - `mtime` / `mtime-desc`: least or most recently modified first.\n  - `extension`: grouped by extension, files without one first.\n\nRules:\n  - Files are sorted within each scanned directory; directories keep their `--directory`\n    order.\n  - Ties are broken by path, and each file's metadata is read once.\n  - The tree section stays alphabetical.\n  - `--toc`, `--dedupe` (which keeps the first copy), and `--max-tokens --trim-strategy\n    tail` follow this order.\n  - `--files-from` lists are always sorted by path."
+    )]
+    sort: FileOrder,
+
+    #[clap(
+        long = "pin",
+        value_name = "PATH",
+        help = "Put this file first in the contents section, marked [PINNED] (repeatable)",
+        long_help = "Put this file first in the contents section, ahead of the `--sort` order, and mark its\nheader with `[PINNED]`.\n\nExamples:\n  --pin README.md\n  -d . --pin src/lib.rs --pin src/main.rs   emits lib.rs, then main.rs, then the rest\n\nRules:\n  - The option can be repeated; pinned files come first in the order given, and the\n    remaining files follow in their usual order without the pinned ones.\n  - PATH is compared with the file paths as scanned, e.g. `src/main.rs` for `-d src`;\n    `./` components are ignored, so `-d .` matches `src/main.rs` too.\n  - Pinning does not select a file: a PATH that is not selected is reported as a\n    warning, and a pinned file that is too large or binary still gets its placeholder.\n  - The tree section is unchanged; `--toc`, `--dry-run`, and `--max-tokens --trim-strategy\n    tail` follow the pinned order.\n  - The marker is part of the header in the text, markdown, and html formats."
+    )]
+    pin: Vec<String>,
+
+    #[clap(
+        short = 'v',
+        long = "verbose",
+        help = "Log each skipped file or directory and the reason to stderr",
+        long_help = "Log one line to stderr for each file or directory that is left out, with the reason.\n\nExamples:\n  Skipped src/node_modules/: ignored directory\n  Skipped src/logo.png: ignored extension\n  Skipped src/data.bin: binary\n\nRules:\n  - Filter rules (ignored extension or filename, extension not allowed, hidden,\n    `.gitignore`, `--exclude-glob`, ...) and placeholders (larger than --max-size, binary,\n    cannot decode, ...) are both reported.\n  - A pruned directory gets a single line ending in `/`; the files below it are not\n    visited, so they are not listed.\n  - Files dropped by `--grep` or `--grep-exclude` are not listed.\n  - Lines are printed whether or not stderr is a terminal, and also with `-q/--quiet`."
+    )]
+    verbose: bool,
+
+    #[clap(
+        long = "report",
+        value_name = "PATH",
+        conflicts_with = "dry_run",
+        help = "Also write every skipped file and the reason to PATH as JSON",
+        long_help = "Also write every file or directory that is left out, or whose content is replaced\nwith a placeholder, to PATH as JSON. The report itself is unchanged.\n\nExample:\n  --report skipped.json writes\n    {\n      \"counts\": {\"binary\": 1, \"ignored-dir\": 1},\n      \"skipped\": [\n        {\"path\": \"src/data.bin\", \"reason\": \"binary\", \"description\": \"binary\", \"directory\": false},\n        {\"path\": \"src/target\", \"reason\": \"ignored-dir\", \"description\": \"ignored directory\", \"directory\": true}\n      ]\n    }\n\nReasons:\n  - Filters: `ignored-ext`, `ignored-name`, `not-allowed`, `ignored-dir`, `hidden`,\n    `gitignore`, `oreuitignore`, `ignore-file`, `exclude-glob`, `exclude-regex`,\n    `min-size`, `modified-before`, `untracked`, `unchanged`, `deselected`.\n  - Placeholders: `too-large`, `binary`, `decode-failed`, `broken-symlink`.\n\nRules:\n  - Lists what `-v/--verbose` logs, sorted by path; `description` is the reason as\n    `--verbose` words it. `counts` has the number of entries per reason.\n  - A pruned directory is one entry with `\"directory\": true`; the files below it are\n    not visited, so they are not listed.\n  - Files dropped by `--grep` or `--grep-exclude` are not listed.\n  - Paths are as scanned, e.g. `src/main.rs` for `-d src`.\n  - Written after the files are read, also when no file matched.\n  - Cannot be combined with `--dry-run`."
+    )]
+    report: Option<String>,
+
+    #[clap(
+        long = "whitelist-case-insensitive",
+        help = "Match whitelisted filenames without regard to case",
+        long_help = "Match `--whitelist-filenames` entries (or `whitelist.files`) without regard to case.\n\nExamples:\n  --whitelist-filenames Dockerfile --whitelist-case-insensitive\n    also keeps `dockerfile` and `DOCKERFILE`\n\nRules:\n  - Applies to plain names and glob patterns alike.\n  - Other filename rules, such as `--ignore-files`, stay case-sensitive."
+    )]
+    whitelist_case_insensitive: bool,
+
+    #[clap(
+        long = "tree-stats",
+        help = "Shorthand for `--tree-details size,lines --tree-dir-totals`",
+        long_help = "Annotate files in the tree with their size and line count, and directories with the\ntotals below them; shorthand for `--tree-details size,lines --tree-dir-totals`.\n\nExample:\n  crate (18.4 KB, 3 files)\n  ├── Cargo.toml (312 B, 14 lines)\n  └── src (18.1 KB, 2 files)\n      ├── lib.rs (3.2 KB, 142 lines)\n      └── main.rs (14.9 KB, 401 lines)\n\nRules:\n  - Details given with `--tree-details` are kept; `size` and `lines` are added after them\n    when missing.\n  - Only the tree is annotated; the contents section is unchanged."
+    )]
+    tree_stats: bool,
+
+    #[clap(
+        long = "ascii-tree",
+        help = "Draw the tree with ASCII connectors instead of box-drawing characters",
+        long_help = "Draw the tree with plain ASCII connectors, for terminals, fonts, and tools that do not\nhandle box-drawing characters.\n\nExample:\n  crate\n  |-- Cargo.toml\n  `-- src\n      |-- cli\n      |   `-- args.rs\n      `-- main.rs\n\nRules:\n  - `├── `, `└── `, and `│   ` become `|-- `, `` `-- ``, and `|   `.\n  - The `…` before directories cut off by `--max-depth` becomes `...`.\n  - Applies to every tree in the report, in every `--format`; file contents are unchanged."
+    )]
+    ascii_tree: bool,
+
+    #[clap(
+        long = "normalize-newlines",
+        help = "Convert CRLF and lone CR line endings in file contents to LF",
+        long_help = "Convert `\\r\\n` and lone `\\r` line endings in file contents to `\\n`.\n\nRules:\n  - Applied right after decoding, before `--strip-comments`, `--squeeze-blank`,\n    `--line-numbers`, and `--head-lines`/`--tail-lines`. `--grep` and `--grep-exclude`\n    match the text as decoded.\n  - Only file contents change; headers, the tree, and placeholders such as\n    `[Binary file skipped]` are left as they are.\n  - Token estimates and sizes in `--toc` and `--stats` count the converted text."
+    )]
+    normalize_newlines: bool,
+
+    #[clap(
+        long = "trim-whitespace",
+        help = "Strip trailing whitespace and collapse long blank runs in file contents",
+        long_help = "Strip trailing whitespace from each line of file contents and collapse runs of\nthree or more blank lines into one, to save tokens.\n\nRules:\n  - Lines holding only whitespace count as blank. Runs of one or two blank lines are kept.\n  - Line endings are kept; a CRLF line stays CRLF.\n  - Markdown files (`.md`, `.markdown`) are left alone, since two trailing spaces there are\n    a hard line break; `--trim-markdown` trims them too.\n  - Applied after `--normalize-newlines` and `--strip-comments`, and before\n    `--squeeze-blank`, `--line-numbers`, and `--head-lines`/`--tail-lines`.\n  - Only file contents change; headers, the tree, and placeholders are left as they are."
+    )]
+    trim_whitespace: bool,
+
+    #[clap(
+        long = "trim-markdown",
+        help = "Apply --trim-whitespace to Markdown files too",
+        long_help = "Apply `--trim-whitespace` to `.md` and `.markdown` files too, dropping the trailing\nspaces that mark hard line breaks.\n\nRules:\n  - Has no effect without `--trim-whitespace`."
+    )]
+    trim_markdown: bool,
+
+    #[clap(
+        long = "lang-map",
+        value_name = "EXT=LANG",
+        value_delimiter = ',',
+        value_parser = parse_lang_mapping,
+        help = "Language tags for extensions, e.g. `.gotmpl=go-template,.hcl=hcl`",
+        long_help = "Language tags for file extensions, extending or overriding the built-in table.\n\nExamples:\n  --lang-map .gotmpl=go-template,.hcl=hcl\n  --lang-map d.ts=typescript --lang-map .json=jsonc\n\nRules:\n  - Tags name the language of Markdown code fences and of `--show-language` headers.\n  - Extensions are normalized like `--extensions`; the dot is optional.\n  - Multi-dot extensions such as `.d.ts` are tried before their last part (`.ts`).\n  - Entries given here win over the built-in table. A file whose extension is in neither\n    is tagged with the bare extension, e.g. `proto`; files without one get no tag.\n  - The option can be repeated."
+    )]
+    lang_map: Vec<String>,
+
+    #[clap(
+        long = "show-language",
+        help = "Note each file's language in its header, e.g. `parser.rs (rust) (in src)`",
+        long_help = "Note each file's language in its header, e.g. `parser.rs (rust) (in src)`.\n\nRules:\n  - The language is the one used for Markdown code fences; see `--lang-map`.\n  - Files without a known language or an extension are not annotated.\n  - Applies to the text, Markdown, and HTML formats."
+    )]
+    show_language: bool,
+
+    #[clap(
+        long = "max-size-for",
+        value_name = "EXT=BYTES",
+        value_delimiter = ',',
+        value_parser = parse_size_override,
+        help = "Per-extension --max-size overrides, e.g. `.json=65536,.lock=10000`",
+        long_help = "Per-extension limits that replace `--max-size` for matching files, in bytes.\n\nExamples:\n  --max-size-for .json=65536,.lock=10000\n  --max-size 1048576 --max-size-for svg=4096\n\nRules:\n  - Extensions are normalized like `--extensions`; the dot is optional. The last\n    extension of a name is used, so `package-lock.json` matches `.json`.\n  - Files over their limit get the `[File size exceeds limit; skipped]` placeholder.\n  - Files with other extensions keep the `--max-size` limit.\n  - The option can be repeated; a later entry for the same extension wins."
+    )]
+    max_size_for: Vec<String>,
+
+    #[clap(
+        long = "interactive",
+        help = "Pick the files to include on the terminal before the report is written",
+        long_help = "List the selected files with their sizes on the terminal and let you deselect some\nbefore the report is written.\n\nExample:\n  /parser 3-5   shows only the files matching `parser`, then toggles files 3 to 5\n\nRules:\n  - Every file starts selected. Enter numbers or ranges to toggle files, e.g. `2 5-7`;\n    `a` selects all files and `n` none. An empty line continues with the selection.\n  - `/TEXT` shows only the files whose path contains the characters of TEXT in order,\n    ignoring case, as fzf matches (`/mnrs` matches `src/main.rs`); `/` alone shows all\n    files again. Files keep their numbers, and `a` and `n` apply to the shown files.\n  - Deselected files are left out of both the tree and the contents.\n  - The list is shown only when stdin and stdout are terminals (stdout may be redirected\n    with `--print-selection`); otherwise a warning is printed and every file is kept, so\n    scripts and pipes still work.\n  - `--print-selection` prints the chosen files instead of writing the report, to replay\n    the choice later with `--files-from`.\n  - Ignored with `--dry-run`."
+    )]
+    interactive: bool,
+
+    #[clap(
+        long = "print-selection",
+        conflicts_with_all = ["dry_run", "watch"],
+        help = "Print the paths of the selected files to stdout, one per line, and exit",
+        long_help = "Print the paths of the selected files to stdout, one per line, and exit without\nwriting the report.\n\nExample:\n  oreuit -d src --interactive --print-selection > picked.txt\n  oreuit --files-from picked.txt -o summary.txt\n\nRules:\n  - With `--interactive`, prints the files left selected in the list, which is drawn on\n    stderr; otherwise every file that passes the filters.\n  - Paths are the files' paths as scanned, e.g. `src/main.rs` for `-d src`, so\n    `--files-from` reads the same files when run from the same directory.\n  - No file is read; `--grep` and `--grep-exclude` need the contents and are not\n    applied.\n  - The exit status is 2 when no file is selected.\n  - Cannot be combined with `--dry-run` or `--watch`."
+    )]
+    print_selection: bool,
+
+    #[clap(
+        long = "watch",
+        conflicts_with = "dry_run",
+        help = "Write the report again whenever a selected file changes, until Ctrl-C",
+        long_help = "Write the report, then write it again each time a selected file is added, removed, or\nmodified, until Ctrl-C.\n\nExample:\n  oreuit -d src -o summary.txt --watch\n\nRules:\n  - Only files that pass the filters are watched, so changes in ignored directories or\n    to ignored files start no run.\n  - The selected files are checked for a new size or modification time twice a second.\n    After a change, the report is written once they have stayed unchanged for 0.3 s, so\n    a checkout or a formatter touching many files starts one run.\n  - The files the report is written to, `--hash-manifest`, and `--report` are not\n    watched and are left out of later reports, so writing them never starts another run.\n  - Every run writes to the same place: `--output`, stdout, or the clipboard. With\n    `--append`, each run is added after the previous one.\n  - Errors, including a run where no file matched, are printed and watching goes on.\n  - The first Ctrl-C lets the report being written finish, then exits with 0; a second\n    one exits at once.\n  - Cannot be combined with `--dry-run`."
+    )]
+    watch: bool,
+
+    #[clap(
+        long = "tree-only",
+        visible_alias = "no-content",
+        conflicts_with = "dry_run",
+        help = "Write only the directory structure, without reading any file",
+        long_help = "Write only the directory structure section, without reading any file.\n`--no-content` is an alias.\n\nRules:\n  - The trees are built from the same filters as a full run; no file is opened, except\n    to count lines for `--tree-details lines` or `--tree-stats`.\n  - The file contents section and its heading are left out, and so are `--toc` and\n    `--stats`.\n  - `--grep` and `--grep-exclude` need the contents and are not applied.\n  - Works with every `--format`, `--clipboard`, `--gzip`, and `-o -`.\n  - With `--bare`, only the trees are written, without any heading."
+    )]
+    tree_only: bool,
+
+    #[clap(
+        long = "bare",
+        requires = "tree_only",
+        help = "With `--tree-only`, write the trees alone, without headings",
+        long_help = "With `--tree-only`, write the raw trees, separated by a blank line, without the\nsection heading or `Tree for` lines.\n\nRules:\n  - `--format` is not applied; the trees are plain text.\n  - Requires `--tree-only`."
+    )]
+    bare: bool,
+
+    #[clap(
+        long = "stream-threshold",
+        value_name = "BYTES",
+        default_value = "4194304",
+        help = "Read files larger than this in chunks while writing (0 = never)",
+        long_help = "Files larger than this many bytes are read in chunks and written to the output as\nthey are read, instead of being held in memory with the rest of the report.\n\nRules:\n  - The default is 4194304 (4 MiB); `0` reads every file whole.\n  - The output is the same either way, and so is the encoding fallback: the file is\n    checked chunk by chunk before it is written.\n  - `--dedupe` and the options that change the contents (`--grep`, `--line-numbers`,\n    `--head-lines`, `--strip-comments`, ...) read every file whole.\n  - Split parts and `--clipboard` build the whole report in memory, so large files are\n    held there after all."
+    )]
+    stream_threshold: u64,
+
+    #[clap(
+        long = "no-tree",
+        conflicts_with = "tree_only",
+        help = "Leave out the directory structure and write only the file contents",
+        long_help = "Leave out the directory structure section and its heading, and write only the file\ncontents, e.g. to append a second batch of files to a prompt that already has the tree.\n\nRules:\n  - Text output starts with the `＜File Contents＞` heading; Markdown and HTML start with\n    their `File Contents` heading.\n  - XML has no `DIRECTORY_TREE` document, or no `<tree>` elements with\n    `--xml-layout summary`; JSON Lines have no tree line.\n  - `--toc` and `--stats` still apply. The table of contents comes first.\n  - Cannot be combined with `--tree-only`."
+    )]
+    no_tree: bool,
+
+    #[clap(
+        long = "git-blame-summary",
+        help = "Note the last commit of each file in its header",
+        long_help = "Note the last commit that touched each file in its header: the abbreviated hash, the\nauthor, and the date.\n\nExample:\n  src/main.rs (in src) (1a2b3c4 by Dana, 2024-05-01)\n\nRules:\n  - Runs one `git log` per scanned directory, stopping once every tracked file has\n    been seen. Files git does not track get no note.\n  - A directory outside a git repository, or a missing `git`, is reported with a warning\n    on stderr and the report is written without notes.\n  - Applies to the file headers of text, Markdown, and HTML output; XML and JSON Lines\n    have no file headers."
+    )]
+    git_blame_summary: bool,
+
+    #[clap(
+        long = "ignore-file-pattern",
+        value_name = "NAMES",
+        help = "Also apply ignore files with these names, e.g. `.dockerignore` (repeatable)",
+        long_help = "Comma-separated names of ignore files with gitignore syntax to apply while scanning,\nsuch as `.dockerignore` or `.npmignore`.\n\nExamples:\n  --ignore-file-pattern .dockerignore\n  --ignore-file-pattern .dockerignore,.npmignore\n\nRules:\n  - Every file with one of these names inside a scanned directory applies to its own\n    subtree; files above the scanned directory are not read.\n  - Patterns follow `.gitignore` semantics, including `dir/`, `**`, and `!pattern`; the\n    closest file wins, so a nested `!pattern` re-includes a path.\n  - Each name is applied on its own: a path is left out when the files of any name\n    exclude it.\n  - Matched paths disappear from both the tree and the contents section.\n  - `--respect-gitignore` keeps applying `.gitignore` up to the repository root; naming\n    `.gitignore` here applies it within the scanned directories only.\n  - The option can be repeated."
+    )]
+    ignore_file_pattern: Vec<String>,
+
+    #[clap(
+        long = "hashes",
+        help = "Note the SHA-256 of each file in its header",
+        long_help = "Note the SHA-256 of each file's bytes in its header, shortened to 12 hex digits, so a\nfile can later be checked against the snapshot.\n\nExample:\n  src/main.rs (in src) [sha256:3f1c9a0b7d2e]\n\nRules:\n  - The hash is of the bytes on disk, before decoding or any option that changes the\n    text. Files read in full are hashed from the bytes already read.\n  - Binary and oversized files are hashed too, although their content is skipped;\n    broken symlinks are not.\n  - Applies to the file headers of text, Markdown, and HTML output; XML and JSON Lines\n    have no file headers."
+    )]
+    hashes: bool,
+
+    #[clap(
+        long = "hash-manifest",
+        value_name = "PATH",
+        help = "Also write the full SHA-256 of each file to PATH, as sha256sum does",
+        long_help = "Also write the full SHA-256 of each file to PATH, one `<hash>  <path>` line per file in\noutput order, the format `sha256sum` writes and `sha256sum -c` checks.\n\nExample:\n  --hashes --hash-manifest summary.sha256\n\nRules:\n  - Implies `--hashes`.\n  - Paths are the files' paths as scanned, e.g. `src/main.rs` for `-d src`, so\n    `sha256sum -c` works from the directory oreuit was run in.\n  - Files without a hash, such as broken symlinks, are left out.\n  - With `--format jsonl` the files are read before the report is written."
+    )]
+    hash_manifest: Option<String>,
 }
 
-fn normalize_extension(value: &str) -> Option<String> {
-    let value = value.trim().to_lowercase();
-    if value.is_empty() {
-        None
-    } else if value.starts_with('.') {
-        Some(value)
-    } else {
-        Some(format!(".{}", value))
+/// Checks that a `--lang-map` entry has the form `EXT=LANG`.
+fn parse_lang_mapping(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((ext, lang)) if !ext.trim().is_empty() && !lang.trim().is_empty() => {
+            Ok(value.trim().to_string())
+        }
+        _ => Err(format!(
+            "expected EXT=LANG such as `.hcl=hcl`, got '{}'",
+            value
+        )),
     }
 }
 
-fn collect_normalized_extensions<I, S>(values: I) -> HashSet<String>
-where
-    I: IntoIterator<Item = S>,
-    S: AsRef<str>,
-{
-    values
-        .into_iter()
-        .filter_map(|value| normalize_extension(value.as_ref()))
-        .collect()
+/// Checks that a `--max-size-for` entry has the form `EXT=BYTES`.
+fn parse_size_override(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((ext, bytes)) if !ext.trim().is_empty() && bytes.trim().parse::<u64>().is_ok() => {
+            Ok(value.trim().to_string())
+        }
+        _ => Err(format!(
+            "expected EXT=BYTES such as `.json=65536`, got '{}'",
+            value
+        )),
+    }
 }
 
-fn default_extensionless_filenames() -> HashSet<String> {
-    [
-        "Makefile",
-        "Dockerfile",
-        "LICENSE",
-        "README",
-        ".gitignore",
-        ".gitattributes",
-        "justfile",
-    ]
-    .iter()
-    .map(|s| s.to_string())
-    .collect()
+/// Parses `--since`: a duration back from now such as `24h` or `7d`, or a UTC date
+/// `YYYY-MM-DD` with an optional `THH:MM[:SS]` time.
+fn parse_since(value: &str) -> Result<SystemTime, String> {
+    let invalid = || {
+        format!(
+            "expected a duration such as `7d` or `24h`, or a date such as `2024-05-01`, got '{}'",
+            value
+        )
+    };
+    let value = value.trim();
+    if let Some(unit) = value.chars().last().filter(char::is_ascii_alphabetic) {
+        let count: u64 = value[..value.len() - 1].parse().map_err(|_| invalid())?;
+        let seconds = match unit {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            'w' => 7 * 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let ago = Duration::from_secs(count.checked_mul(seconds).ok_or_else(invalid)?);
+        return SystemTime::now().checked_sub(ago).ok_or_else(invalid);
+    }
+
+    let (date, time) = value.split_once('T').unwrap_or((value, ""));
+    let numbers = |text: &str, sep: char| -> Option<Vec<u64>> {
+        text.split(sep).map(|part| part.parse().ok()).collect()
+    };
+    let date = numbers(date, '-').ok_or_else(invalid)?;
+    let time = if time.is_empty() {
+        vec![0, 0]
+    } else {
+        numbers(time, ':').ok_or_else(invalid)?
+    };
+    let [year, month, day] = date[..] else {
+        return Err(invalid());
+    };
+    let (hour, minute, second) = match time[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return Err(invalid()),
+    };
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return Err(invalid());
+    }
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        return Err(invalid());
+    }
+    let seconds = days as u64 * 86_400 + hour * 3_600 + minute * 60 + second;
+    Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
 }
 
-fn sorted_strings(values: &HashSet<String>) -> Vec<String> {
-    let mut items: Vec<String> = values.iter().cloned().collect();
-    items.sort_unstable();
-    items
+/// Days from 1970-01-01 to a date (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: u64, month: u64, day: u64) -> i64 {
+    let year = year as i64 - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
 }
 
-fn build_filter_rules_from_config(config: Config) -> FilterRules {
-    let whitelist_filenames: HashSet<String> = config.whitelist.files.into_iter().collect();
+/// Checks each label of `--encoding` so that a typo fails with the list of valid labels.
+fn parse_encoding_labels(value: &str) -> Result<String, String> {
+    for label in split_list(value) {
+        check_encoding_label(&label)?;
+    }
+    Ok(value.to_string())
+}
 
-    FilterRules {
-        allowed: collect_normalized_extensions(config.whitelist.extensions),
-        ignore_exts: collect_normalized_extensions(config.blacklist.extensions),
-        ignore_dirs: config.blacklist.directories.into_iter().collect(),
-        whitelist_filenames,
-        ignore_files: config.blacklist.files.into_iter().collect(),
-        extensionless_allowed: default_extensionless_filenames(),
+/// Parses a comma-separated CLI list, dropping blank entries.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Resolves a list option that can be omitted (built-in defaults), prefixed with `+,`
+/// (added to the defaults), or given in full (replacing the defaults).
+fn list_with_defaults(value: Option<&str>, defaults: &[&str]) -> Vec<String> {
+    let defaults = defaults.iter().map(|s| s.to_string());
+    match value.map(str::trim) {
+        None | Some("") => defaults.collect(),
+        Some(val) => match val.strip_prefix("+,") {
+            Some(extra) => defaults.chain(split_list(extra)).collect(),
+            None => split_list(val),
+        },
     }
 }
 
-fn build_filter_rules_from_cli(args: &Args) -> FilterRules {
-    let allowed: HashSet<String> = match &args.extensions {
-        None => DEFAULT_ALLOWED_EXTENSIONS.clone(),
-        Some(val) => {
-            let val = val.trim();
-            if val.is_empty() {
-                DEFAULT_ALLOWED_EXTENSIONS.clone()
-            } else if val.starts_with("+,") {
-                let mut set = DEFAULT_ALLOWED_EXTENSIONS.clone();
-                for s in val.trim_start_matches("+,").split(',') {
-                    if let Some(ext) = normalize_extension(s) {
-                        set.insert(ext);
-                    }
-                }
-                set
-            } else {
-                collect_normalized_extensions(val.split(','))
-            }
-        }
-    };
+/// Applies the filter-related CLI flags that `--config` replaces.
+fn apply_cli_filters(options: SummaryOptions, args: &Args) -> SummaryOptions {
+    options
+        .allowed_extensions(list_with_defaults(
+            args.extensions.as_deref(),
+            DEFAULT_ALLOWED_EXTENSIONS,
+        ))
+        .ignore_extensions(split_list(&args.ignore_extensions))
+        .ignore_dirs(list_with_defaults(
+            args.ignore_dirs.as_deref(),
+            DEFAULT_IGNORE_DIRS,
+        ))
+        .whitelist_filenames(split_list(&args.whitelist_filenames))
+        .ignore_files(split_list(&args.ignore_files))
+}
+
+/// Looks for `.oreuit.toml` in the first directory passed to `--directory`, then for
+/// `.oreuit.toml` or `oreuit.toml` in the current directory.
+fn find_project_config(directories: &str) -> Option<PathBuf> {
+    let first = directories
+        .split(',')
+        .map(str::trim)
+        .find(|s| !s.is_empty());
+    first
+        .map(|dir| Path::new(dir).join(PROJECT_CONFIG_FILENAME))
+        .into_iter()
+        .chain([
+            PathBuf::from(PROJECT_CONFIG_FILENAME),
+            PathBuf::from(PROJECT_CONFIG_FILENAME.trim_start_matches('.')),
+        ])
+        .find(|path| path.is_file())
+}
 
-    let ignore_dirs: HashSet<String> = match &args.ignore_dirs {
-        None => DEFAULT_IGNORE_DIRS.clone(),
-        Some(val) => {
-            let val = val.trim();
-            if val.is_empty() {
-                DEFAULT_IGNORE_DIRS.clone()
-            } else if val.starts_with("+,") {
-                let mut set = DEFAULT_IGNORE_DIRS.clone();
-                for s in val.trim_start_matches("+,").split(',') {
-                    let s = s.trim().to_string();
-                    if s.is_empty() {
-                        continue;
-                    }
-                    set.insert(s);
+/// Command-line arguments for the options a project config sets, so that the config goes
+/// through the same parser. Options given on the command line, or conflicting with one
+/// that was, are left out: the command line wins.
+fn config_arguments(config: &ProjectConfig, matches: &ArgMatches) -> Result<Vec<String>, String> {
+    let mut command = Args::command();
+    command.build();
+    let given =
+        |arg: &Arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine);
+    let conflict = |a: &Arg, b: &Arg| {
+        command
+            .get_arg_conflicts_with(a)
+            .iter()
+            .any(|other| other.get_id() == b.get_id())
+    };
+    let mut arguments = Vec::new();
+    for (key, values) in config.option_values() {
+        let long = key.replace('_', "-");
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long.as_str()))
+            .ok_or_else(|| format!("`{}` is not a command-line option", key))?;
+        let overridden = given(arg)
+            || command
+                .get_arguments()
+                .filter(|other| given(other))
+                .any(|other| conflict(arg, other) || conflict(other, arg));
+        if overridden {
+            continue;
+        }
+        match arg.get_action() {
+            ArgAction::SetTrue => {
+                if values == ["true"] {
+                    arguments.push(format!("--{}", long));
                 }
-                set
-            } else {
-                val.split(',')
-                    .filter_map(|s| {
-                        let s = s.trim().to_string();
-                        if s.is_empty() {
-                            None
-                        } else {
-                            Some(s)
-                        }
-                    })
-                    .collect()
             }
+            ArgAction::Append => {
+                arguments.extend(values.iter().map(|value| format!("--{}={}", long, value)))
+            }
+            _ => arguments.push(format!("--{}={}", long, values.join(","))),
         }
-    };
+    }
+    Ok(arguments)
+}
 
-    let whitelist_filenames: HashSet<String> = args
-        .whitelist_filenames
-        .split(',')
-        .filter_map(|s| {
-            let s = s.trim().to_string();
-            if s.is_empty() {
-                None
+/// Reads newline-separated paths from a file, or from stdin when `source` is `-`. With
+/// `null`, paths are separated by NUL bytes and are not trimmed.
+///
+/// Blank entries are ignored, and paths that are not regular files are skipped with a
+/// warning. The result is sorted and deduplicated like `collect_files` output.
+fn read_file_list(source: &str, null: bool) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let text = if source == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        fs::read_to_string(source)
+            .map_err(|e| format!("Failed to read file list '{}': {}", source, e))?
+    };
+    let entries: Vec<&str> = if null {
+        text.split('\0').collect()
+    } else {
+        text.lines().map(str::trim).collect()
+    };
+    let mut files: Vec<PathBuf> = entries
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            let path = PathBuf::from(line);
+            if path.is_file() {
+                Some(path)
             } else {
-                Some(s)
-            }
-        })
-        .collect();
-
-    let ignore_files: HashSet<String> = args
-        .ignore_files
-        .split(',')
-        .filter_map(|s| {
-            let s = s.trim().to_string();
-            if s.is_empty() {
+                eprintln!("Warning: File not found, skipping: {}", path.display());
                 None
-            } else {
-                Some(s)
             }
         })
         .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
 
-    FilterRules {
-        allowed,
-        ignore_exts: collect_normalized_extensions(args.ignore_extensions.split(',')),
-        ignore_dirs,
-        whitelist_filenames,
-        ignore_files,
-        extensionless_allowed: default_extensionless_filenames(),
-    }
+/// Why oreuit stopped without a report, and the exit status that tells scripts apart.
+enum CliError {
+    /// Arguments clap rejected, or `--help` and `--version`, which clap prints itself.
+    Usage(clap::Error),
+    /// Invalid options, an unreadable config or file list, a failed clone, and the like.
+    Other(Box<dyn Error>),
+    /// None of the given directories exists.
+    NoDirectories,
+    /// The filters left no file to report; the message names the mode that found none.
+    NoFiles(&'static str),
+    /// The report could not be written to its destination or copied to the clipboard.
+    Output(Box<dyn Error>),
 }
 
-/// Determines if a file is binary by checking for NUL bytes in the first 1024 bytes
-fn is_binary(file_path: &Path) -> bool {
-    if let Ok(mut file) = fs::File::open(file_path) {
-        let mut buffer = [0u8; 1024];
-        if let Ok(n) = file.read(&mut buffer) {
-            return buffer[..n].contains(&0);
+impl CliError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            // clap exits with 2 for usage errors, which here means that no file matched.
+            CliError::Usage(e) if !e.use_stderr() => ExitCode::SUCCESS,
+            CliError::Usage(_) | CliError::Other(_) | CliError::NoDirectories => ExitCode::from(1),
+            CliError::NoFiles(_) => ExitCode::from(2),
+            CliError::Output(_) => ExitCode::from(3),
         }
     }
-    true
 }
 
-/// Attempts to read a file as UTF-8, and if it fails, tries to decode using SHIFT_JIS.
-/// If both attempts fail, returns "[Cannot decode file content]".
-fn read_file_contents(file_path: &Path) -> String {
-    match fs::read_to_string(file_path) {
-        Ok(text) => text,
-        Err(_) => match fs::read(file_path) {
-            Ok(bytes) => {
-                let (cow, _, had_errors) = SHIFT_JIS.decode(&bytes);
-                if had_errors {
-                    "[Cannot decode file content]".to_string()
-                } else {
-                    cow.into_owned()
-                }
-            }
-            Err(_) => "[Cannot decode file content]".to_string(),
-        },
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(e) => write!(f, "{}", e),
+            CliError::Other(e) | CliError::Output(e) => write!(f, "{}", e),
+            CliError::NoDirectories => write!(f, "No valid directories specified or found."),
+            CliError::NoFiles(message) => write!(f, "{}", message),
+        }
     }
 }
 
-/// Recursively searches the specified directory and lists files that
-/// - Match allowed extensions OR are whitelisted filenames
-/// - Do not have ignored extensions
-/// - Are not ignored filenames
-///
-/// Files within ignored directories are not searched.
-fn collect_files(
-    directory: &Path,
-    allowed: &HashSet<String>,
-    ignore_exts: &HashSet<String>,
-    ignore_dirs: &HashSet<String>,
-    whitelist_filenames: &HashSet<String>,
-    ignore_files: &HashSet<String>,
-    extensionless_allowed: &HashSet<String>,
-) -> Vec<PathBuf> {
-    let walker = WalkDir::new(directory).into_iter().filter_entry(|e| {
-        if e.file_type().is_dir() {
-            if let Some(name) = e.file_name().to_str() {
-                return !ignore_dirs.contains(&name.to_string());
-            }
-        }
-        true
-    });
-    let mut files = Vec::new();
-    for entry in walker.filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() {
-            let path = entry.path();
-            let file_name_os = entry.file_name();
-            let file_name = file_name_os.to_string_lossy();
-            if whitelist_filenames.contains(file_name.as_ref()) {
-                files.push(path.to_path_buf());
-                continue;
-            }
-            if ignore_files.contains(file_name.as_ref()) {
-                continue;
-            }
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                let Some(ext_formatted) = normalize_extension(ext) else {
-                    continue;
-                };
-                if ignore_exts.contains(&ext_formatted) {
-                    continue;
-                }
-                if !allowed.is_empty() && !allowed.contains(&ext_formatted) {
-                    continue;
-                }
-            } else if !allowed.is_empty() && !extensionless_allowed.contains(file_name.as_ref()) {
-                continue;
-            }
-            files.push(path.to_path_buf());
-        }
+impl<E: Into<Box<dyn Error>>> From<E> for CliError {
+    fn from(e: E) -> Self {
+        CliError::Other(e.into())
     }
-    files.sort();
-    files
 }
 
-/// Generates a tree structure of the specified directory.
-fn build_tree(
-    directory: &Path,
-    allowed: &HashSet<String>,
-    ignore_exts: &HashSet<String>,
-    ignore_dirs: &HashSet<String>,
-    whitelist_filenames: &HashSet<String>,
-    ignore_files: &HashSet<String>,
-    extensionless_allowed: &HashSet<String>,
-) -> String {
-    let base_name = match directory.file_name().and_then(|s| s.to_str()) {
-        Some(s) => s.to_string(),
-        None => directory.to_string_lossy().into_owned(),
-    };
-    let mut lines = vec![base_name];
-    build_tree_helper(
-        directory,
-        "",
-        allowed,
-        ignore_exts,
-        ignore_dirs,
-        whitelist_filenames,
-        ignore_files,
-        extensionless_allowed,
-        &mut lines,
-    );
-    lines.join("\n")
+/// Whether a `--directory` entry has glob characters.
+fn is_glob(entry: &str) -> bool {
+    entry.contains(['*', '?', '[', '{'])
 }
 
-/// Helper function that recursively traverses the directory structure and builds the tree string
-#[allow(clippy::too_many_arguments)]
-fn build_tree_helper(
-    path: &Path,
-    prefix: &str,
-    allowed: &HashSet<String>,
-    ignore_exts: &HashSet<String>,
-    ignore_dirs: &HashSet<String>,
-    whitelist_filenames: &HashSet<String>,
-    ignore_files: &HashSet<String>,
-    extensionless_allowed: &HashSet<String>,
-    lines: &mut Vec<String>,
-) {
-    let mut entries: Vec<fs::DirEntry> = match fs::read_dir(path) {
-        Ok(iter) => iter.filter_map(|e| e.ok()).collect(),
-        Err(_) => return,
+/// Expands a `--directory` glob such as `crates/*` or `packages/*/src` into the directories
+/// it matches, in path order. Only the directory below the leading literal components is
+/// walked, and no deeper than the pattern reaches unless it has `**`.
+fn expand_directory_glob(pattern: &str) -> Result<Vec<PathBuf>, globset::Error> {
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher();
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components.iter().take_while(|c| !is_glob(c)).count();
+    let base = match components[..literal].join("/") {
+        base if base.is_empty() && pattern.starts_with('/') => "/".to_string(),
+        base => base,
     };
-    entries.sort_by_key(|e| e.file_name());
-    let mut filtered_entries = Vec::new();
-    for entry in entries {
-        let entry_path = entry.path();
-        let file_name_os = entry.file_name();
-        let name_buf = file_name_os.to_string_lossy().to_string();
-        let name = &name_buf;
-        if entry_path.is_dir() {
-            if ignore_dirs.contains(name) {
-                continue;
-            }
-            filtered_entries.push((entry, true));
-        } else if entry_path.is_file() {
-            if whitelist_filenames.contains(name) {
-                filtered_entries.push((entry, false));
-                continue;
-            }
-            if ignore_files.contains(name) {
-                continue;
-            }
-            if let Some(ext) = entry_path.extension().and_then(|e| e.to_str()) {
-                let Some(ext_formatted) = normalize_extension(ext) else {
-                    continue;
-                };
-                if ignore_exts.contains(&ext_formatted) {
-                    continue;
-                }
-                if !allowed.is_empty() && !allowed.contains(&ext_formatted) {
-                    continue;
-                }
-            } else if !allowed.is_empty() && !extensionless_allowed.contains(name.as_str()) {
-                continue;
-            }
-            filtered_entries.push((entry, false));
-        }
+    let mut walk = WalkDir::new(if base.is_empty() { "." } else { &base })
+        .min_depth(1)
+        .sort_by_file_name();
+    if !pattern.contains("**") {
+        walk = walk.max_depth(components.len() - literal);
     }
-    let count = filtered_entries.len();
-    for (i, (entry, is_dir)) in filtered_entries.into_iter().enumerate() {
-        let is_last = i == count - 1;
-        let connector = if is_last { "└── " } else { "├── " };
-        let name_buf = entry.file_name().to_string_lossy().to_string();
-        let name = &name_buf;
-        lines.push(format!("{}{}{}", prefix, connector, name));
-        if is_dir {
-            let new_prefix = if is_last {
-                format!("{}    ", prefix)
-            } else {
-                format!("{}│   ", prefix)
-            };
-            build_tree_helper(
-                &entry.path(),
-                &new_prefix,
-                allowed,
-                ignore_exts,
-                ignore_dirs,
-                whitelist_filenames,
-                ignore_files,
-                extensionless_allowed,
-                lines,
-            );
-        }
-    }
-}
-
-lazy_static! {
-    static ref DEFAULT_ALLOWED_EXTENSIONS: HashSet<String> = [
-        ".txt", ".md", ".py", ".js", ".java", ".cpp", ".c", ".cs", ".rb", ".go", ".rs", ".hpp",
-        ".ts", ".tsx", ".d.ts", ".jsx", ".toml", ".msg", ".srv", ".action", ".launch", ".urdf",
-        ".xacro", ".cfg",
-    ]
-    .iter()
-    .map(|s| s.to_string())
-    .collect();
-    static ref DEFAULT_IGNORE_DIRS: HashSet<String> = [
-        ".git",
-        ".vscode",
-        "target",
-        "node_modules",
-        "__pycache__",
-        ".idea",
-        "build",
-        "dist",
-        ".ruff_cache",
-        ".cache",
-        ".tox",
-        ".nox",
-        ".pytest_cache",
-        "htmlcov",
-        "instance",
-        ".env",
-        ".venv",
-        "env",
-        "venv",
-        "ENV",
-        "site",
-        ".mypy_cache",
-        "debug",
-    ]
-    .iter()
-    .map(|s| s.to_string())
-    .collect();
+    Ok(walk
+        .into_iter()
+        .filter_entry(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| {
+            // Entries under `.` are matched and scanned without the `./` prefix.
+            let path = entry.path();
+            path.strip_prefix(".").unwrap_or(path).to_path_buf()
+        })
+        .filter(|path| matcher.is_match(path))
+        .collect())
 }
 
-impl Config {
-    fn from_defaults() -> Self {
-        Config {
-            whitelist: WhitelistConfig {
-                extensions: sorted_strings(&DEFAULT_ALLOWED_EXTENSIONS),
-                files: vec![
-                    "Dockerfile".to_string(),
-                    "Makefile".to_string(),
-                    "justfile".to_string(),
-                ],
-            },
-            blacklist: BlacklistConfig {
-                extensions: vec![
-                    ".bin", ".zip", ".tar", ".gz", ".7z", ".rar", ".exe", ".dll", ".so", ".dylib",
-                    ".a", ".lib", ".obj", ".o", ".class", ".jar", ".war", ".ear", ".ipynb", ".jpg",
-                    ".jpeg", ".png", ".gif",
-                ]
-                .iter()
-                .map(|s| s.to_string())
-                .collect(),
-                files: vec![],
-                directories: sorted_strings(&DEFAULT_IGNORE_DIRS),
-            },
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(CliError::Usage(e)) => {
+            let _ = e.print();
+            CliError::Usage(e).exit_code()
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            e.exit_code()
         }
-    }
-
-    fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
-        let content = fs::read_to_string(path)
-            .map_err(|e| format!("Config file not found or unreadable: {}", e))?;
-        let config: Config =
-            toml::from_str(&content).map_err(|e| format!("Config TOML parse error: {}", e))?;
-        Ok(config)
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+fn run() -> Result<(), CliError> {
+    let matches = Args::command().try_get_matches().map_err(CliError::Usage)?;
+    let mut args = Args::from_arg_matches(&matches).map_err(CliError::Usage)?;
 
     if args.generate_config {
         let default_config = Config::from_defaults();
@@ -640,263 +1317,285 @@ fn main() -> Result<(), Box<dyn Error>> {
         return Ok(());
     }
 
-    let directories: Vec<PathBuf> = args
-        .directories
-        .split(',')
-        .filter_map(|s| {
-            let s = s.trim();
-            if s.is_empty() {
-                None
-            } else {
-                let path = PathBuf::from(s);
-                if !path.exists() {
-                    eprintln!("Warning: Directory not found, skipping: {}", path.display());
-                    None
-                } else if !path.is_dir() {
-                    eprintln!(
-                        "Warning: Path is not a directory, skipping: {}",
-                        path.display()
-                    );
-                    None
-                } else {
-                    Some(path)
-                }
-            }
-        })
-        .collect();
-
-    if directories.is_empty() {
-        eprintln!("Error: No valid directories specified or found.");
-        return Ok(());
-    }
-
-    let filters = if let Some(config_path) = &args.config {
-        let config = Config::from_file(config_path)
-            .map_err(|e| format!("Failed to load config file '{}': {}", config_path, e))?;
-        build_filter_rules_from_config(config)
-    } else {
-        build_filter_rules_from_cli(&args)
+    let config_path = match &args.config {
+        Some(path) => Some(PathBuf::from(path)),
+        None => find_project_config(&args.directories),
     };
-
-    let mut all_tree_text = String::new();
-    let mut all_file_contents = String::new();
-
-    for dir in &directories {
-        let dir_name_for_header = match dir.file_name().and_then(|s| s.to_str()) {
-            Some(s) => s.to_string(),
-            None => dir.to_string_lossy().into_owned(),
+    let mut filter_config = None;
+    if let Some(path) = &config_path {
+        let config_error = |e: &dyn fmt::Display| {
+            format!("Failed to load config file '{}': {}", path.display(), e)
         };
+        let config =
+            ProjectConfig::from_file(&path.to_string_lossy()).map_err(|e| config_error(&e))?;
+        let mut argv: Vec<OsString> = env::args_os().collect();
+        let config_args = config_arguments(&config, &matches).map_err(|e| config_error(&e))?;
+        argv.splice(1..1, config_args.into_iter().map(OsString::from));
+        // The command line parsed on its own, so an error here comes from the config.
+        let merged = Args::command().try_get_matches_from(argv).map_err(|e| {
+            let message = e.to_string();
+            config_error(
+                &message
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: "),
+            )
+        })?;
+        args = Args::from_arg_matches(&merged).map_err(CliError::Usage)?;
+        filter_config = config.filters();
+    }
+    let since = match &args.since {
+        Some(value) => {
+            Some(parse_since(value).map_err(|e| format!("Invalid --since value: {}", e))?)
+        }
+        None => None,
+    };
 
-        let tree_text = build_tree(
-            dir,
-            &filters.allowed,
-            &filters.ignore_exts,
-            &filters.ignore_dirs,
-            &filters.whitelist_filenames,
-            &filters.ignore_files,
-            &filters.extensionless_allowed,
-        );
-
-        all_tree_text.push_str(&format!(
-            "=== Tree for {} ===\n{}\n\n",
-            dir_name_for_header, tree_text
-        ));
-
-        let files = collect_files(
-            dir,
-            &filters.allowed,
-            &filters.ignore_exts,
-            &filters.ignore_dirs,
-            &filters.whitelist_filenames,
-            &filters.ignore_files,
-            &filters.extensionless_allowed,
+    let to_stdout = args.stdout || args.output == "-";
+    if args.clipboard && to_stdout {
+        return Err("`--clipboard` cannot be combined with writing to stdout (`-o -`)".into());
+    }
+    if args.clipboard && args.append {
+        return Err("`--append` cannot be combined with `--clipboard`".into());
+    }
+    if args.tee && args.clipboard {
+        return Err(
+            "`--tee` cannot be combined with `--clipboard`, which skips writing the file".into(),
         );
-
-        for file in files {
-            let relative_path = file.strip_prefix(dir).unwrap_or(&file).to_string_lossy();
-
-            let header = format!(
-                "--------------------------------------------------------------------------------\n{} (in {}):\n--------------------------------------------------------------------------------\n",
-                relative_path, dir_name_for_header
-            );
-            let size = fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
-            let content = if size > args.max_size {
-                "[File size exceeds limit; skipped]\n".to_string()
-            } else if is_binary(&file) {
-                "[Binary file skipped]\n".to_string()
-            } else {
-                read_file_contents(&file)
-            };
-            all_file_contents.push_str(&header);
-            all_file_contents.push_str(&content);
-            all_file_contents.push_str("\n\n");
-        }
     }
-
-    if !all_tree_text.is_empty() {
-        all_tree_text.pop();
-        all_tree_text.pop();
+    if args.tee && to_stdout {
+        return Err("`--tee` cannot be combined with writing to stdout (`-o -`)".into());
     }
-    if !all_file_contents.is_empty() {
-        all_file_contents.pop();
-        all_file_contents.pop();
+    for (copies, flag) in [(args.clipboard, "--clipboard"), (args.tee, "--tee")] {
+        if copies && args.format == OutputFormat::Jsonl && !args.tree_only {
+            return Err(format!(
+                "`--format jsonl` streams its output and cannot be combined with `{}`",
+                flag
+            )
+            .into());
+        }
     }
-
-    let output_text = format!(
-        "＜Directory Structure＞\n\n{}\n\n＜File Contents＞\n\n{}",
-        all_tree_text, all_file_contents
-    );
-
-    if args.clipboard {
-        #[cfg(feature = "clipboard")]
-        {
-            // Assumes arboard is set as optional = true and configured in features in Cargo.toml
-            match arboard::Clipboard::new() {
-                Ok(mut clipboard) => {
-                    clipboard.set_text(output_text)?;
-                    println!("Output content has been copied to the clipboard.");
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Failed to access the clipboard: {}. Try writing to a file instead.",
-                        e
-                    );
-                }
-            }
+    let split = match (args.split_size, args.split_tokens) {
+        (Some(bytes), _) => Some(("--split-size", SplitLimit::Bytes(bytes))),
+        (None, Some(tokens)) => Some(("--split-tokens", SplitLimit::Tokens(tokens))),
+        (None, None) => None,
+    };
+    if let Some((flag, _)) = split {
+        if args.clipboard {
+            return Err(format!("`{}` cannot be combined with `--clipboard`", flag).into());
         }
-        #[cfg(not(feature = "clipboard"))]
-        {
-            eprintln!("Clipboard feature is not enabled. Please compile with '--features clipboard' or use the -o option to write to a file.");
+        if args.tee {
+            return Err(format!("`{}` cannot be combined with `--tee`", flag).into());
         }
-    } else {
-        fs::write(&args.output, output_text)?;
-        println!("Output completed: {}", args.output);
-    }
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::env;
-    use std::fs;
-    use std::process;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    struct TestTempDir {
-        path: PathBuf,
-    }
-
-    impl TestTempDir {
-        fn new(name: &str) -> Self {
-            let unique = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_nanos();
-            let path =
-                env::temp_dir().join(format!("oreuit_{}_{}_{}", name, process::id(), unique));
-            fs::create_dir_all(&path).unwrap();
-            Self { path }
+        if args.append {
+            return Err(format!(
+                "`{}` writes numbered files and cannot be combined with `--append`",
+                flag
+            )
+            .into());
         }
+        if to_stdout {
+            return Err(format!(
+                "`{}` writes numbered files and cannot be combined with writing to stdout (`-o -`)",
+                flag
+            )
+            .into());
+        }
+    }
 
-        fn write_file(&self, relative: &str, content: &str) -> PathBuf {
-            let path = self.path.join(relative);
-            if let Some(parent) = path.parent() {
-                fs::create_dir_all(parent).unwrap();
+    if args.progress {
+        args.quiet = false;
+    }
+    if args.no_stats {
+        args.stats = false;
+    }
+    if args.hash_manifest.is_some() {
+        args.hashes = true;
+    }
+    if args.tree_stats {
+        for detail in [TreeDetail::Size, TreeDetail::Lines] {
+            if !args.tree_details.contains(&detail) {
+                args.tree_details.push(detail);
             }
-            fs::write(&path, content).unwrap();
-            path
         }
+        args.tree_dir_totals = true;
+    }
+    if let Some(max_lines) = args.max_lines_per_file {
+        args.head_lines = Some(max_lines - max_lines / 2);
+        args.tail_lines = Some(max_lines / 2);
     }
 
-    impl Drop for TestTempDir {
-        fn drop(&mut self) {
-            let _ = fs::remove_dir_all(&self.path);
+    // Clones of remote repositories, removed when dropped at the end of `main`, including
+    // on an early return with an error.
+    let mut checkouts: Vec<(RemoteCheckout, String)> = Vec::new();
+    let mut directories: Vec<PathBuf> = Vec::new();
+    if args.files_from.is_none() {
+        for s in args.directories.split(',').map(str::trim) {
+            if s.is_empty() {
+                continue;
+            }
+            if let Some(repo) = GitHubRepo::parse(s) {
+                if !args.quiet {
+                    eprintln!("Cloning {} ...", repo.name());
+                }
+                let checkout = repo.clone_shallow()?;
+                directories.push(checkout.path().to_path_buf());
+                checkouts.push((checkout, repo.name()));
+                continue;
+            }
+            let path = PathBuf::from(s);
+            if !path.exists() && is_glob(s) {
+                let matched = expand_directory_glob(s)
+                    .map_err(|e| format!("Invalid --directory glob '{}': {}", s, e))?;
+                if matched.is_empty() {
+                    eprintln!("Warning: No directory matches glob, skipping: {}", s);
+                }
+                directories.extend(matched);
+                continue;
+            }
+            if !path.exists() {
+                eprintln!("Warning: Directory not found, skipping: {}", path.display());
+            } else if !path.is_dir() && !path.is_file() {
+                eprintln!(
+                    "Warning: Path is not a directory or file, skipping: {}",
+                    path.display()
+                );
+            } else {
+                directories.push(path);
+            }
         }
     }
 
-    #[test]
-    fn config_loads_valid_toml() {
-        let temp_dir = TestTempDir::new("valid_toml");
-        let config_path = temp_dir.write_file(
-            "oreuit.toml",
-            r#"
-[whitelist]
-extensions = [".rs", "py"]
-files = ["Dockerfile"]
-
-[blacklist]
-extensions = [".png"]
-files = ["Cargo.lock"]
-directories = ["target"]
-"#,
-        );
-
-        let config = Config::from_file(config_path.to_str().unwrap()).unwrap();
+    // Overlapping entries such as `crates/*,crates/core` name the same directory twice.
+    let mut seen = HashSet::new();
+    directories.retain(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())));
 
-        assert_eq!(config.whitelist.extensions, vec![".rs", "py"]);
-        assert_eq!(config.whitelist.files, vec!["Dockerfile"]);
-        assert_eq!(config.blacklist.extensions, vec![".png"]);
-        assert_eq!(config.blacklist.files, vec!["Cargo.lock"]);
-        assert_eq!(config.blacklist.directories, vec!["target"]);
+    if directories.is_empty() && args.files_from.is_none() {
+        return Err(CliError::NoDirectories);
     }
 
-    #[test]
-    fn config_errors_on_missing_file() {
-        let temp_dir = TestTempDir::new("missing_config");
-        let missing_path = temp_dir.path.join("does_not_exist.toml");
-
-        let err = Config::from_file(missing_path.to_str().unwrap()).unwrap_err();
-
-        assert!(err
-            .to_string()
-            .contains("Config file not found or unreadable"));
+    let mut options = SummaryOptions::new();
+    options = match filter_config {
+        Some(config) => options.config(config),
+        None => apply_cli_filters(options, &args),
+    };
+    options = options
+        .directories(directories)
+        .respect_gitignore(args.respect_gitignore)
+        .oreuitignore(!args.no_oreuitignore)
+        .ignore_file_patterns(
+            args.ignore_file_pattern
+                .iter()
+                .flat_map(|names| split_list(names)),
+        )
+        .include_globs(args.include_glob.iter().cloned())
+        .exclude_globs(args.exclude_glob.iter().cloned())
+        .max_depth(args.max_depth)
+        .min_size(args.min_size)
+        .modified_since(since)
+        .follow_symlinks(args.follow_symlinks)
+        .hidden(args.hidden)
+        .max_size(args.max_size)
+        .max_size_for(args.max_size_for.iter().filter_map(|entry| {
+            let (ext, bytes) = entry.split_once('=')?;
+            Some((ext.to_string(), bytes.trim().parse().ok()?))
+        }))
+        .binary_threshold(args.binary_threshold)
+        .binary_preview(args.binary_preview)
+        .embed_images(args.embed_images)
+        .notebooks(args.notebooks)
+        .archive_listing(args.archive_listing)
+        .line_numbers(args.line_numbers)
+        .head_lines(args.head_lines)
+        .tail_lines(args.tail_lines)
+        .max_tokens(args.max_tokens)
+        .trim_strategy(args.trim_strategy)
+        .jobs(args.jobs)
+        .dedupe(args.dedupe)
+        .grep(args.grep.clone())
+        .grep_exclude(args.grep_exclude.clone())
+        .tree_details(args.tree_details.iter().copied())
+        .tree_dir_totals(args.tree_dir_totals)
+        .ascii_tree(args.ascii_tree)
+        .exclude_regex(args.exclude_regex.clone())
+        .git_tracked(args.git_tracked)
+        .changed_since(args.changed_since.clone())
+        .strip_comments(args.strip_comments)
+        .squeeze_blank(args.squeeze_blank)
+        .relative_to(args.relative_to.as_ref().map(PathBuf::from))
+        .order(args.sort)
+        .pin(&args.pin)
+        .whitelist_case_insensitive(args.whitelist_case_insensitive)
+        .normalize_newlines(args.normalize_newlines)
+        .trim_whitespace(args.trim_whitespace)
+        .trim_markdown(args.trim_markdown)
+        .stream_threshold(Some(args.stream_threshold).filter(|&bytes| bytes > 0))
+        .git_blame_summary(args.git_blame_summary)
+        .hashes(args.hashes)
+        .encodings(args.encoding.as_deref().map(split_list).unwrap_or_default());
+    for (checkout, name) in &checkouts {
+        options = options.directory_name(checkout.path(), name);
+    }
+    if let Some(source) = &args.files_from {
+        options = options.files(read_file_list(source, args.null)?);
     }
 
-    #[test]
-    fn config_errors_on_invalid_toml() {
-        let temp_dir = TestTempDir::new("invalid_toml");
-        let config_path = temp_dir.write_file(
-            "broken.toml",
-            r#"
-[whitelist
-extensions = [".rs"]
-"#,
+    // The list is drawn on stderr; stdout may be redirected to keep `--print-selection`.
+    let terminal =
+        io::stdin().is_terminal() && (io::stdout().is_terminal() || args.print_selection);
+    if args.interactive && !args.dry_run && !terminal {
+        eprintln!(
+            "Warning: --interactive needs a terminal on stdin and stdout; continuing with every selected file"
         );
-
-        let err = Config::from_file(config_path.to_str().unwrap()).unwrap_err();
-
-        assert!(err.to_string().contains("Config TOML parse error"));
     }
-
-    #[test]
-    fn extensions_are_normalized() {
-        assert_eq!(normalize_extension(".rs"), Some(".rs".to_string()));
-        assert_eq!(normalize_extension("rs"), Some(".rs".to_string()));
-        assert_eq!(normalize_extension(" RS "), Some(".rs".to_string()));
-
-        let normalized = collect_normalized_extensions([".rs", "rs", " RS ", ".PY", " py "]);
-
-        assert!(normalized.contains(".rs"));
-        assert!(normalized.contains(".py"));
-        assert_eq!(normalized.len(), 2);
+    let interactive = args.interactive && !args.dry_run && terminal;
+    let options = if interactive {
+        let planned = Summarizer::new(options.clone())?.dry_run();
+        let deselected = pick_files(&planned.files)?;
+        if deselected.len() == planned.files.len() {
+            return Err(CliError::NoFiles("--interactive: no files selected"));
+        }
+        options.deselect(deselected)
+    } else {
+        options
+    };
+    if args.print_selection {
+        let files = Summarizer::new(options)?.selected_files();
+        let mut out = io::stdout().lock();
+        for file in &files {
+            writeln!(out, "{}", file.display()).map_err(|e| output_error("stdout", e))?;
+        }
+        if files.is_empty() {
+            return Err(CliError::NoFiles("--print-selection: no files selected"));
+        }
+        return Ok(());
     }
-
-    #[test]
-    fn whitelist_filename_overrides_blacklist_file() {
-        let temp_dir = TestTempDir::new("whitelist_overrides_blacklist");
-        let target_file = temp_dir.write_file("config.toml", "name = 'oreuit'\n");
-
-        let files = collect_files(
-            &temp_dir.path,
-            &HashSet::new(),
-            &HashSet::new(),
-            &HashSet::new(),
-            &HashSet::from(["config.toml".to_string()]),
-            &HashSet::from(["config.toml".to_string()]),
-            &HashSet::new(),
-        );
-
-        assert_eq!(files, vec![target_file]);
+    let output = if to_stdout {
+        ReportOutput::stdout()
+    } else {
+        ReportOutput::file(&args.output)
     }
+    .gzip(args.gzip.then_some(args.gzip_level))
+    .append(args.append)
+    .split(split.map(|(_, limit)| limit));
+    if args.watch {
+        return watch(&args, options, &output);
+    }
+    let summarizer = Summarizer::new(options)?;
+    if args.dry_run {
+        let dry_run = summarizer.dry_run();
+        dry_run
+            .write_listing(&mut io::stdout().lock())
+            .map_err(|e| output_error("stdout", e))?;
+        if dry_run.files.is_empty() {
+            return Err(CliError::NoFiles("--dry-run: no files matched"));
+        }
+        return Ok(());
+    }
+    write_report(&args, &summarizer, &output)
+}
+
+fn output_error(destination: &str, e: io::Error) -> CliError {
+    CliError::Output(format!("Failed to write {}: {}", destination, e).into())
 }
@@ -0,0 +1,260 @@
+/// Nesting deeper than this is not a notebook; it also keeps the parser off deep recursion.
+const MAX_DEPTH: usize = 128;
+
+/// A parsed JSON value. Numbers are only checked, since no notebook field read here is one.
+enum Json {
+    Null,
+    Bool,
+    Number,
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: &str) -> Option<()> {
+        self.text[self.pos..].starts_with(expected).then(|| {
+            self.pos += expected.len();
+        })
+    }
+
+    fn value(&mut self, depth: usize) -> Option<Json> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.eat("}").is_some() {
+                    return Some(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.eat(":")?;
+                    fields.push((key, self.value(depth + 1)?));
+                    self.skip_whitespace();
+                    if self.eat("}").is_some() {
+                        return Some(Json::Object(fields));
+                    }
+                    self.eat(",")?;
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.eat("]").is_some() {
+                    return Some(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    if self.eat("]").is_some() {
+                        return Some(Json::Array(items));
+                    }
+                    self.eat(",")?;
+                }
+            }
+            b'"' => self.string().map(Json::String),
+            b't' => self.eat("true").map(|_| Json::Bool),
+            b'f' => self.eat("false").map(|_| Json::Bool),
+            b'n' => self.eat("null").map(|_| Json::Null),
+            _ => self.number(),
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        self.text[start..self.pos]
+            .parse::<f64>()
+            .ok()
+            .map(|_| Json::Number)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.eat("\"")?;
+        let mut out = String::new();
+        loop {
+            // Quotes and backslashes are ASCII, so the run before one is whole characters.
+            let rest = &self.text[self.pos..];
+            let run = rest.find(['"', '\\'])?;
+            out.push_str(&rest[..run]);
+            self.pos += run + 1;
+            if rest.as_bytes()[run] == b'"' {
+                return Some(out);
+            }
+            let escaped = self.peek()?;
+            self.pos += 1;
+            match escaped {
+                b'"' => out.push('"'),
+                b'\\' => out.push('\\'),
+                b'/' => out.push('/'),
+                b'b' => out.push('\u{8}'),
+                b'f' => out.push('\u{c}'),
+                b'n' => out.push('\n'),
+                b'r' => out.push('\r'),
+                b't' => out.push('\t'),
+                b'u' => {
+                    let high = self.hex4()?;
+                    let code = if (0xD800..0xDC00).contains(&high) && self.eat("\\u").is_some() {
+                        match self.hex4()? {
+                            low @ 0xDC00..=0xDFFF => {
+                                0x10000 + ((high - 0xD800) << 10) + low - 0xDC00
+                            }
+                            _ => 0xFFFD,
+                        }
+                    } else {
+                        high
+                    };
+                    out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.pos..self.pos + 4)?;
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        self.pos += 4;
+        Some(value)
+    }
+}
+
+fn parse(text: &str) -> Option<Json> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    (parser.pos == text.len()).then_some(value)
+}
+
+/// A cell's `source`, which is either one string or a list of lines.
+fn cell_source(source: &Json) -> Option<String> {
+    match source {
+        Json::String(s) => Some(s.clone()),
+        Json::Array(lines) => lines.iter().map(Json::as_str).collect(),
+        _ => None,
+    }
+}
+
+/// The markdown and code cells of a Jupyter notebook (nbformat 4), in order: markdown cells
+/// as they are, and code cells fenced with the kernel's language, e.g. ```` ```python ````.
+/// Outputs, attachments, and raw cells are dropped.
+///
+/// Returns `None` when `json` is not a notebook.
+pub(crate) fn notebook_source(json: &str) -> Option<String> {
+    let notebook = parse(json)?;
+    let metadata = notebook.get("metadata");
+    let kernel_language = metadata
+        .and_then(|m| m.get("kernelspec"))
+        .and_then(|kernel| kernel.get("language"))
+        .and_then(Json::as_str);
+    let info_language = metadata
+        .and_then(|m| m.get("language_info"))
+        .and_then(|info| info.get("name"))
+        .and_then(Json::as_str);
+    let language = kernel_language.or(info_language).unwrap_or("python");
+    let Json::Array(cells) = notebook.get("cells")? else {
+        return None;
+    };
+
+    let mut blocks = Vec::new();
+    for cell in cells {
+        let source = cell_source(cell.get("source")?)?;
+        let source = source.trim_end();
+        if source.trim().is_empty() {
+            continue;
+        }
+        match cell.get("cell_type").and_then(Json::as_str) {
+            Some("markdown") => blocks.push(source.to_string()),
+            Some("code") => {
+                let longest_run = source.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                let fence = "`".repeat(3.max(longest_run + 1));
+                blocks.push(format!("{}{}\n{}\n{}", fence, language, source, fence));
+            }
+            _ => {}
+        }
+    }
+    let mut out = blocks.join("\n\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notebooks_keep_markdown_and_fenced_code_cells() {
+        let notebook = include_str!("../tests/fixtures/notebook.ipynb");
+        assert_eq!(
+            notebook_source(notebook).unwrap(),
+            "# Loading data\n\
+             Read the CSV with `pandas` \u{2014} caf\u{e9} \u{1f600}.\n\
+             \n\
+             ```python\n\
+             import pandas as pd\n\
+             df = pd.read_csv(\"data.csv\")\n\
+             ```\n\
+             \n\
+             ````python\n\
+             print(\"```\")\n\
+             ````\n"
+        );
+
+        assert!(notebook_source("{\"cells\": [").is_none());
+        assert!(notebook_source("{\"nbformat\": 4}").is_none());
+        assert!(notebook_source("[1, 2] trailing").is_none());
+        assert_eq!(
+            notebook_source("{\"cells\": [], \"metadata\": {}}").as_deref(),
+            Some("")
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::render::{OutputFormat, SplitLimit};
+use crate::tokens::estimate_tokens;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Where a report is written: stdout, or a file that can be compressed, appended to, or
+/// split into numbered parts.
+///
+/// ```no_run
+/// use oreuit::{OutputFormat, RenderOptions, ReportOutput, Summarizer, SummaryOptions};
+///
+/// let summary = Summarizer::new(SummaryOptions::new().directories(["src"]))?.run()?;
+/// let output = ReportOutput::file("summary.txt").gzip(Some(6));
+/// let written = summary.write_to(OutputFormat::Text, &RenderOptions::default(), &output)?;
+/// assert_eq!(written.paths, ["summary.txt.gz"]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReportOutput {
+    /// `None` for stdout.
+    path: Option<String>,
+    gzip_level: Option<u32>,
+    append: bool,
+    split: Option<SplitLimit>,
+}
+
+/// What a `ReportOutput` was given to write.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WrittenReport {
+    /// Estimated token count of the written text, before compression.
+    pub tokens: usize,
+    /// The files written, each split part in order; empty for stdout.
+    pub paths: Vec<String>,
+}
+
+impl ReportOutput {
+    /// Writes the report to the file at `path`, replacing it.
+    pub fn file(path: impl Into<String>) -> Self {
+        ReportOutput {
+            path: Some(path.into()),
+            gzip_level: None,
+            append: false,
+            split: None,
+        }
+    }
+
+    /// Writes the report to stdout.
+    pub fn stdout() -> Self {
+        ReportOutput {
+            path: None,
+            ..ReportOutput::file("")
+        }
+    }
+
+    /// Compresses the report with gzip at this level (0-9); a file gets `.gz` appended
+    /// unless its name already ends with it.
+    pub fn gzip(mut self, level: Option<u32>) -> Self {
+        self.gzip_level = level;
+        self
+    }
+
+    /// Adds the report after the file's content instead of replacing it. A file that
+    /// already has content gets a separator first, which depends on the format: a line of
+    /// `=`, a `---` rule, or an `<!-- oreuit: appended run -->` comment. Has no effect on
+    /// stdout.
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Writes numbered parts within `limit` each instead of one file, as
+    /// `Summary::write_to` renders them; see `part_path`. Has no effect on stdout.
+    pub fn split(mut self, limit: Option<SplitLimit>) -> Self {
+        self.split = limit;
+        self
+    }
+
+    /// Whether the report goes to numbered parts; never for stdout.
+    pub fn is_split(&self) -> bool {
+        self.split_limit().is_some()
+    }
+
+    pub(crate) fn split_limit(&self) -> Option<SplitLimit> {
+        self.split.filter(|_| self.path.is_some())
+    }
+
+    /// The file the report is written to, with `.gz` appended when compressing; `None` for
+    /// stdout.
+    pub fn path(&self) -> Option<String> {
+        self.path
+            .as_deref()
+            .map(|path| self.compressed(path.to_string()))
+    }
+
+    /// The file of split part `part`, counted from 1: a zero-padded number goes before
+    /// the extension, so `summary.txt` gives `summary.001.txt` and `out.txt.gz` gives
+    /// `out.001.txt.gz`. `None` for stdout.
+    pub fn part_path(&self, part: usize) -> Option<String> {
+        let output = self.path.as_deref()?;
+        let (base, gz) = match output.strip_suffix(".gz") {
+            Some(base) => (base, ".gz"),
+            None => (output, ""),
+        };
+        let path = Path::new(base);
+        let numbered = match path.extension() {
+            Some(ext) => path.with_extension(format!("{:03}.{}", part, ext.to_string_lossy())),
+            None => path.with_extension(format!("{:03}", part)),
+        };
+        Some(self.compressed(format!("{}{}", numbered.to_string_lossy(), gz)))
+    }
+
+    fn compressed(&self, mut path: String) -> String {
+        if self.gzip_level.is_some() && !path.ends_with(".gz") {
+            path.push_str(".gz");
+        }
+        path
+    }
+
+    /// Writes `text`, a whole report in `format`, ignoring `split`.
+    pub fn write_text(
+        &self,
+        format: OutputFormat,
+        text: &str,
+    ) -> Result<WrittenReport, Box<dyn Error>> {
+        self.write_with(format, |out| {
+            out.write_all(text.as_bytes())?;
+            Ok(estimate_tokens(text))
+        })
+    }
+
+    /// Writes a report in `format` through `write`, which streams into the (possibly
+    /// compressed) writer it is given and returns the estimated token count. `split` is
+    /// ignored.
+    pub fn write_with(
+        &self,
+        format: OutputFormat,
+        write: impl FnOnce(&mut dyn Write) -> Result<usize, Box<dyn Error>>,
+    ) -> Result<WrittenReport, Box<dyn Error>> {
+        let Some(output_path) = self.path() else {
+            let tokens = write_streamed(io::stdout().lock(), self.gzip_level, write)
+                .map_err(|e| format!("Failed to write stdout: {}", e))?;
+            return Ok(WrittenReport {
+                tokens,
+                paths: Vec::new(),
+            });
+        };
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(self.append)
+            .truncate(!self.append)
+            .open(&output_path)
+            .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+        let separator = match file.metadata() {
+            Ok(metadata) if self.append && metadata.len() > 0 => append_separator(format),
+            _ => "",
+        };
+        let tokens = write_streamed(file, self.gzip_level, |out| {
+            out.write_all(separator.as_bytes())?;
+            write(out)
+        })
+        .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+        Ok(WrittenReport {
+            tokens,
+            paths: vec![output_path],
+        })
+    }
+
+    /// Writes each of `parts` to the file `part_path` gives it.
+    pub(crate) fn write_parts(&self, parts: &[String]) -> Result<WrittenReport, Box<dyn Error>> {
+        let mut written = WrittenReport::default();
+        for (i, part) in parts.iter().enumerate() {
+            let part_path = self
+                .part_path(i + 1)
+                .ok_or("Split parts need an output file")?;
+            let file = fs::File::create(&part_path)
+                .map_err(|e| format!("Failed to write {}: {}", part_path, e))?;
+            written.tokens += write_streamed(file, self.gzip_level, |out| {
+                out.write_all(part.as_bytes())?;
+                Ok(estimate_tokens(part))
+            })
+            .map_err(|e| format!("Failed to write {}: {}", part_path, e))?;
+            written.paths.push(part_path);
+        }
+        Ok(written)
+    }
+}
+
+/// What `ReportOutput::append` writes between the reports of two runs.
+fn append_separator(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Text => {
+            "\n================================================================================\n\n"
+        }
+        OutputFormat::Markdown => "\n---\n\n",
+        OutputFormat::Xml | OutputFormat::Html => "\n<!-- oreuit: appended run -->\n",
+        OutputFormat::Jsonl => "",
+    }
+}
+
+/// Writes a report to `destination` through `write`, which streams into the (optionally
+/// gzip-compressed) writer it is given and returns the estimated token count.
+fn write_streamed<W: Write>(
+    destination: W,
+    gzip_level: Option<u32>,
+    write: impl FnOnce(&mut dyn Write) -> Result<usize, Box<dyn Error>>,
+) -> Result<usize, Box<dyn Error>> {
+    let mut writer = BufWriter::new(destination);
+    let tokens = match gzip_level {
+        Some(level) => {
+            let mut encoder = GzEncoder::new(&mut writer, Compression::new(level));
+            let tokens = write(&mut encoder)?;
+            encoder.finish()?;
+            tokens
+        }
+        None => write(&mut writer)?,
+    };
+    writer.flush()?;
+    Ok(tokens)
+}
+
+/// Counts the lines written through it, which in JSON Lines output are the records.
+pub(crate) struct LineCounter<'a, W: Write + ?Sized> {
+    pub(crate) inner: &'a mut W,
+    pub(crate) lines: usize,
+}
+
+impl<W: Write + ?Sized> Write for LineCounter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.lines += buf[..written].iter().filter(|&&b| b == b'\n').count();
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TestTempDir;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn part_paths_number_before_the_extension() {
+        let part = |output: &str, gzip: Option<u32>| {
+            ReportOutput::file(output).gzip(gzip).part_path(2).unwrap()
+        };
+        assert_eq!(part("summary.txt", None), "summary.002.txt");
+        assert_eq!(part("out/summary", None), "out/summary.002");
+        assert_eq!(part("out.txt.gz", None), "out.002.txt.gz");
+        assert_eq!(part("summary.md", Some(6)), "summary.002.md.gz");
+        assert_eq!(ReportOutput::stdout().part_path(1), None);
+        assert_eq!(
+            ReportOutput::file("a.txt").gzip(Some(1)).path().unwrap(),
+            "a.txt.gz"
+        );
+    }
+
+    #[test]
+    fn appended_reports_follow_a_separator() {
+        let temp_dir = TestTempDir::new("report_output");
+        let path = temp_dir.path.join("summary.md");
+        let output = ReportOutput::file(path.to_string_lossy()).append(true);
+
+        output
+            .write_text(OutputFormat::Markdown, "first\n")
+            .unwrap();
+        let written = output
+            .write_text(OutputFormat::Markdown, "second\n")
+            .unwrap();
+        assert_eq!(written.paths, [path.to_string_lossy()]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "first\n\n---\n\nsecond\n"
+        );
+
+        let parts = ReportOutput::file(temp_dir.path.join("p.txt").to_string_lossy())
+            .gzip(Some(6))
+            .write_parts(&["one\n".to_string(), "two\n".to_string()])
+            .unwrap();
+        assert_eq!(parts.paths.len(), 2);
+        let mut second = String::new();
+        GzDecoder::new(fs::File::open(&parts.paths[1]).unwrap())
+            .read_to_string(&mut second)
+            .unwrap();
+        assert!(parts.paths[1].ends_with("p.002.txt.gz"));
+        assert_eq!(second, "two\n");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{output_error, CliError};
+use oreuit::{format_size, format_thousands, skip_report_json, Progress, SkippedEntry};
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Single-line progress indicator on stderr, redrawn in place.
+///
+/// Warnings go through `report` as well, so the line is cleared before they are printed
+/// and they never end up on the same line.
+pub(crate) struct ProgressLine {
+    enabled: bool,
+    /// Also print `Skipped` events, one line each.
+    verbose: bool,
+    /// `Skipped` events kept for `--report`.
+    skipped: Option<Mutex<Vec<SkippedEntry>>>,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    last_draw: Option<Instant>,
+    drawn: bool,
+}
+
+impl ProgressLine {
+    const WIDTH: usize = 79;
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    pub(crate) fn new(enabled: bool, verbose: bool, keep_skipped: bool) -> Self {
+        ProgressLine {
+            enabled,
+            verbose,
+            skipped: keep_skipped.then(|| Mutex::new(Vec::new())),
+            state: Mutex::new(ProgressState::default()),
+        }
+    }
+
+    pub(crate) fn report(&self, event: Progress) {
+        if let Progress::Skipped {
+            path,
+            name,
+            reason,
+            directory,
+        } = event
+        {
+            if let Some(skipped) = &self.skipped {
+                skipped.lock().unwrap().push(SkippedEntry {
+                    path: path.to_path_buf(),
+                    name,
+                    reason,
+                    directory,
+                });
+            }
+            if !self.verbose {
+                return;
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        let message = match event {
+            Progress::Warning(message) => {
+                Self::clear(&mut state);
+                eprintln!("Warning: {}", message);
+                return;
+            }
+            Progress::Skipped {
+                path,
+                reason,
+                directory,
+                ..
+            } => {
+                Self::clear(&mut state);
+                let slash = if directory { "/" } else { "" };
+                eprintln!("Skipped {}{}: {}", path.display(), slash, reason);
+                return;
+            }
+            _ if !self.enabled => return,
+            Progress::Scanning(dir) => format!("scanning {}", dir.display()),
+            Progress::Reading {
+                done,
+                total,
+                bytes,
+                path,
+            } => {
+                // Redrawing for every file slows down large runs; always show the last one.
+                let recent = state
+                    .last_draw
+                    .is_some_and(|last| last.elapsed() < Self::INTERVAL);
+                if recent && done < total {
+                    return;
+                }
+                format!(
+                    "reading files {}/{}, {} (current: {})",
+                    format_thousands(done),
+                    format_thousands(total),
+                    format_size(bytes),
+                    path.display()
+                )
+            }
+        };
+        let line: String = message.chars().take(Self::WIDTH).collect();
+        eprint!("\r\x1b[K{}", line);
+        state.last_draw = Some(Instant::now());
+        state.drawn = true;
+    }
+
+    /// Erases the line so the messages that follow start on a clean line.
+    pub(crate) fn finish(&self) {
+        Self::clear(&mut self.state.lock().unwrap());
+    }
+
+    /// Writes the `Skipped` events kept for `--report` to `path`, sorted by path. Reading in
+    /// parallel reports placeholders in no fixed order.
+    pub(crate) fn write_skip_report(&self, path: &str) -> Result<(), CliError> {
+        let mut entries = match &self.skipped {
+            Some(skipped) => skipped.lock().unwrap().clone(),
+            None => Vec::new(),
+        };
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        fs::write(path, skip_report_json(&entries)).map_err(|e| output_error(path, e))?;
+        println!("Skip report completed: {}", path);
+        Ok(())
+    }
+
+    fn clear(state: &mut ProgressState) {
+        if state.drawn {
+            eprint!("\r\x1b[K");
+            state.drawn = false;
+        }
+    }
+}
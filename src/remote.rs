@@ -0,0 +1,196 @@
+use crate::content::base64;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A GitHub repository given as `https://github.com/owner/repo`, optionally with `.git`
+/// and a `#branch` suffix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitHubRepo {
+    pub owner: String,
+    pub repo: String,
+    /// Branch or tag after `#`; the default branch when `None`.
+    pub branch: Option<String>,
+}
+
+impl GitHubRepo {
+    /// Recognizes `https://github.com/owner/repo[.git][/][#branch]`; anything else, such
+    /// as a local path, gives `None`. Owner and repository names are limited to ASCII
+    /// letters, digits, `.`, `_` and `-`, and a branch may not start with `-` or contain
+    /// whitespace or control characters, so neither can be read by git as an option.
+    pub fn parse(input: &str) -> Option<Self> {
+        let rest = input
+            .strip_prefix("https://github.com/")
+            .or_else(|| input.strip_prefix("http://github.com/"))?;
+        let (path, branch) = match rest.split_once('#') {
+            Some((path, branch)) => (path, Some(branch)),
+            None => (rest, None),
+        };
+        let mut segments = path.trim_end_matches('/').split('/');
+        let owner = segments.next()?;
+        let repo = segments.next()?;
+        let repo = repo.strip_suffix(".git").unwrap_or(repo);
+        if !is_name_segment(owner) || !is_name_segment(repo) || segments.next().is_some() {
+            return None;
+        }
+        let branch = branch.filter(|b| !b.is_empty());
+        if branch.is_some_and(|b| {
+            b.starts_with('-') || b.chars().any(|c| c.is_whitespace() || c.is_control())
+        }) {
+            return None;
+        }
+        Some(GitHubRepo {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            branch: branch.map(str::to_string),
+        })
+    }
+
+    /// `owner/repo`, used in place of the checkout's directory name in the report.
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.owner, self.repo)
+    }
+
+    /// Shallow-clones the repository into a new directory under the system temp directory.
+    ///
+    /// A non-empty `GITHUB_TOKEN` is sent as an HTTP header through git's environment, so
+    /// it does not show up in the command line or in the clone's config. Git never prompts
+    /// for credentials; a private repository without a usable token fails instead.
+    pub fn clone_shallow(&self) -> Result<RemoteCheckout, Box<dyn Error>> {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let checkout = RemoteCheckout {
+            path: env::temp_dir().join(format!(
+                "oreuit_{}_{}_{}_{}",
+                self.owner,
+                self.repo,
+                process::id(),
+                unique
+            )),
+        };
+        let mut command = Command::new("git");
+        command
+            .args(["clone", "--quiet", "--depth", "1"])
+            .env("GIT_TERMINAL_PROMPT", "0");
+        if let Some(branch) = &self.branch {
+            command.args(["--branch", branch]);
+        }
+        if let Some(token) = env::var("GITHUB_TOKEN").ok().filter(|t| !t.is_empty()) {
+            let credentials = base64(format!("x-access-token:{}", token).as_bytes());
+            command
+                .env("GIT_CONFIG_COUNT", "1")
+                .env("GIT_CONFIG_KEY_0", "http.https://github.com/.extraheader")
+                .env(
+                    "GIT_CONFIG_VALUE_0",
+                    format!("AUTHORIZATION: basic {}", credentials),
+                );
+        }
+        let url = format!("https://github.com/{}/{}.git", self.owner, self.repo);
+        let output = command
+            .arg("--")
+            .arg(&url)
+            .arg(&checkout.path)
+            .output()
+            .map_err(|e| format!("Failed to run git to clone {}: {}", url, e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "Failed to clone {}: {}",
+                url,
+                stderr
+                    .lines()
+                    .find(|line| !line.trim().is_empty())
+                    .map_or("git clone failed", |line| line
+                        .trim_start_matches("fatal: "))
+            )
+            .into());
+        }
+        Ok(checkout)
+    }
+}
+
+fn is_name_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment != "."
+        && segment != ".."
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// A temporary clone of a remote repository, deleted when dropped.
+#[derive(Debug)]
+pub struct RemoteCheckout {
+    path: PathBuf,
+}
+
+impl RemoteCheckout {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for RemoteCheckout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_urls_are_parsed_with_optional_branch() {
+        assert_eq!(
+            GitHubRepo::parse("https://github.com/yuki-inaho/oreuit#dev"),
+            Some(GitHubRepo {
+                owner: "yuki-inaho".to_string(),
+                repo: "oreuit".to_string(),
+                branch: Some("dev".to_string()),
+            })
+        );
+        let plain = GitHubRepo::parse("https://github.com/owner/repo.git/").unwrap();
+        assert_eq!(
+            (plain.name(), plain.branch),
+            ("owner/repo".to_string(), None)
+        );
+        assert_eq!(GitHubRepo::parse("https://github.com/owner"), None);
+        assert_eq!(GitHubRepo::parse("https://github.com/o/r/tree/main"), None);
+        assert_eq!(GitHubRepo::parse("src"), None);
+    }
+
+    #[test]
+    fn github_urls_with_unsafe_names_or_branches_are_rejected() {
+        for input in [
+            "https://github.com/owner/repo#--upload-pack=touch",
+            "https://github.com/owner/repo#-b",
+            "https://github.com/owner/repo#main branch",
+            "https://github.com/owner/repo#main\tx",
+            "https://github.com/owner/repo#main\n",
+            "https://github.com/owner/repo#\u{7f}",
+            "https://github.com/../repo",
+            "https://github.com/owner/.",
+            "https://github.com/owner/..git",
+            "https://github.com/own%20er/repo",
+            "https://github.com/owner/re po",
+            "https://github.com/owner/repo?x=1",
+            "https://github.com/owner/r\u{e9}po",
+        ] {
+            assert_eq!(GitHubRepo::parse(input), None, "{:?}", input);
+        }
+        assert_eq!(
+            GitHubRepo::parse("https://github.com/my.org/my_repo-2#release/v1.2-rc"),
+            Some(GitHubRepo {
+                owner: "my.org".to_string(),
+                repo: "my_repo-2".to_string(),
+                branch: Some("release/v1.2-rc".to_string()),
+            })
+        );
+    }
+}
@@ -0,0 +1,1767 @@
+use crate::content::FileSection;
+use crate::filter::normalize_extension;
+use crate::stats::SummaryStats;
+use crate::tokens::{estimate_tokens, format_size, format_thousands, TokenCounter};
+use crate::tree::TreeSection;
+use crate::SkippedEntry;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Output format of a rendered report.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Plain text with dashed-line file headers
+    Text,
+    /// Markdown with a fenced code block per file
+    Markdown,
+    /// `<documents>` XML in the shape recommended for Anthropic prompts
+    Xml,
+    /// JSON Lines: one object with the trees, then one object per file
+    Jsonl,
+    /// A standalone HTML page with a collapsible section per file
+    Html,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum XmlSkipped {
+    /// Emit skipped files with their placeholder as the document body
+    #[default]
+    Placeholder,
+    /// Leave skipped files out of the XML entirely
+    Omit,
+}
+
+/// Element layout of `OutputFormat::Xml`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum XmlLayout {
+    /// Numbered `<document>` elements, the tree first
+    #[default]
+    Documents,
+    /// A `<directory>` element per scanned directory holding its `<tree>` and `<file>`s
+    Summary,
+}
+
+/// Section headings and file separators of `OutputFormat::Text`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderStyle {
+    /// `＜Directory Structure＞` headings and dashed lines around each file header
+    #[default]
+    Default,
+    /// The default layout with ASCII `<...>` brackets
+    Ascii,
+    /// Markdown-like `#` headings and `## path` before each file, without separators
+    Minimal,
+}
+
+/// Where `--toc` puts the list of included files within the directory structure section.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TocPlacement {
+    /// Right after the directory structure heading, before the trees
+    #[default]
+    Before,
+    /// After the trees
+    After,
+}
+
+/// How large each part of a split report may grow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitLimit {
+    /// At most this many bytes per part, for `--split-size`.
+    Bytes(u64),
+    /// At most this many estimated tokens per part, for `--split-tokens`.
+    Tokens(u64),
+}
+
+impl SplitLimit {
+    fn measure(self, text: &str) -> u64 {
+        match self {
+            SplitLimit::Bytes(_) => text.len() as u64,
+            SplitLimit::Tokens(_) => estimate_tokens(text) as u64,
+        }
+    }
+
+    fn max(self) -> u64 {
+        match self {
+            SplitLimit::Bytes(max) | SplitLimit::Tokens(max) => max,
+        }
+    }
+}
+
+/// Settings that only affect how collected sections are rendered.
+#[derive(Default)]
+pub struct RenderOptions {
+    /// Annotate each file header with its estimated token count.
+    pub token_counts: bool,
+    /// How `OutputFormat::Xml` emits files whose content was skipped.
+    pub xml_skipped: XmlSkipped,
+    /// Element layout of `OutputFormat::Xml`.
+    pub xml_layout: XmlLayout,
+    /// Append a statistics footer after the file contents.
+    pub stats: bool,
+    /// Headings and separators used by `OutputFormat::Text`.
+    pub header_style: HeaderStyle,
+    /// Note the encoding in the header of files that were not UTF-8, e.g. `[EUC-JP]`.
+    pub show_encoding: bool,
+    /// List the included files with their size and line count next to the trees.
+    pub toc: Option<TocPlacement>,
+    /// Language tags by extension, e.g. `.gotmpl` → `go-template`, that extend or override
+    /// the built-in table. Keys are matched like `--extensions`, with or without the dot.
+    pub lang_map: HashMap<String, String>,
+    /// Note the language in each file header, e.g. `parser.rs (rust) (in src)`.
+    pub show_language: bool,
+    /// Render only the directory structure: the file contents and their heading, the
+    /// table of contents, and the statistics are left out.
+    pub tree_only: bool,
+    /// Leave out the directory structure section and its heading; the report starts with
+    /// the file contents, after the table of contents when there is one.
+    pub no_tree: bool,
+}
+
+/// Renders the `path (in dir)` label used in file headers.
+pub(crate) fn file_label(section: &FileSection, options: &RenderOptions) -> String {
+    let mut label = section.relative_path.clone();
+    if options.show_language {
+        if let Some(language) = section_language(section, &options.lang_map) {
+            label.push_str(&format!(" ({})", language));
+        }
+    }
+    label.push_str(&format!(" (in {})", section.dir_name));
+    if section.pinned {
+        label.push_str(" [PINNED]");
+    }
+    if options.show_encoding {
+        if let Some(encoding) = section.encoding {
+            label.push_str(&format!(" [{}]", encoding));
+        }
+    }
+    if let Some(commit) = &section.last_commit {
+        label.push_str(&format!(
+            " ({} by {}, {})",
+            commit.hash, commit.author, commit.date
+        ));
+    }
+    if let Some(sha256) = &section.sha256 {
+        label.push_str(&format!(" [sha256:{}]", &sha256[..12]));
+    }
+    if options.token_counts {
+        label.push_str(&format!(
+            ": ~{} token{}",
+            format_thousands(section.tokens),
+            if section.tokens == 1 { "" } else { "s" }
+        ));
+    }
+    label
+}
+
+/// One line per file for `--toc`, in output order: `path (in dir): 1,234 bytes, 56 lines`,
+/// or the first line of the placeholder for a skipped file. Sizes are those of the emitted
+/// content.
+fn toc_lines(sections: &[FileSection]) -> Vec<String> {
+    sections
+        .iter()
+        .map(|f| {
+            let summary = match f.skipped {
+                Some(_) => f.content.lines().next().unwrap_or_default().to_string(),
+                None => {
+                    let lines = f.line_count();
+                    format!(
+                        "{} bytes, {} line{}",
+                        format_thousands(f.text_len()),
+                        format_thousands(lines),
+                        if lines == 1 { "" } else { "s" }
+                    )
+                }
+            };
+            format!("{} (in {}): {}", f.relative_path, f.dir_name, summary)
+        })
+        .collect()
+}
+
+/// The `--toc` block used by the text and XML formats, or `None` without `--toc`.
+fn toc_text(sections: &[FileSection], options: &RenderOptions) -> Option<String> {
+    options.toc?;
+    let mut text = format!("Files ({}):", format_thousands(sections.len()));
+    for line in toc_lines(sections) {
+        text.push('\n');
+        text.push_str(&line);
+    }
+    Some(text)
+}
+
+/// Puts the `--toc` block before or after the tree text, separated by a blank line.
+fn with_toc(tree_text: String, sections: &[FileSection], options: &RenderOptions) -> String {
+    match (toc_text(sections, options), options.toc) {
+        (Some(toc), Some(TocPlacement::After)) => format!("{}\n\n{}", tree_text, toc),
+        (Some(toc), _) => format!("{}\n\n{}", toc, tree_text),
+        (None, _) => tree_text,
+    }
+}
+
+/// Headings and separators `render_text` uses for one `HeaderStyle`.
+struct TextDecorations {
+    directory: &'static str,
+    contents: &'static str,
+    statistics: &'static str,
+    /// `(before, after)` the directory name in each tree heading.
+    tree: (&'static str, &'static str),
+    /// Line drawn above and below each file label; `None` puts `## ` before it instead.
+    file_rule: Option<&'static str>,
+}
+
+const FILE_RULE: &str =
+    "--------------------------------------------------------------------------------";
+
+fn text_decorations(style: HeaderStyle) -> TextDecorations {
+    match style {
+        HeaderStyle::Default => TextDecorations {
+            directory: "＜Directory Structure＞",
+            contents: "＜File Contents＞",
+            statistics: "＜Statistics＞",
+            tree: ("=== Tree for ", " ==="),
+            file_rule: Some(FILE_RULE),
+        },
+        HeaderStyle::Ascii => TextDecorations {
+            directory: "<Directory Structure>",
+            contents: "<File Contents>",
+            statistics: "<Statistics>",
+            tree: ("=== Tree for ", " ==="),
+            file_rule: Some(FILE_RULE),
+        },
+        HeaderStyle::Minimal => TextDecorations {
+            directory: "# Directory Structure",
+            contents: "# File Contents",
+            statistics: "# Statistics",
+            tree: ("## Tree for ", ""),
+            file_rule: None,
+        },
+    }
+}
+
+/// A rendered report cut where it may be split into parts: `head` holds the headings and
+/// trees, each of `blocks` one file, and `foot` the rest. Concatenated in order they give
+/// the whole report.
+struct ReportPieces<'a> {
+    head: String,
+    blocks: Vec<Block<'a>>,
+    foot: String,
+}
+
+impl ReportPieces<'_> {
+    fn concat(self) -> String {
+        let mut out = self.head;
+        for block in &self.blocks {
+            out.push_str(&block.text());
+        }
+        out.push_str(&self.foot);
+        out
+    }
+
+    /// Writes the report to `out`, streaming the text of streamed files, and returns its
+    /// estimated token count.
+    fn write<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<usize> {
+        let mut tokens = TokenCounter::default();
+        out.write_all(self.head.as_bytes())?;
+        tokens.feed(&self.head);
+        for block in &self.blocks {
+            block.write(out, &mut tokens)?;
+        }
+        out.write_all(self.foot.as_bytes())?;
+        tokens.feed(&self.foot);
+        Ok(tokens.total())
+    }
+}
+
+/// How a block embeds a file's text.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Embed {
+    Raw,
+    /// Escaped like `escape_xml`.
+    Xml,
+    /// Escaped like `escape_xml`, without the final newline, for a `<pre>` element.
+    Html,
+    /// Escaped for the inside of a JSON string.
+    Json,
+}
+
+impl Embed {
+    fn push(self, out: &mut String, text: &str) {
+        match self {
+            Embed::Raw => out.push_str(text),
+            Embed::Xml | Embed::Html => push_escaped_xml(out, text),
+            Embed::Json => push_escaped_json(out, text),
+        }
+    }
+}
+
+/// The part of a report for one file, or one directory with `XmlLayout::Summary`. File
+/// texts are referenced rather than copied, so a streamed file is only read while the
+/// block is written.
+#[derive(Default)]
+struct Block<'a> {
+    parts: Vec<BlockPart<'a>>,
+}
+
+enum BlockPart<'a> {
+    Text(String),
+    File(&'a FileSection, Embed),
+}
+
+impl<'a> Block<'a> {
+    fn push_str(&mut self, text: &str) {
+        match self.parts.last_mut() {
+            Some(BlockPart::Text(last)) => last.push_str(text),
+            _ => self.parts.push(BlockPart::Text(text.to_string())),
+        }
+    }
+
+    fn push_file(&mut self, section: &'a FileSection, embed: Embed) {
+        self.parts.push(BlockPart::File(section, embed));
+    }
+
+    fn ends_with_newline(&self) -> bool {
+        match self.parts.last() {
+            Some(BlockPart::Text(text)) => text.ends_with('\n'),
+            Some(BlockPart::File(section, embed)) => {
+                *embed != Embed::Html && section.ends_with_newline()
+            }
+            None => false,
+        }
+    }
+
+    /// Passes the block to `write` a piece at a time.
+    fn for_each_chunk(&self, write: &mut dyn FnMut(&str) -> io::Result<()>) -> io::Result<()> {
+        for part in &self.parts {
+            match part {
+                BlockPart::Text(text) => write(text)?,
+                BlockPart::File(section, embed) => {
+                    let mut escaped = String::new();
+                    // For `Embed::Html`, a newline at the end of a chunk waits for the next
+                    // one, so the file's final newline can be left out.
+                    let mut held_newline = false;
+                    section.for_each_chunk(&mut |mut chunk| {
+                        escaped.clear();
+                        if *embed == Embed::Html {
+                            if held_newline && !chunk.is_empty() {
+                                escaped.push('\n');
+                                held_newline = false;
+                            }
+                            if let Some(rest) = chunk.strip_suffix('\n') {
+                                chunk = rest;
+                                held_newline = true;
+                            }
+                        }
+                        embed.push(&mut escaped, chunk);
+                        write(&escaped)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The whole block as text. A streamed file that can no longer be read ends where
+    /// reading failed.
+    fn text(&self) -> String {
+        let mut text = String::new();
+        let _ = self.for_each_chunk(&mut |chunk| {
+            text.push_str(chunk);
+            Ok(())
+        });
+        text
+    }
+
+    /// Writes the block to `out`, counting its tokens in `tokens`.
+    fn write<W: Write + ?Sized>(&self, out: &mut W, tokens: &mut TokenCounter) -> io::Result<()> {
+        self.for_each_chunk(&mut |chunk| {
+            tokens.feed(chunk);
+            out.write_all(chunk.as_bytes())
+        })
+    }
+}
+
+fn report_pieces<'a>(
+    format: OutputFormat,
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    if options.tree_only {
+        return tree_only_pieces(format, trees, options);
+    }
+    match format {
+        OutputFormat::Text => text_pieces(trees, sections, options),
+        OutputFormat::Markdown => markdown_pieces(trees, sections, options),
+        OutputFormat::Xml => match options.xml_layout {
+            XmlLayout::Documents => xml_pieces(trees, sections, options),
+            XmlLayout::Summary => xml_summary_pieces(trees, sections, options),
+        },
+        OutputFormat::Jsonl => jsonl_pieces(trees, sections, options),
+        OutputFormat::Html => html_pieces(trees, sections, options),
+    }
+}
+
+/// Renders the default plain-text report.
+pub(crate) fn render_text(
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+) -> String {
+    report_pieces(OutputFormat::Text, trees, sections, options).concat()
+}
+
+/// Renders a Markdown report with one fenced code block per file.
+pub(crate) fn render_markdown(
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+) -> String {
+    report_pieces(OutputFormat::Markdown, trees, sections, options).concat()
+}
+
+/// Renders XML in `options.xml_layout`.
+///
+/// `Documents` gives `<documents>`: the trees as the first document with source
+/// `DIRECTORY_TREE`, then one document per file in sorted order, and with `stats` a last
+/// document with source `STATISTICS`. `Summary` gives `<summary>` with one `<directory>`
+/// per tree, holding the `<tree>` and a `<file>` per file in that directory.
+pub(crate) fn render_xml(
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+) -> String {
+    report_pieces(OutputFormat::Xml, trees, sections, options).concat()
+}
+
+/// Renders a standalone HTML page: the trees in `<pre>` blocks, then each file in a
+/// collapsed `<details>` element whose `id` is derived from its path, e.g. `src-main-rs`.
+pub(crate) fn render_html(
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+) -> String {
+    report_pieces(OutputFormat::Html, trees, sections, options).concat()
+}
+
+/// Writes the report in `format` to `out`, reading streamed files a chunk at a time, and
+/// returns its estimated token count. The bytes are the same as the rendered text.
+pub(crate) fn write_report<W: Write + ?Sized>(
+    format: OutputFormat,
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+    out: &mut W,
+) -> io::Result<usize> {
+    report_pieces(format, trees, sections, options).write(out)
+}
+
+/// Renders the report as parts within `limit` each, for `--split-size` and `--split-tokens`.
+///
+/// A new part starts before the file that would take the current one past the limit, so a
+/// file's block is never split; a block larger than the limit gets a part of its own. Token
+/// counts are summed per block, so a part's total is an estimate like every other count.
+/// The trees always go in the first part and the statistics footer in the last. Each part
+/// starts with a `Part X of Y` line (an XML comment for `OutputFormat::Xml`, where every
+/// part is a complete `<documents>` element, and an HTML comment for `OutputFormat::Html`,
+/// where every part is a complete page). With `XmlLayout::Summary` a block is a whole
+/// `<directory>`, tree included, and every part is a complete `<summary>` element.
+/// `OutputFormat::Jsonl` parts have no `Part` line, so each stays valid JSON Lines.
+pub(crate) fn render_parts(
+    format: OutputFormat,
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+    limit: SplitLimit,
+) -> Vec<String> {
+    let pieces = report_pieces(format, trees, sections, options);
+    let mut used = limit.measure(&pieces.head);
+    let mut parts = vec![pieces.head];
+    let mut blocks_in_part = 0;
+    for block in pieces.blocks.iter().map(Block::text) {
+        let current = parts.last_mut().expect("parts starts non-empty");
+        let size = limit.measure(&block);
+        if blocks_in_part > 0 && used + size > limit.max() {
+            parts.push(block);
+            used = size;
+            blocks_in_part = 1;
+        } else {
+            current.push_str(&block);
+            used += size;
+            blocks_in_part += 1;
+        }
+    }
+    parts
+        .last_mut()
+        .expect("parts starts non-empty")
+        .push_str(&pieces.foot);
+
+    let total = parts.len();
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, body)| {
+            let body = body.trim_start_matches('\n');
+            if format == OutputFormat::Xml {
+                let root = match options.xml_layout {
+                    XmlLayout::Documents => "documents",
+                    XmlLayout::Summary => "summary",
+                };
+                let open = if i == 0 {
+                    String::new()
+                } else {
+                    format!("<{}>\n", root)
+                };
+                let close = if i + 1 == total {
+                    String::new()
+                } else {
+                    format!("</{}>\n", root)
+                };
+                format!(
+                    "<!-- Part {} of {} -->\n{}{}{}",
+                    i + 1,
+                    total,
+                    open,
+                    body,
+                    close
+                )
+            } else if format == OutputFormat::Html {
+                // The comment goes after the doctype, which has to come first.
+                let body = body.strip_prefix(HTML_PAGE_START).unwrap_or(body);
+                let close = if i + 1 == total { "" } else { HTML_PAGE_END };
+                format!(
+                    "{}<!-- Part {} of {} -->\n{}{}",
+                    HTML_PAGE_START,
+                    i + 1,
+                    total,
+                    body,
+                    close
+                )
+            } else if format == OutputFormat::Jsonl {
+                // A header line would not be JSON; the parts are numbered by file name.
+                body.to_string()
+            } else {
+                format!("Part {} of {}\n\n{}", i + 1, total, body)
+            }
+        })
+        .collect()
+}
+
+/// The directory structure section of each format on its own, as the `head` of pieces
+/// without blocks.
+fn tree_only_pieces<'a>(
+    format: OutputFormat,
+    trees: &[TreeSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let head = match format {
+        OutputFormat::Text => {
+            let style = text_decorations(options.header_style);
+            let tree_text = trees
+                .iter()
+                .map(|t| format!("{}{}{}\n{}", style.tree.0, t.dir_name, style.tree.1, t.tree))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            format!("{}\n\n{}\n", style.directory, tree_text)
+        }
+        OutputFormat::Markdown => {
+            let mut head = String::from("# Directory Structure\n");
+            for t in trees {
+                head.push_str(&format!(
+                    "\n## Tree for {}\n\n```\n{}\n```\n",
+                    t.dir_name, t.tree
+                ));
+            }
+            head
+        }
+        OutputFormat::Xml => {
+            let tree_text = trees
+                .iter()
+                .map(|t| format!("=== Tree for {} ===\n{}", t.dir_name, t.tree))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            match options.xml_layout {
+                XmlLayout::Documents => format!(
+                    "<documents>\n{}</documents>\n",
+                    xml_document(1, "DIRECTORY_TREE", &tree_text)
+                ),
+                XmlLayout::Summary => {
+                    let mut head = String::from("<summary>\n");
+                    for t in trees {
+                        head.push_str(&format!(
+                            "<directory name=\"{}\">\n{}</directory>\n",
+                            escape_xml(&t.dir_name),
+                            xml_element("tree", "", &t.tree)
+                        ));
+                    }
+                    head.push_str("</summary>\n");
+                    head
+                }
+            }
+        }
+        OutputFormat::Jsonl => jsonl_tree_line(trees),
+        OutputFormat::Html => {
+            let mut head = format!("{}<h1>Directory Structure</h1>\n", HTML_PAGE_START);
+            for t in trees {
+                head.push_str(&format!(
+                    "<h2>Tree for {}</h2>\n{}",
+                    escape_xml(&t.dir_name),
+                    html_pre(&t.tree)
+                ));
+            }
+            head.push_str(HTML_PAGE_END);
+            head
+        }
+    };
+    ReportPieces {
+        head,
+        blocks: Vec::new(),
+        foot: String::new(),
+    }
+}
+
+fn text_pieces<'a>(
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let style = text_decorations(options.header_style);
+    let tree_text = trees
+        .iter()
+        .map(|t| format!("{}{}{}\n{}", style.tree.0, t.dir_name, style.tree.1, t.tree))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let head = if options.no_tree {
+        let toc = toc_text(sections, options).map(|toc| format!("{}\n\n", toc));
+        format!("{}{}\n\n", toc.unwrap_or_default(), style.contents)
+    } else {
+        let tree_text = with_toc(tree_text, sections, options);
+        format!(
+            "{}\n\n{}\n\n{}\n\n",
+            style.directory, tree_text, style.contents
+        )
+    };
+    let mut blocks: Vec<Block> = sections
+        .iter()
+        .map(|f| {
+            let label = file_label(f, options);
+            let mut block = Block::default();
+            match style.file_rule {
+                Some(rule) => {
+                    let colon = if options.token_counts { "" } else { ":" };
+                    block.push_str(&format!("{rule}\n{}{}\n{rule}\n", label, colon));
+                }
+                None => block.push_str(&format!("## {}\n", label)),
+            }
+            block.push_file(f, Embed::Raw);
+            block
+        })
+        .collect();
+    let count = blocks.len();
+    for block in blocks.iter_mut().take(count.saturating_sub(1)) {
+        block.push_str("\n\n");
+    }
+    let mut foot = String::new();
+    if options.stats {
+        if !blocks
+            .last()
+            .map_or(head.ends_with('\n'), Block::ends_with_newline)
+        {
+            foot.push('\n');
+        }
+        foot.push_str(&format!("\n{}\n\n", style.statistics));
+        foot.push_str(&stats_text(trees.len(), sections));
+    }
+    ReportPieces { head, blocks, foot }
+}
+
+fn markdown_pieces<'a>(
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let mut toc = String::new();
+    if options.toc.is_some() {
+        toc.push_str(&format!(
+            "\n## Files ({})\n\n",
+            format_thousands(sections.len())
+        ));
+        for line in toc_lines(sections) {
+            toc.push_str(&format!("- {}\n", line));
+        }
+    }
+    let mut head = String::new();
+    if !options.no_tree {
+        head.push_str("# Directory Structure\n");
+        if options.toc == Some(TocPlacement::Before) {
+            head.push_str(&toc);
+        }
+        for t in trees {
+            head.push_str(&format!(
+                "\n## Tree for {}\n\n```\n{}\n```\n",
+                t.dir_name, t.tree
+            ));
+        }
+        if options.toc == Some(TocPlacement::After) {
+            head.push_str(&toc);
+        }
+        head.push('\n');
+    }
+    head.push_str("# File Contents\n");
+    if options.no_tree {
+        head.push_str(&toc);
+    }
+    let blocks = sections
+        .iter()
+        .map(|f| {
+            let fence = code_fence(f.longest_backtick_run());
+            let language = section_language(f, &options.lang_map).unwrap_or_default();
+            let mut block = Block::default();
+            block.push_str(&format!(
+                "\n### {}\n\n{}{}\n",
+                file_label(f, options),
+                fence,
+                language
+            ));
+            block.push_file(f, Embed::Raw);
+            if !f.ends_with_newline() {
+                block.push_str("\n");
+            }
+            block.push_str(&fence);
+            block.push_str("\n");
+            block
+        })
+        .collect();
+    let mut foot = String::new();
+    if options.stats {
+        foot.push_str("\n# Statistics\n\n");
+        for (indent, line) in SummaryStats::from_sections(trees.len(), sections).lines() {
+            foot.push_str(&format!("{}- {}\n", "  ".repeat(indent), line));
+        }
+    }
+    ReportPieces { head, blocks, foot }
+}
+
+fn xml_pieces<'a>(
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let tree_text = trees
+        .iter()
+        .map(|t| format!("=== Tree for {} ===\n{}", t.dir_name, t.tree))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let mut head = String::from("<documents>\n");
+    // Index of the first file's document, after the tree or the table of contents.
+    let mut first_index = 1;
+    if options.no_tree {
+        if let Some(toc) = toc_text(sections, options) {
+            head.push_str(&xml_document(1, "TOC", &toc));
+            first_index += 1;
+        }
+    } else {
+        let tree_text = with_toc(tree_text, sections, options);
+        head.push_str(&xml_document(1, "DIRECTORY_TREE", &tree_text));
+        first_index += 1;
+    }
+    let blocks: Vec<Block> = sections
+        .iter()
+        .filter(|f| f.skipped.is_none() || options.xml_skipped != XmlSkipped::Omit)
+        .enumerate()
+        .map(|(i, f)| {
+            let mut block = Block::default();
+            block.push_str(&format!(
+                "<document index=\"{}\">\n<source>{}</source>\n<document_contents>\n",
+                i + first_index,
+                escape_xml(&f.path.to_string_lossy())
+            ));
+            block.push_file(f, Embed::Xml);
+            if !f.ends_with_newline() {
+                block.push_str("\n");
+            }
+            block.push_str("</document_contents>\n</document>\n");
+            block
+        })
+        .collect();
+    let mut foot = String::new();
+    if options.stats {
+        foot.push_str(&xml_document(
+            blocks.len() + first_index,
+            "STATISTICS",
+            &stats_text(trees.len(), sections),
+        ));
+    }
+    foot.push_str("</documents>\n");
+    ReportPieces { head, blocks, foot }
+}
+
+fn xml_summary_pieces<'a>(
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let toc = toc_text(sections, options).map(|toc| xml_element("toc", "", &toc));
+    let mut head = String::from("<summary>\n");
+    if options.toc == Some(TocPlacement::Before) {
+        head.push_str(toc.as_deref().unwrap_or_default());
+    }
+    // Files come grouped by directory in the order of the trees.
+    let mut files = sections.iter().peekable();
+    let blocks = trees
+        .iter()
+        .map(|t| {
+            let mut block = Block::default();
+            block.push_str(&format!(
+                "<directory name=\"{}\">\n",
+                escape_xml(&t.dir_name)
+            ));
+            if !options.no_tree {
+                block.push_str(&xml_element("tree", "", &t.tree));
+            }
+            while let Some(f) = files.next_if(|f| f.dir_name == t.dir_name) {
+                if f.skipped.is_some() && options.xml_skipped == XmlSkipped::Omit {
+                    continue;
+                }
+                let mut attributes = format!(
+                    " path=\"{}\" size=\"{}\"",
+                    escape_xml(&f.relative_path),
+                    f.size
+                );
+                match f.skipped {
+                    Some(reason) => {
+                        attributes.push_str(&format!(" skipped=\"{}\"", reason.name()));
+                        block.push_str(&format!("<file{}></file>\n", attributes));
+                    }
+                    None => {
+                        block.push_str(&format!("<file{}>\n", attributes));
+                        block.push_file(f, Embed::Xml);
+                        if f.text_len() > 0 && !f.ends_with_newline() {
+                            block.push_str("\n");
+                        }
+                        block.push_str("</file>\n");
+                    }
+                }
+            }
+            block.push_str("</directory>\n");
+            block
+        })
+        .collect();
+    let mut foot = String::new();
+    if options.toc == Some(TocPlacement::After) {
+        foot.push_str(toc.as_deref().unwrap_or_default());
+    }
+    if options.stats {
+        foot.push_str(&xml_element(
+            "statistics",
+            "",
+            &stats_text(trees.len(), sections),
+        ));
+    }
+    foot.push_str("</summary>\n");
+    ReportPieces { head, blocks, foot }
+}
+
+/// `<name attributes>`, the escaped `text` on its own lines, and the closing tag.
+fn xml_element(name: &str, attributes: &str, text: &str) -> String {
+    let mut out = format!("<{}{}>\n{}", name, attributes, escape_xml(text));
+    if !text.is_empty() && !text.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!("</{}>\n", name));
+    out
+}
+
+fn xml_document(index: usize, source: &str, contents: &str) -> String {
+    let mut out = format!(
+        "<document index=\"{}\">\n<source>{}</source>\n<document_contents>\n{}",
+        index,
+        escape_xml(source),
+        escape_xml(contents)
+    );
+    if !contents.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str("</document_contents>\n</document>\n");
+    out
+}
+
+/// Renders the statistics footer as indented plain-text lines.
+fn stats_text(directories: usize, sections: &[FileSection]) -> String {
+    SummaryStats::from_sections(directories, sections)
+        .lines()
+        .into_iter()
+        .map(|(indent, line)| format!("{}{}\n", "  ".repeat(indent), line))
+        .collect()
+}
+
+/// Escapes the characters that are significant in XML text and attribute values.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    push_escaped_xml(&mut escaped, text);
+    escaped
+}
+
+fn push_escaped_xml(escaped: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+}
+
+const HTML_PAGE_START: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Directory Structure</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+pre { background: #f6f8fa; padding: 0.75em; overflow-x: auto; }
+summary { cursor: pointer; font-family: monospace; }
+</style>
+</head>
+<body>
+";
+
+const HTML_PAGE_END: &str = "</body>
+</html>
+";
+
+fn html_pieces<'a>(
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let ids = html_ids(sections);
+    let mut toc = String::new();
+    if options.toc.is_some() {
+        toc.push_str(&format!(
+            "<h2>Files ({})</h2>\n<ul>\n",
+            format_thousands(sections.len())
+        ));
+        for (line, id) in toc_lines(sections).iter().zip(&ids) {
+            toc.push_str(&format!(
+                "<li><a href=\"#{}\">{}</a></li>\n",
+                escape_xml(id),
+                escape_xml(line)
+            ));
+        }
+        toc.push_str("</ul>\n");
+    }
+    let mut head = String::from(HTML_PAGE_START);
+    if options.no_tree {
+        head.push_str(&toc);
+    } else {
+        head.push_str("<h1>Directory Structure</h1>\n");
+        if options.toc == Some(TocPlacement::Before) {
+            head.push_str(&toc);
+        }
+        for t in trees {
+            head.push_str(&format!(
+                "<h2>Tree for {}</h2>\n{}",
+                escape_xml(&t.dir_name),
+                html_pre(&t.tree)
+            ));
+        }
+        if options.toc == Some(TocPlacement::After) {
+            head.push_str(&toc);
+        }
+    }
+    head.push_str("<h1>File Contents</h1>\n");
+    let blocks = sections
+        .iter()
+        .zip(&ids)
+        .map(|(f, id)| {
+            let mut block = Block::default();
+            block.push_str(&format!(
+                "<details id=\"{}\">\n<summary>{} ({})</summary>\n<pre>\n",
+                escape_xml(id),
+                escape_xml(&file_label(f, options)),
+                format_size(f.size)
+            ));
+            block.push_file(f, Embed::Html);
+            block.push_str("</pre>\n</details>\n");
+            block
+        })
+        .collect();
+    let mut foot = String::new();
+    if options.stats {
+        foot.push_str("<h1>Statistics</h1>\n");
+        foot.push_str(&html_pre(&stats_text(trees.len(), sections)));
+    }
+    foot.push_str(HTML_PAGE_END);
+    ReportPieces { head, blocks, foot }
+}
+
+/// `<pre>` holding the escaped `text`. The newline after the tag is dropped by HTML
+/// parsers, so text that starts with a blank line keeps it.
+fn html_pre(text: &str) -> String {
+    let mut out = format!("<pre>\n{}", escape_xml(text));
+    if text.ends_with('\n') {
+        out.pop();
+    }
+    out.push_str("</pre>\n");
+    out
+}
+
+/// Element ids for the file sections: the relative path with every character other than
+/// letters, digits, `-`, and `_` replaced by `-`, and `-2`, `-3`, ... appended when the
+/// same path occurs in more than one directory.
+fn html_ids(sections: &[FileSection]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    sections
+        .iter()
+        .map(|f| {
+            let base: String = f
+                .relative_path
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            let mut id = base.clone();
+            let mut n = 1;
+            while !seen.insert(id.clone()) {
+                n += 1;
+                id = format!("{}-{}", base, n);
+            }
+            id
+        })
+        .collect()
+}
+
+/// Renders `OutputFormat::Jsonl`: the tree line, then one line per file. Token counts,
+/// `--toc`, and `--stats` do not apply; `no_tree` leaves out the tree line.
+pub(crate) fn render_jsonl(
+    trees: &[TreeSection],
+    sections: &[FileSection],
+    options: &RenderOptions,
+) -> String {
+    jsonl_pieces(trees, sections, options).concat()
+}
+
+fn jsonl_pieces<'a>(
+    trees: &[TreeSection],
+    sections: &'a [FileSection],
+    options: &RenderOptions,
+) -> ReportPieces<'a> {
+    let head = if options.no_tree {
+        String::new()
+    } else {
+        jsonl_tree_line(trees)
+    };
+    ReportPieces {
+        head,
+        blocks: sections.iter().map(jsonl_file_block).collect(),
+        foot: String::new(),
+    }
+}
+
+/// The first line of `OutputFormat::Jsonl`, e.g.
+/// `{"trees":[{"directory":"src","tree":"src\n└── lib.rs"}]}`.
+pub(crate) fn jsonl_tree_line(trees: &[TreeSection]) -> String {
+    let trees: Vec<String> = trees
+        .iter()
+        .map(|t| {
+            format!(
+                "{{\"directory\":{},\"tree\":{}}}",
+                json_string(&t.dir_name),
+                json_string(&t.tree)
+            )
+        })
+        .collect();
+    format!("{{\"trees\":[{}]}}\n", trees.join(","))
+}
+
+/// One file's line of `OutputFormat::Jsonl`: `path` as in file headers, the scanned
+/// `directory`, the on-disk `size`, and the `content`. A file replaced with a placeholder
+/// also gets `"skipped"` with the reason's name, e.g. `"binary"`.
+fn jsonl_file_block(section: &FileSection) -> Block<'_> {
+    let mut block = Block::default();
+    block.push_str(&format!(
+        "{{\"path\":{},\"directory\":{},\"size\":{},\"content\":\"",
+        json_string(&section.relative_path),
+        json_string(&section.dir_name),
+        section.size
+    ));
+    block.push_file(section, Embed::Json);
+    block.push_str("\"");
+    if let Some(reason) = section.skipped {
+        block.push_str(&format!(",\"skipped\":{}", json_string(reason.name())));
+    }
+    block.push_str("}\n");
+    block
+}
+
+/// Writes one file's line of `OutputFormat::Jsonl` to `out`, streaming the text of a
+/// streamed file, and returns its estimated token count.
+pub(crate) fn write_jsonl_file_line<W: Write + ?Sized>(
+    section: &FileSection,
+    out: &mut W,
+) -> io::Result<usize> {
+    let mut tokens = TokenCounter::default();
+    jsonl_file_block(section).write(out, &mut tokens)?;
+    Ok(tokens.total())
+}
+
+/// The `--report` file: the number of entries per reason `name`, then every entry in the
+/// given order, one per line.
+///
+/// ```text
+/// {
+///   "counts": {"binary": 1, "ignored-dir": 1},
+///   "skipped": [
+///     {"path": "assets/logo.bin", "reason": "binary", "description": "binary", "directory": false},
+///     {"path": "target", "reason": "ignored-dir", "description": "ignored directory", "directory": true}
+///   ]
+/// }
+/// ```
+pub fn skip_report_json(entries: &[SkippedEntry]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(entry.name).or_insert(0) += 1;
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(name, count)| format!("{}: {}", json_string(name), count))
+        .collect();
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "    {{\"path\": {}, \"reason\": {}, \"description\": {}, \"directory\": {}}}",
+                json_string(&entry.path.to_string_lossy()),
+                json_string(entry.name),
+                json_string(entry.reason),
+                entry.directory
+            )
+        })
+        .collect();
+    let skipped = if lines.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n  ]", lines.join(",\n"))
+    };
+    format!(
+        "{{\n  \"counts\": {{{}}},\n  \"skipped\": {}\n}}\n",
+        counts.join(", "),
+        skipped
+    )
+}
+
+/// Quotes `text` as a JSON string. Control characters are escaped, so the result never
+/// spans lines; everything else, including non-ASCII text, is kept as is.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    push_escaped_json(&mut quoted, text);
+    quoted.push('"');
+    quoted
+}
+
+fn push_escaped_json(quoted: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => quoted.push(c),
+        }
+    }
+}
+
+/// Returns a backtick fence longer than the longest backtick run of a file's text.
+fn code_fence(longest_backtick_run: usize) -> String {
+    "`".repeat((longest_backtick_run + 1).max(3))
+}
+
+/// The language tag of a file from its name, or for a script without an extension from
+/// the interpreter on its `#!` line.
+fn section_language(section: &FileSection, overrides: &HashMap<String, String>) -> Option<String> {
+    language_for_path(&section.path, overrides).or_else(|| {
+        let interpreter = section.interpreter.as_deref()?;
+        // `python3.11` is tagged like `python`.
+        let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        let language = match name {
+            "sh" | "bash" | "dash" | "ksh" => "bash",
+            "node" | "nodejs" | "deno" | "bun" => "javascript",
+            "python" | "pypy" => "python",
+            "Rscript" => "r",
+            "" => interpreter,
+            _ => name,
+        };
+        Some(language.to_string())
+    })
+}
+
+/// Maps a file to the language tag used for Markdown code fences and `show_language`.
+///
+/// Every extension of the name is tried, longest first, so `.d.ts` comes before `.ts`:
+/// first in `overrides`, then in the built-in table. Unknown extensions fall back to the
+/// last extension without its dot, e.g. `proto`; names without one give `None`.
+fn language_for_path(path: &Path, overrides: &HashMap<String, String>) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+    let file_name = name.to_lowercase();
+    // A leading dot is part of the name, as in `.gitignore`.
+    let extensions: Vec<&str> = file_name
+        .char_indices()
+        .skip(1)
+        .filter(|&(_, c)| c == '.')
+        .map(|(i, _)| &file_name[i..])
+        .filter(|ext| ext.len() > 1)
+        .collect();
+    let overridden = extensions.iter().find_map(|ext| {
+        overrides
+            .iter()
+            .find(|(key, _)| normalize_extension(key).as_deref() == Some(*ext))
+    });
+    if let Some((_, language)) = overridden {
+        return Some(language.clone());
+    }
+    let builtin = match name.as_ref() {
+        "Dockerfile" => Some("dockerfile"),
+        "Makefile" | "makefile" => Some("makefile"),
+        "justfile" => Some("just"),
+        _ => extensions.iter().find_map(|ext| builtin_language(ext)),
+    };
+    match builtin {
+        Some(language) => Some(language.to_string()),
+        None => extensions.last().map(|ext| ext[1..].to_string()),
+    }
+}
+
+/// Built-in language tag of a normalized extension such as `.rs`.
+fn builtin_language(ext: &str) -> Option<&'static str> {
+    let language = match ext {
+        ".rs" => "rust",
+        ".py" => "python",
+        ".js" | ".mjs" | ".cjs" => "javascript",
+        ".jsx" => "jsx",
+        ".ts" => "typescript",
+        ".tsx" => "tsx",
+        ".java" => "java",
+        ".c" | ".h" => "c",
+        ".cpp" | ".cc" | ".cxx" | ".hpp" | ".hh" => "cpp",
+        ".cs" => "csharp",
+        ".rb" => "ruby",
+        ".go" => "go",
+        ".kt" => "kotlin",
+        ".swift" => "swift",
+        ".php" => "php",
+        ".scala" => "scala",
+        ".lua" => "lua",
+        ".sh" | ".bash" => "bash",
+        ".sql" => "sql",
+        ".html" | ".htm" => "html",
+        ".css" => "css",
+        ".json" => "json",
+        ".yaml" | ".yml" => "yaml",
+        ".toml" => "toml",
+        ".md" => "markdown",
+        ".xml" | ".launch" | ".urdf" | ".xacro" => "xml",
+        ".cfg" | ".ini" => "ini",
+        ".proto" => "protobuf",
+        ".hcl" | ".tf" => "hcl",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::SkipReason;
+    use std::path::PathBuf;
+
+    #[test]
+    fn markdown_uses_language_tags_and_safe_fences() {
+        let trees = vec![TreeSection {
+            dir_name: "src".to_string(),
+            tree: "src\n└── main.rs".to_string(),
+        }];
+        let sections = vec![
+            FileSection {
+                path: PathBuf::from("src/main.rs"),
+                relative_path: "main.rs".to_string(),
+                dir_name: "src".to_string(),
+                content: "fn main() {}\n".to_string(),
+                skipped: None,
+                tokens: 0,
+                encoding: None,
+                size: 0,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+            FileSection {
+                path: PathBuf::from("src/notes.unknown"),
+                relative_path: "notes.unknown".to_string(),
+                dir_name: "src".to_string(),
+                content: "```\nnested\n```".to_string(),
+                skipped: None,
+                tokens: 0,
+                encoding: None,
+                size: 0,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+        ];
+
+        let markdown = render_markdown(&trees, &sections, &RenderOptions::default());
+
+        assert!(markdown.contains("```\nsrc\n└── main.rs\n```\n"));
+        assert!(markdown.contains("### main.rs (in src)\n\n```rust\nfn main() {}\n```\n"));
+        assert!(markdown
+            .contains("### notes.unknown (in src)\n\n````unknown\n```\nnested\n```\n````\n"));
+    }
+
+    #[test]
+    fn language_tags_use_overrides_multi_dot_extensions_and_bare_fallback() {
+        let builtin = HashMap::new();
+        let language = |name: &str, overrides: &HashMap<String, String>| {
+            language_for_path(Path::new(name), overrides)
+        };
+        assert_eq!(language("a/lib.PY", &builtin).as_deref(), Some("python"));
+        assert_eq!(
+            language("Dockerfile", &builtin).as_deref(),
+            Some("dockerfile")
+        );
+        assert_eq!(language("data.msg", &builtin).as_deref(), Some("msg"));
+        assert_eq!(
+            language("types.d.ts", &builtin).as_deref(),
+            Some("typescript")
+        );
+        assert_eq!(language(".gitignore", &builtin), None);
+        assert_eq!(language("LICENSE", &builtin), None);
+
+        let overrides = HashMap::from([
+            ("gotmpl".to_string(), "go-template".to_string()),
+            (".d.ts".to_string(), "dts".to_string()),
+            (".json".to_string(), "jsonc".to_string()),
+        ]);
+        assert_eq!(
+            language("page.gotmpl", &overrides).as_deref(),
+            Some("go-template")
+        );
+        assert_eq!(language("types.d.ts", &overrides).as_deref(), Some("dts"));
+        assert_eq!(
+            language("app.ts", &overrides).as_deref(),
+            Some("typescript")
+        );
+        assert_eq!(
+            language("tsconfig.json", &overrides).as_deref(),
+            Some("jsonc")
+        );
+
+        let section = FileSection {
+            path: PathBuf::from("src/parser.rs"),
+            relative_path: "parser.rs".to_string(),
+            dir_name: "src".to_string(),
+            content: String::new(),
+            skipped: None,
+            tokens: 0,
+            encoding: None,
+            size: 0,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        };
+        let options = RenderOptions {
+            show_language: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(file_label(&section, &options), "parser.rs (rust) (in src)");
+        let script = |interpreter: &str| FileSection {
+            path: PathBuf::from("bin/deploy"),
+            interpreter: Some(interpreter.to_string()),
+            ..section.clone()
+        };
+        assert_eq!(
+            section_language(&script("python3.11"), &builtin).as_deref(),
+            Some("python")
+        );
+        assert_eq!(
+            section_language(&script("dash"), &builtin).as_deref(),
+            Some("bash")
+        );
+        assert_eq!(
+            section_language(&script("perl"), &builtin).as_deref(),
+            Some("perl")
+        );
+    }
+
+    #[test]
+    fn xml_documents_are_indexed_and_escaped() {
+        let trees = vec![TreeSection {
+            dir_name: "d".to_string(),
+            tree: "d\n├── a&b.rs\n└── c.bin".to_string(),
+        }];
+        let sections = vec![
+            FileSection {
+                path: PathBuf::from("d/a&b.rs"),
+                relative_path: "a&b.rs".to_string(),
+                dir_name: "d".to_string(),
+                content: "if a < b && b > c {}\n".to_string(),
+                skipped: None,
+                tokens: 0,
+                encoding: None,
+                size: 0,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+            FileSection {
+                path: PathBuf::from("d/c.bin"),
+                relative_path: "c.bin".to_string(),
+                dir_name: "d".to_string(),
+                content: SkipReason::Binary.placeholder().to_string(),
+                skipped: Some(SkipReason::Binary),
+                tokens: 0,
+                encoding: None,
+                size: 0,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+        ];
+
+        let xml = render_xml(&trees, &sections, &RenderOptions::default());
+        assert!(
+            xml.starts_with("<documents>\n<document index=\"1\">\n<source>DIRECTORY_TREE</source>")
+        );
+        assert!(xml.contains("├── a&amp;b.rs"));
+        assert!(xml.contains(
+            "<document index=\"2\">\n<source>d/a&amp;b.rs</source>\n<document_contents>\nif a &lt; b &amp;&amp; b &gt; c {}\n</document_contents>"
+        ));
+        assert!(xml.contains("<document index=\"3\">\n<source>d/c.bin</source>"));
+        assert!(xml.ends_with("</document>\n</documents>\n"));
+
+        let omit = RenderOptions {
+            xml_skipped: XmlSkipped::Omit,
+            ..Default::default()
+        };
+        let omitted = render_xml(&trees, &sections, &omit);
+        assert!(!omitted.contains("<source>d/c.bin</source>"));
+        assert!(!omitted.contains("index=\"3\""));
+    }
+
+    #[test]
+    fn html_escapes_contents_and_links_each_file() {
+        let trees = vec![TreeSection {
+            dir_name: "crate".to_string(),
+            tree: "crate\n└── src\n    └── main.rs".to_string(),
+        }];
+        let section = |dir: &str, content: &str| FileSection {
+            path: PathBuf::from(dir).join("src/main.rs"),
+            relative_path: "src/main.rs".to_string(),
+            dir_name: dir.to_string(),
+            content: content.to_string(),
+            skipped: None,
+            tokens: 0,
+            encoding: None,
+            size: 2048,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        };
+        let sections = vec![
+            section("crate", "\nif a < b && c > d {}\n"),
+            section("other", "</pre><script>"),
+        ];
+        let options = RenderOptions {
+            toc: Some(TocPlacement::Before),
+            ..RenderOptions::default()
+        };
+
+        let html = render_html(&trees, &sections, &options);
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<a href=\"#src-main-rs-2\">src/main.rs (in other): "));
+        assert!(html.contains("<pre>\ncrate\n└── src\n    └── main.rs</pre>\n"));
+        assert!(html.contains(
+            "<details id=\"src-main-rs\">\n<summary>src/main.rs (in crate) (2.0 KB)</summary>\n<pre>\n\nif a &lt; b &amp;&amp; c &gt; d {}</pre>\n</details>\n"
+        ));
+        assert!(html.contains("<pre>\n&lt;/pre&gt;&lt;script&gt;</pre>"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+
+        let parts = render_parts(
+            OutputFormat::Html,
+            &trees,
+            &sections,
+            &RenderOptions::default(),
+            SplitLimit::Bytes(1),
+        );
+        assert_eq!(parts.len(), 2);
+        for (i, part) in parts.iter().enumerate() {
+            assert!(part.starts_with(HTML_PAGE_START));
+            assert!(part.contains(&format!("<!-- Part {} of 2 -->", i + 1)));
+            assert!(part.ends_with(HTML_PAGE_END));
+        }
+    }
+
+    #[test]
+    fn jsonl_escapes_each_file_onto_one_line() {
+        let trees = vec![TreeSection {
+            dir_name: "crate".to_string(),
+            tree: "crate\n└── a.rs".to_string(),
+        }];
+        let sections = vec![FileSection {
+            path: PathBuf::from("crate/a.rs"),
+            relative_path: "a.rs".to_string(),
+            dir_name: "crate".to_string(),
+            content: "say(\"hi\")\r\n\t\\ é\u{1}".to_string(),
+            skipped: None,
+            tokens: 0,
+            encoding: None,
+            size: 20,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        }];
+
+        assert_eq!(
+            render_jsonl(&trees, &sections, &RenderOptions::default()),
+            "{\"trees\":[{\"directory\":\"crate\",\"tree\":\"crate\\n└── a.rs\"}]}\n{\"path\":\"a.rs\",\"directory\":\"crate\",\"size\":20,\"content\":\"say(\\\"hi\\\")\\r\\n\\t\\\\ é\\u0001\"}\n"
+        );
+    }
+
+    #[test]
+    fn xml_summary_groups_files_by_directory() {
+        let trees = vec![
+            TreeSection {
+                dir_name: "a".to_string(),
+                tree: "a\n└── x.rs".to_string(),
+            },
+            TreeSection {
+                dir_name: "b\"q".to_string(),
+                tree: "b\"q\n└── y.bin".to_string(),
+            },
+        ];
+        let sections = vec![
+            FileSection {
+                path: PathBuf::from("a/x.rs"),
+                relative_path: "x.rs".to_string(),
+                dir_name: "a".to_string(),
+                content: "a < b".to_string(),
+                skipped: None,
+                tokens: 0,
+                encoding: None,
+                size: 5,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+            FileSection {
+                path: PathBuf::from("b/y.bin"),
+                relative_path: "y.bin".to_string(),
+                dir_name: "b\"q".to_string(),
+                content: SkipReason::Binary.placeholder().to_string(),
+                skipped: Some(SkipReason::Binary),
+                tokens: 0,
+                encoding: None,
+                size: 2048,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+        ];
+        let summary = RenderOptions {
+            xml_layout: XmlLayout::Summary,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_xml(&trees, &sections, &summary),
+            "<summary>\n<directory name=\"a\">\n<tree>\na\n└── x.rs\n</tree>\n<file path=\"x.rs\" size=\"5\">\na &lt; b\n</file>\n</directory>\n<directory name=\"b&quot;q\">\n<tree>\nb&quot;q\n└── y.bin\n</tree>\n<file path=\"y.bin\" size=\"2048\" skipped=\"binary\"></file>\n</directory>\n</summary>\n"
+        );
+        let omit = RenderOptions {
+            xml_skipped: XmlSkipped::Omit,
+            ..summary
+        };
+        assert!(!render_xml(&trees, &sections, &omit).contains("y.bin\""));
+    }
+
+    #[test]
+    fn header_styles_change_headings_and_separators() {
+        let trees = vec![TreeSection {
+            dir_name: "d".to_string(),
+            tree: "d\n└── a.rs".to_string(),
+        }];
+        let sections = vec![FileSection {
+            path: PathBuf::from("d/a.rs"),
+            relative_path: "a.rs".to_string(),
+            dir_name: "d".to_string(),
+            content: "fn a() {}\n".to_string(),
+            skipped: None,
+            tokens: 0,
+            encoding: None,
+            size: 0,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        }];
+        let render = |header_style| {
+            let options = RenderOptions {
+                header_style,
+                ..Default::default()
+            };
+            render_text(&trees, &sections, &options)
+        };
+
+        let ascii = render(HeaderStyle::Ascii);
+        assert_eq!(
+            ascii,
+            render(HeaderStyle::Default)
+                .replace("＜", "<")
+                .replace("＞", ">")
+        );
+        assert_eq!(
+            render(HeaderStyle::Minimal),
+            "# Directory Structure\n\n## Tree for d\nd\n└── a.rs\n\n# File Contents\n\n## a.rs (in d)\nfn a() {}\n"
+        );
+    }
+
+    #[test]
+    fn token_counts_annotate_headers() {
+        let section = FileSection {
+            path: PathBuf::from("src/main.rs"),
+            relative_path: "main.rs".to_string(),
+            dir_name: "src".to_string(),
+            content: String::new(),
+            skipped: None,
+            tokens: 1243,
+            encoding: None,
+            size: 0,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        };
+        let with_counts = RenderOptions {
+            token_counts: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            file_label(&section, &with_counts),
+            "main.rs (in src): ~1,243 tokens"
+        );
+        let tiny = FileSection {
+            tokens: 1,
+            ..section.clone()
+        };
+        assert_eq!(
+            file_label(&tiny, &with_counts),
+            "main.rs (in src): ~1 token"
+        );
+        assert!(render_text(&[], &[section], &RenderOptions::default())
+            .contains("\nmain.rs (in src):\n"));
+        assert_eq!(format_thousands(0), "0");
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(1234567), "1,234,567");
+    }
+
+    #[test]
+    fn toc_lists_files_before_or_after_the_trees() {
+        let trees = vec![TreeSection {
+            dir_name: "d".to_string(),
+            tree: "d\n├── a.rs\n└── c.bin".to_string(),
+        }];
+        let sections = vec![
+            FileSection {
+                path: PathBuf::from("d/a.rs"),
+                relative_path: "a.rs".to_string(),
+                dir_name: "d".to_string(),
+                content: "fn a() {}\n".to_string(),
+                skipped: None,
+                tokens: 0,
+                encoding: None,
+                size: 0,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+            FileSection {
+                path: PathBuf::from("d/c.bin"),
+                relative_path: "c.bin".to_string(),
+                dir_name: "d".to_string(),
+                content: SkipReason::Binary.placeholder().to_string(),
+                skipped: Some(SkipReason::Binary),
+                tokens: 0,
+                encoding: None,
+                size: 0,
+                streamed: None,
+                last_commit: None,
+                interpreter: None,
+                sha256: None,
+                pinned: false,
+            },
+        ];
+        let options = |toc| RenderOptions {
+            toc: Some(toc),
+            ..Default::default()
+        };
+        let toc = "Files (2):\na.rs (in d): 10 bytes, 1 line\nc.bin (in d): [Binary file skipped]";
+        let tree = "=== Tree for d ===\nd\n├── a.rs\n└── c.bin";
+
+        let before = render_text(&trees, &sections, &options(TocPlacement::Before));
+        assert!(before.starts_with(&format!(
+            "＜Directory Structure＞\n\n{}\n\n{}\n\n",
+            toc, tree
+        )));
+        let after = render_text(&trees, &sections, &options(TocPlacement::After));
+        assert!(after.starts_with(&format!(
+            "＜Directory Structure＞\n\n{}\n\n{}\n\n＜File Contents＞",
+            tree, toc
+        )));
+        let plain = render_text(&trees, &sections, &RenderOptions::default());
+        assert_eq!(after.replace(&format!("\n\n{}", toc), ""), plain);
+
+        let markdown = render_markdown(&trees, &sections, &options(TocPlacement::Before));
+        assert!(markdown.starts_with(
+            "# Directory Structure\n\n## Files (2)\n\n- a.rs (in d): 10 bytes, 1 line\n"
+        ));
+    }
+}
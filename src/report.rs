@@ -0,0 +1,183 @@
+use crate::progress_line::ProgressLine;
+use crate::{output_error, Args, CliError};
+use oreuit::{
+    estimate_tokens, format_thousands, OutputFormat, RenderOptions, ReportOutput, Summarizer,
+    Summary, WrittenReport,
+};
+use std::fs;
+use std::io::{self, IsTerminal};
+
+/// Reads the selected files and writes the report to `output` or the clipboard.
+pub(crate) fn write_report(
+    args: &Args,
+    summarizer: &Summarizer,
+    output: &ReportOutput,
+) -> Result<(), CliError> {
+    let progress = ProgressLine::new(
+        !args.quiet && io::stderr().is_terminal(),
+        args.verbose,
+        args.report.is_some(),
+    );
+    // Split parts are sized from the whole report, the token budget notice and the hash
+    // manifest need the summary, and `--no-tree` drops the tree line that is written
+    // first; otherwise JSON Lines are written while the files are read.
+    if args.format == OutputFormat::Jsonl
+        && !output.is_split()
+        && args.max_tokens.is_none()
+        && !args.tree_only
+        && !args.no_tree
+        && args.hash_manifest.is_none()
+    {
+        let written = summarizer.write_jsonl_to(output, |e| progress.report(e));
+        progress.finish();
+        if let Some(path) = &args.report {
+            progress.write_skip_report(path)?;
+        }
+        let (written, files) = written.map_err(CliError::Output)?;
+        print_written(output, &written);
+        if files == 0 {
+            return Err(CliError::NoFiles(NO_FILES_MATCHED));
+        }
+        return Ok(());
+    }
+    let summary = if args.tree_only {
+        Ok(summarizer.run_tree_only(|event| progress.report(event)))
+    } else {
+        summarizer.run_with_progress(|event| progress.report(event))
+    };
+    progress.finish();
+    if let Some(path) = &args.report {
+        progress.write_skip_report(path)?;
+    }
+    let summary = summary?;
+    if summary.trimmed.dropped_files > 0 {
+        if let Some(max_tokens) = args.max_tokens {
+            eprintln!(
+                "Token budget: omitted {} file(s) (~{} tokens) to fit --max-tokens {}",
+                summary.trimmed.dropped_files,
+                format_thousands(summary.trimmed.dropped_tokens),
+                format_thousands(max_tokens)
+            );
+        }
+    }
+
+    let render_options = RenderOptions {
+        token_counts: !args.no_token_counts,
+        xml_skipped: args.xml_skipped,
+        xml_layout: args.xml_layout,
+        stats: args.stats,
+        header_style: args.header_style,
+        show_encoding: args.show_encoding,
+        toc: args.toc,
+        lang_map: args
+            .lang_map
+            .iter()
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(ext, lang)| (ext.trim().to_string(), lang.trim().to_string()))
+            .collect(),
+        show_language: args.show_language,
+        tree_only: args.tree_only,
+        no_tree: args.no_tree,
+    };
+    // Files read in chunks are read again while the report is written, so they are never
+    // held whole; the clipboard needs the whole text.
+    if !args.bare && !args.clipboard && !args.tee {
+        let written = summary
+            .write_to(args.format, &render_options, output)
+            .map_err(CliError::Output)?;
+        print_written(output, &written);
+        return finish_report(args, &summary);
+    }
+    let output_text = if args.bare {
+        let trees: Vec<&str> = summary.trees.iter().map(|t| t.tree.as_str()).collect();
+        format!("{}\n", trees.join("\n\n"))
+    } else {
+        summary.render(args.format, &render_options)
+    };
+
+    if args.clipboard {
+        eprintln!(
+            "Estimated tokens: {}",
+            format_thousands(estimate_tokens(&output_text))
+        );
+        copy_to_clipboard(output_text).map_err(|e| {
+            CliError::Output(format!("{}. Try writing to a file instead.", e).into())
+        })?;
+        println!("Output content has been copied to the clipboard.");
+        return finish_report(args, &summary);
+    }
+    let written = output
+        .write_text(args.format, &output_text)
+        .map_err(CliError::Output)?;
+    print_written(output, &written);
+    // The file is kept whatever happens to the copy.
+    let copied = match written.paths.first() {
+        Some(path) if args.tee => match copy_to_clipboard(output_text) {
+            Ok(()) => {
+                println!("Output content has been copied to the clipboard.");
+                Ok(())
+            }
+            Err(e) => Err(CliError::Output(
+                format!("{}. The report was still written to {}.", e, path).into(),
+            )),
+        },
+        _ => Ok(()),
+    };
+    let finished = finish_report(args, &summary);
+    copied.and(finished)
+}
+
+/// Prints the estimated token count to stderr and the written files, if any, to stdout.
+fn print_written(output: &ReportOutput, written: &WrittenReport) {
+    eprintln!("Estimated tokens: {}", format_thousands(written.tokens));
+    if output.is_split() {
+        println!(
+            "Output completed: {} ({} parts)",
+            written.paths.join(", "),
+            written.paths.len()
+        );
+    } else if let Some(path) = written.paths.first() {
+        println!("Output completed: {}", path);
+    }
+}
+
+/// Puts `text` on the clipboard, or says why it could not.
+fn copy_to_clipboard(text: String) -> Result<(), String> {
+    #[cfg(feature = "clipboard")]
+    {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| format!("Failed to access the clipboard: {}", e))
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        let _ = text;
+        Err("Clipboard feature is not enabled; compile with '--features clipboard'".to_string())
+    }
+}
+
+/// Runs after the report is written: prints the `--stats` totals on one line to stderr, so
+/// they are shown with `--clipboard` and `-o -` as well, and writes `--hash-manifest`.
+///
+/// A report without files was still written, so an empty one can be inspected, but the
+/// exit status tells scripts apart.
+fn finish_report(args: &Args, summary: &Summary) -> Result<(), CliError> {
+    if !args.no_stats && !args.tree_only {
+        eprintln!("Stats: {}", summary.stats().one_line());
+    }
+    if let Some(path) = &args.hash_manifest {
+        let manifest: String = summary
+            .files
+            .iter()
+            .filter_map(|f| Some(format!("{}  {}\n", f.sha256.as_ref()?, f.path.display())))
+            .collect();
+        fs::write(path, manifest).map_err(|e| output_error(path, e))?;
+        println!("Hash manifest completed: {}", path);
+    }
+    if summary.files.is_empty() && !args.tree_only {
+        return Err(CliError::NoFiles(NO_FILES_MATCHED));
+    }
+    Ok(())
+}
+
+const NO_FILES_MATCHED: &str = "No files matched; the report lists no file contents.";
@@ -0,0 +1,221 @@
+use crate::content::{FileSection, SkipReason};
+use crate::filter::normalize_extension;
+use crate::tokens::format_thousands;
+use std::collections::BTreeMap;
+
+/// Files and bytes included for one extension.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExtensionStats {
+    pub files: usize,
+    pub bytes: usize,
+}
+
+/// Totals over the contents section of a report, shown by `--stats` and on stderr.
+///
+/// Only files whose content was included count towards `files`, `bytes`, `lines`,
+/// `tokens`, and `by_extension`; the sizes are those of the emitted text, after line
+/// numbering and truncation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SummaryStats {
+    /// Scanned directories, one per tree; files given directly count as well.
+    pub directories: usize,
+    pub files: usize,
+    pub bytes: usize,
+    pub lines: usize,
+    /// Estimated tokens of the included contents, without headers.
+    pub tokens: usize,
+    /// Keyed by normalized extension (`.rs`), or `(none)` for extensionless files.
+    pub by_extension: BTreeMap<String, ExtensionStats>,
+    pub too_large: usize,
+    pub binary: usize,
+    pub decode_failed: usize,
+    pub token_budget: usize,
+    pub duplicates: usize,
+    /// Size on disk of the files replaced by `[Duplicate of …]`, i.e. what `dedupe` saved.
+    pub duplicate_bytes: usize,
+    pub broken_symlinks: usize,
+}
+
+impl SummaryStats {
+    pub(crate) fn from_sections(directories: usize, sections: &[FileSection]) -> Self {
+        let mut stats = SummaryStats {
+            directories,
+            ..SummaryStats::default()
+        };
+        for section in sections {
+            match section.skipped {
+                Some(SkipReason::TooLarge) => stats.too_large += 1,
+                Some(SkipReason::Binary) => stats.binary += 1,
+                Some(SkipReason::DecodeFailed) => stats.decode_failed += 1,
+                Some(SkipReason::TokenBudget) => stats.token_budget += 1,
+                Some(SkipReason::Duplicate) => {
+                    stats.duplicates += 1;
+                    stats.duplicate_bytes += section.size as usize;
+                }
+                Some(SkipReason::BrokenSymlink) => stats.broken_symlinks += 1,
+                None => {
+                    let bytes = section.text_len();
+                    stats.files += 1;
+                    stats.bytes += bytes;
+                    stats.lines += section.line_count();
+                    stats.tokens += section.tokens;
+                    let extension = section
+                        .path
+                        .extension()
+                        .and_then(|ext| normalize_extension(&ext.to_string_lossy()))
+                        .unwrap_or_else(|| "(none)".to_string());
+                    let entry = stats.by_extension.entry(extension).or_default();
+                    entry.files += 1;
+                    entry.bytes += bytes;
+                }
+            }
+        }
+        stats
+    }
+
+    /// The footer lines, as `(indent level, text)` so each format can lay them out.
+    pub(crate) fn lines(&self) -> Vec<(usize, String)> {
+        let mut lines = vec![
+            (
+                0,
+                format!("Directories: {}", format_thousands(self.directories)),
+            ),
+            (0, format!("Files: {}", format_thousands(self.files))),
+            (0, format!("Bytes: {}", format_thousands(self.bytes))),
+            (0, format!("Lines: {}", format_thousands(self.lines))),
+            (0, format!("Tokens: ~{}", format_thousands(self.tokens))),
+        ];
+        if !self.by_extension.is_empty() {
+            lines.push((0, "By extension:".to_string()));
+            for (extension, ext) in &self.by_extension {
+                lines.push((
+                    1,
+                    format!(
+                        "{}: {} file(s), {} bytes",
+                        extension,
+                        format_thousands(ext.files),
+                        format_thousands(ext.bytes)
+                    ),
+                ));
+            }
+        }
+        lines.push((0, format!("Skipped: {}", self.skipped())));
+        lines
+    }
+
+    /// The totals on one line, printed to stderr after the report is written, e.g.
+    /// `2 dir(s), 14 file(s), 48,210 bytes, 1,204 lines, ~12,053 tokens; skipped 1 too
+    /// large, 0 binary, 0 undecodable`.
+    pub fn one_line(&self) -> String {
+        format!(
+            "{} dir(s), {} file(s), {} bytes, {} lines, ~{} tokens; skipped {}",
+            format_thousands(self.directories),
+            format_thousands(self.files),
+            format_thousands(self.bytes),
+            format_thousands(self.lines),
+            format_thousands(self.tokens),
+            self.skipped()
+        )
+    }
+
+    /// Placeholder counts by reason; the rarer reasons only when there are any.
+    fn skipped(&self) -> String {
+        let mut skipped = format!(
+            "{} too large, {} binary, {} undecodable",
+            format_thousands(self.too_large),
+            format_thousands(self.binary),
+            format_thousands(self.decode_failed)
+        );
+        if self.token_budget > 0 {
+            skipped.push_str(&format!(
+                ", {} over token budget",
+                format_thousands(self.token_budget)
+            ));
+        }
+        if self.duplicates > 0 {
+            skipped.push_str(&format!(
+                ", {} duplicate(s) ({} bytes saved)",
+                format_thousands(self.duplicates),
+                format_thousands(self.duplicate_bytes)
+            ));
+        }
+        if self.broken_symlinks > 0 {
+            skipped.push_str(&format!(
+                ", {} broken symlink(s)",
+                format_thousands(self.broken_symlinks)
+            ));
+        }
+        skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn section(path: &str, content: &str, skipped: Option<SkipReason>) -> FileSection {
+        FileSection {
+            path: PathBuf::from(path),
+            relative_path: path.to_string(),
+            dir_name: ".".to_string(),
+            content: content.to_string(),
+            skipped,
+            tokens: 0,
+            encoding: None,
+            size: content.len() as u64,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        }
+    }
+
+    #[test]
+    fn stats_count_included_files_and_skips() {
+        let sections = vec![
+            section("src/main.rs", "fn main() {}\n", None),
+            section("src/lib.RS", "a\nb", None),
+            section("Makefile", "all:\n", None),
+            section(
+                "big.rs",
+                SkipReason::TooLarge.placeholder(),
+                Some(SkipReason::TooLarge),
+            ),
+            section(
+                "x.bin",
+                SkipReason::Binary.placeholder(),
+                Some(SkipReason::Binary),
+            ),
+            FileSection {
+                size: 1200,
+                ..section(
+                    "vendor/main.rs",
+                    "[Duplicate of src/main.rs (in .)]\n",
+                    Some(SkipReason::Duplicate),
+                )
+            },
+        ];
+
+        let stats = SummaryStats::from_sections(1, &sections);
+
+        assert_eq!((stats.files, stats.bytes, stats.lines), (3, 21, 4));
+        assert_eq!(
+            stats.by_extension.get(".rs"),
+            Some(&ExtensionStats {
+                files: 2,
+                bytes: 16
+            })
+        );
+        assert_eq!(stats.by_extension["(none)"].files, 1);
+        assert_eq!(
+            stats.lines().last().unwrap().1,
+            "Skipped: 1 too large, 1 binary, 0 undecodable, 1 duplicate(s) (1,200 bytes saved)"
+        );
+        assert_eq!(
+            stats.one_line(),
+            "1 dir(s), 3 file(s), 21 bytes, 4 lines, ~0 tokens; skipped 1 too large, 1 binary, 0 undecodable, 1 duplicate(s) (1,200 bytes saved)"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::content::{FileSection, SkipReason};
+use crate::tree::TreeSection;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Estimates the number of LLM tokens in `text` with a whitespace-plus-punctuation heuristic.
+///
+/// ASCII words count as one token per started group of four characters, every other
+/// non-whitespace character (punctuation, CJK, symbols) counts as one token. Runs of the
+/// same punctuation character, such as `-----` separators, count as one token per started
+/// group of eight, since tokenizers merge them.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut counter = TokenCounter::default();
+    counter.feed(text);
+    counter.total()
+}
+
+/// `estimate_tokens` over a text given a chunk at a time. Words and punctuation runs may
+/// span chunks, so the total is the same as for the whole text.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TokenCounter {
+    tokens: usize,
+    word_len: usize,
+    previous: Option<char>,
+    run_len: usize,
+}
+
+impl TokenCounter {
+    pub(crate) fn feed(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                self.word_len += 1;
+                self.previous = None;
+                continue;
+            }
+            self.tokens += (self.word_len + 3) / 4;
+            self.word_len = 0;
+            if c.is_whitespace() {
+                self.previous = None;
+                continue;
+            }
+            if c.is_ascii_punctuation() && self.previous == Some(c) {
+                self.run_len += 1;
+                if self.run_len % 8 == 0 {
+                    self.tokens += 1;
+                }
+            } else {
+                self.tokens += 1;
+                self.run_len = 0;
+            }
+            self.previous = Some(c);
+        }
+    }
+
+    pub(crate) fn total(&self) -> usize {
+        self.tokens + (self.word_len + 3) / 4
+    }
+}
+
+/// Estimates the tokens of a file from its size alone, at four bytes per token, for
+/// previews such as `--dry-run` that do not read contents. Typical source code comes out
+/// close to `estimate_tokens`; dense punctuation or CJK text is undercounted.
+pub fn estimate_tokens_from_size(bytes: u64) -> usize {
+    ((bytes + 3) / 4) as usize
+}
+
+/// Which files `--max-tokens` drops first when the report does not fit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrimStrategy {
+    /// Keep the longest prefix of the sorted file list that fits
+    #[default]
+    Tail,
+    /// Drop the files with the most tokens first
+    Largest,
+}
+
+/// Files and estimated tokens removed by `apply_token_budget`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrimSummary {
+    pub dropped_files: usize,
+    pub dropped_tokens: usize,
+}
+
+/// Replaces file contents with an `[omitted: token budget exceeded]` line until the running
+/// estimate fits in `max_tokens`.
+///
+/// The trees count toward the budget but are never dropped, since the structure is the
+/// most valuable part of the report. Dropped files keep their position in the sorted output.
+pub(crate) fn apply_token_budget(
+    trees: &[TreeSection],
+    sections: &mut [FileSection],
+    max_tokens: usize,
+    strategy: TrimStrategy,
+) -> TrimSummary {
+    let tree_cost: usize = trees
+        .iter()
+        .map(|t| estimate_tokens(&t.dir_name) + estimate_tokens(&t.tree))
+        .sum();
+    let costs: Vec<usize> = sections
+        .iter()
+        .map(|s| estimate_tokens(&s.relative_path) + s.tokens)
+        .collect();
+
+    let mut dropped = vec![false; sections.len()];
+    match strategy {
+        TrimStrategy::Tail => {
+            let mut used = tree_cost;
+            let mut exhausted = false;
+            for (i, cost) in costs.iter().enumerate() {
+                if !exhausted && used + cost <= max_tokens {
+                    used += cost;
+                } else {
+                    exhausted = true;
+                    dropped[i] = true;
+                }
+            }
+        }
+        TrimStrategy::Largest => {
+            let mut used = tree_cost + costs.iter().sum::<usize>();
+            let mut order: Vec<usize> = (0..sections.len()).collect();
+            // Largest first; among equals, drop the later file first.
+            order.sort_by(|&a, &b| costs[b].cmp(&costs[a]).then(b.cmp(&a)));
+            for i in order {
+                if used <= max_tokens {
+                    break;
+                }
+                used -= costs[i];
+                dropped[i] = true;
+            }
+        }
+    }
+
+    let mut summary = TrimSummary::default();
+    for (section, _) in sections.iter_mut().zip(dropped).filter(|(_, d)| *d) {
+        summary.dropped_files += 1;
+        summary.dropped_tokens += section.tokens;
+        section.content = SkipReason::TokenBudget.placeholder().to_string();
+        section.skipped = Some(SkipReason::TokenBudget);
+        section.streamed = None;
+        section.tokens = estimate_tokens(&section.content);
+    }
+    summary
+}
+
+/// Formats a count with `,` thousands separators, e.g. `1,243`.
+pub fn format_thousands(value: usize) -> String {
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// `512 B`, `14.2 KB`, `3.0 MB`, ... with 1024-byte units and one decimal.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{render_text, RenderOptions};
+    use std::path::PathBuf;
+
+    #[test]
+    fn token_estimate_counts_words_and_punctuation() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("fn main() {}"), 6);
+        assert_eq!(estimate_tokens("identifier_name"), 4);
+        assert_eq!(estimate_tokens("日本語"), 3);
+        assert_eq!(estimate_tokens(&"-".repeat(80)), 10);
+        assert_eq!(estimate_tokens_from_size(0), 0);
+        assert_eq!(estimate_tokens_from_size(9), 3);
+    }
+
+    #[test]
+    fn token_budget_drops_files_but_keeps_tree() {
+        let trees = vec![TreeSection {
+            dir_name: "d".to_string(),
+            tree: "d\n├── a.txt\n└── b.txt".to_string(),
+        }];
+        let section = |name: &str, content: &str| FileSection {
+            path: PathBuf::from(name),
+            relative_path: name.to_string(),
+            dir_name: "d".to_string(),
+            content: content.to_string(),
+            skipped: None,
+            tokens: estimate_tokens(content),
+            encoding: None,
+            size: content.len() as u64,
+            streamed: None,
+            last_commit: None,
+            interpreter: None,
+            sha256: None,
+            pinned: false,
+        };
+        let mut sections = vec![
+            section("a", "one two three\n"),
+            section("b", &"word ".repeat(100)),
+            section("c", "x\n"),
+        ];
+
+        let summary = apply_token_budget(&trees, &mut sections, 30, TrimStrategy::Tail);
+
+        assert_eq!(summary.dropped_files, 2);
+        assert_eq!(sections[0].content, "one two three\n");
+        assert_eq!(sections[1].skipped, Some(SkipReason::TokenBudget));
+        assert_eq!(sections[2].content, "[omitted: token budget exceeded]\n");
+        assert!(render_text(&trees, &sections, &RenderOptions::default()).contains("└── b.txt"));
+
+        let mut sections = vec![
+            section("a", "one two three\n"),
+            section("b", &"word ".repeat(100)),
+            section("c", "x\n"),
+        ];
+        let summary = apply_token_budget(&trees, &mut sections, 30, TrimStrategy::Largest);
+
+        assert_eq!(
+            summary,
+            TrimSummary {
+                dropped_files: 1,
+                dropped_tokens: 100,
+            }
+        );
+        assert_eq!(sections[0].skipped, None);
+        assert_eq!(sections[1].skipped, Some(SkipReason::TokenBudget));
+        assert_eq!(sections[2].skipped, None);
+    }
+}
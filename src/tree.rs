@@ -0,0 +1,868 @@
+use crate::filter::{
+    dir_exclusion, file_exclusion, is_broken_symlink, is_dir_excluded, is_file_selected,
+    sort_files, ExclusionReason, FilterRules,
+};
+use crate::tokens::{format_size, format_thousands};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Tree text for one scanned directory.
+#[derive(Clone, Debug)]
+pub struct TreeSection {
+    /// Name shown in the `Tree for` heading.
+    pub dir_name: String,
+    pub tree: String,
+}
+
+/// Extra information shown after each file name in the tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeDetail {
+    /// File size, e.g. `14.2 KB`
+    Size,
+    /// Last modification date in local time, e.g. `2024-05-02`
+    Mtime,
+    /// Number of lines, e.g. `142 lines`; left out for files with NUL bytes
+    Lines,
+}
+
+/// Formats the selected details of `path` as ` (14.2 KB, 2024-05-02)`.
+///
+/// Returns an empty string when no details are selected or the file cannot be stat'ed, so
+/// the entry degrades to its bare name; an unavailable mtime alone is left out.
+fn detail_suffix(path: &Path, details: &[TreeDetail]) -> String {
+    if details.is_empty() {
+        return String::new();
+    }
+    let Ok(metadata) = fs::metadata(path) else {
+        return String::new();
+    };
+    let parts: Vec<String> = details
+        .iter()
+        .filter_map(|detail| match detail {
+            TreeDetail::Size => Some(format_size(metadata.len())),
+            TreeDetail::Mtime => metadata.modified().ok().and_then(local_date),
+            TreeDetail::Lines => count_lines(path).map(|lines| {
+                format!(
+                    "{} line{}",
+                    format_thousands(lines),
+                    if lines == 1 { "" } else { "s" }
+                )
+            }),
+        })
+        .collect();
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", parts.join(", "))
+    }
+}
+
+/// Lines in the file at `path`, counting a last line without a newline; `None` when it
+/// cannot be read or contains a NUL byte, as binary files do.
+fn count_lines(path: &Path) -> Option<usize> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+    Some(newlines + usize::from(bytes.last().is_some_and(|&b| b != b'\n')))
+}
+
+/// ISO `YYYY-MM-DD` date of `time` in the local time zone, or `None` before 1970.
+#[cfg(unix)]
+fn local_date(time: SystemTime) -> Option<String> {
+    let secs = libc::time_t::try_from(time.duration_since(UNIX_EPOCH).ok()?.as_secs()).ok()?;
+    // SAFETY: `localtime_r` only writes to `tm`, which is a plain C struct.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return None;
+    }
+    Some(format!(
+        "{:04}-{:02}-{:02}",
+        i64::from(tm.tm_year) + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday
+    ))
+}
+
+/// Without a way to look up the local time zone, the date is given in UTC.
+#[cfg(not(unix))]
+fn local_date(time: SystemTime) -> Option<String> {
+    let days = (time.duration_since(UNIX_EPOCH).ok()?.as_secs() / 86_400) as i64;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's `civil_from_days`).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// The strings that draw a tree's branches, chosen once and passed down while rendering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct TreeConnectors {
+    /// Before an entry with more entries after it in its directory.
+    branch: &'static str,
+    /// Before the last entry of a directory.
+    last: &'static str,
+    /// Below an entry that is not the last, where its children are drawn.
+    pipe: &'static str,
+    /// Marks a directory cut off by `--max-depth`.
+    ellipsis: &'static str,
+}
+
+impl TreeConnectors {
+    /// Box-drawing characters, e.g. `├── `.
+    pub(crate) const UNICODE: TreeConnectors = TreeConnectors {
+        branch: "├── ",
+        last: "└── ",
+        pipe: "│   ",
+        ellipsis: "…",
+    };
+
+    /// ASCII only, as `tree --charset=ascii` draws it, e.g. `|-- `.
+    pub(crate) const ASCII: TreeConnectors = TreeConnectors {
+        branch: "|-- ",
+        last: "`-- ",
+        pipe: "|   ",
+        ellipsis: "...",
+    };
+
+    fn connector(self, is_last: bool) -> &'static str {
+        if is_last {
+            self.last
+        } else {
+            self.branch
+        }
+    }
+
+    /// The prefix for the children of an entry drawn after `prefix`.
+    fn child_prefix(self, prefix: &str, is_last: bool) -> String {
+        if is_last {
+            format!("{}    ", prefix)
+        } else {
+            format!("{}{}", prefix, self.pipe)
+        }
+    }
+}
+
+impl Default for TreeConnectors {
+    fn default() -> Self {
+        TreeConnectors::UNICODE
+    }
+}
+
+/// Files and bytes selected below a directory, for `--tree-dir-totals`.
+#[derive(Clone, Copy, Default)]
+struct DirTotals {
+    files: usize,
+    bytes: u64,
+}
+
+impl DirTotals {
+    fn add(&mut self, other: DirTotals) {
+        self.files += other.files;
+        self.bytes += other.bytes;
+    }
+
+    /// ` (3.4 MB, 212 files)`, or ` (0 files)` when everything below was filtered out.
+    fn suffix(self) -> String {
+        match self.files {
+            0 => " (0 files)".to_string(),
+            1 => format!(" ({}, 1 file)", format_size(self.bytes)),
+            n => format!(
+                " ({}, {} files)",
+                format_size(self.bytes),
+                format_thousands(n)
+            ),
+        }
+    }
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// The tree of `directory`, with its own name on the first line.
+#[cfg(test)]
+pub(crate) fn build_tree(directory: &Path, filters: &FilterRules) -> String {
+    let base_name = match directory.file_name().and_then(|s| s.to_str()) {
+        Some(s) => s.to_string(),
+        None => directory.to_string_lossy().into_owned(),
+    };
+    scan_directory(directory, filters, &mut |_, _, _| {}).tree(&base_name, filters)
+}
+
+/// One walk of a scanned directory, from which both its tree and its files are taken.
+pub(crate) struct DirScan {
+    entries: Vec<ScanEntry>,
+    /// The selected files, in `--sort` order.
+    pub(crate) files: Vec<PathBuf>,
+}
+
+struct ScanEntry {
+    path: PathBuf,
+    kind: EntryKind,
+}
+
+enum EntryKind {
+    File,
+    Dir(Vec<ScanEntry>),
+    /// A symlinked directory that is listed but not expanded.
+    Link,
+    /// A directory at `--max-depth`, with the totals of the files below it.
+    Truncated(DirTotals),
+}
+
+/// Walks `directory` once, keeping what the tree lists and the files that are selected,
+/// and passing each file and each pruned directory that is left out to `on_excluded`
+/// together with the reason and whether it is a directory.
+///
+/// Directories at `--max-depth` are listed but not expanded; the files below them are
+/// only counted. Files within ignored directories are not searched, so they are not
+/// reported. When the scanned directory itself is in `--ignore-dirs`, its tree is still
+/// listed but none of its files are selected.
+///
+/// Symlinked directories are listed but only expanded with `--follow-symlinks`, and never
+/// when they point at a directory being expanded. Broken symlinks are kept as files with
+/// `--follow-symlinks`, so that they show up with a placeholder.
+pub(crate) fn scan_directory(
+    directory: &Path,
+    filters: &FilterRules,
+    on_excluded: &mut dyn FnMut(&Path, ExclusionReason, bool),
+) -> DirScan {
+    let root_ignored = directory
+        .file_name()
+        .unwrap_or(directory.as_os_str())
+        .to_str()
+        .is_some_and(|name| filters.ignore_dirs.contains(name));
+    if root_ignored {
+        on_excluded(directory, ExclusionReason::IgnoredDirectory, true);
+    }
+    let mut scanner = Scanner {
+        root: directory,
+        filters,
+        on_excluded,
+        ancestors: Vec::new(),
+        selecting: !root_ignored,
+        files: Vec::new(),
+    };
+    if filters.follow_symlinks {
+        scanner.ancestors.push(
+            directory
+                .canonicalize()
+                .unwrap_or_else(|_| directory.to_path_buf()),
+        );
+    }
+    let entries = scanner.scan(directory, 0);
+    let mut files = scanner.files;
+    sort_files(&mut files, filters.order);
+    DirScan { entries, files }
+}
+
+struct Scanner<'a> {
+    root: &'a Path,
+    filters: &'a FilterRules,
+    on_excluded: &'a mut dyn FnMut(&Path, ExclusionReason, bool),
+    /// Canonical paths of the directories being expanded, with `--follow-symlinks`.
+    ancestors: Vec<PathBuf>,
+    /// Files are selected and exclusions reported; false below an ignored root.
+    selecting: bool,
+    files: Vec<PathBuf>,
+}
+
+impl Scanner<'_> {
+    /// Lists the entries of `path`, whose children are at `depth` (0 for the root's).
+    fn scan(&mut self, path: &Path, depth: usize) -> Vec<ScanEntry> {
+        let mut entries: Vec<fs::DirEntry> = match fs::read_dir(path) {
+            Ok(iter) => iter.filter_map(|e| e.ok()).collect(),
+            Err(_) => return Vec::new(),
+        };
+        entries.sort_by_key(|e| e.file_name());
+        let follow = self.filters.follow_symlinks;
+        let mut listed = Vec::new();
+        for entry in entries {
+            let entry_path = entry.path();
+            let file_type = entry.file_type().ok();
+            if entry_path.is_dir() {
+                let expand = if !file_type.is_some_and(|t| t.is_symlink()) {
+                    true
+                } else if follow {
+                    // A link back to a directory being expanded would recurse forever.
+                    entry_path
+                        .canonicalize()
+                        .is_ok_and(|target| !self.ancestors.contains(&target))
+                } else {
+                    false
+                };
+                if let Some(reason) = dir_exclusion(self.root, &entry_path, self.filters) {
+                    // Links that are not walked into are left out silently.
+                    if expand && self.selecting {
+                        (self.on_excluded)(&entry_path, reason, true);
+                    }
+                    continue;
+                }
+                let kind = if !expand {
+                    EntryKind::Link
+                } else if self
+                    .filters
+                    .max_depth
+                    .is_some_and(|max_depth| depth >= max_depth)
+                {
+                    EntryKind::Truncated(count_files_below(self.root, &entry_path, self.filters))
+                } else {
+                    if follow {
+                        self.ancestors.push(
+                            entry_path
+                                .canonicalize()
+                                .unwrap_or_else(|_| entry_path.clone()),
+                        );
+                    }
+                    let children = self.scan(&entry_path, depth + 1);
+                    if follow {
+                        self.ancestors.pop();
+                    }
+                    EntryKind::Dir(children)
+                };
+                listed.push(ScanEntry {
+                    path: entry_path,
+                    kind,
+                });
+                continue;
+            }
+            let is_file = entry_path.is_file();
+            let broken = follow && !is_file && is_broken_symlink(&entry_path);
+            if !is_file && !broken {
+                continue;
+            }
+            // Without `--follow-symlinks` a linked file is listed in the tree, but its
+            // contents are not read.
+            let selectable = self.selecting && (follow || file_type.is_some_and(|t| t.is_file()));
+            match file_exclusion(self.root, &entry_path, self.filters) {
+                None => {
+                    if selectable {
+                        self.files.push(entry_path.clone());
+                    }
+                    listed.push(ScanEntry {
+                        path: entry_path,
+                        kind: EntryKind::File,
+                    });
+                }
+                Some(reason) if selectable => (self.on_excluded)(&entry_path, reason, false),
+                Some(_) => {}
+            }
+        }
+        listed
+    }
+}
+
+impl DirScan {
+    /// The tree text, with `root_name` on the first line.
+    pub(crate) fn tree(&self, root_name: &str, filters: &FilterRules) -> String {
+        let mut lines = vec![root_name.to_string()];
+        let totals = render_scan(&self.entries, "", filters, &mut lines);
+        if filters.tree_dir_totals {
+            lines[0].push_str(&totals.suffix());
+        }
+        lines.join("\n")
+    }
+
+    /// Leaves out the selected files whose target is already in `seen`, from the files and
+    /// from the tree alike, and adds the targets of the others. With `--follow-symlinks` a
+    /// file can be reached through several paths; only the first is kept.
+    pub(crate) fn drop_aliases(&mut self, seen: &mut HashSet<PathBuf>) {
+        let mut aliases = HashSet::new();
+        self.files.retain(|file| {
+            let canonical = file.canonicalize().unwrap_or_else(|_| file.clone());
+            seen.insert(canonical) || !aliases.insert(file.clone())
+        });
+        if !aliases.is_empty() {
+            remove_files(&mut self.entries, &aliases);
+        }
+    }
+}
+
+fn remove_files(entries: &mut Vec<ScanEntry>, paths: &HashSet<PathBuf>) {
+    entries.retain_mut(|entry| match &mut entry.kind {
+        EntryKind::File => !paths.contains(&entry.path),
+        EntryKind::Dir(children) => {
+            remove_files(children, paths);
+            true
+        }
+        EntryKind::Link | EntryKind::Truncated(_) => true,
+    });
+}
+
+/// Adds the lines for `entries` below a directory whose children are drawn after `prefix`.
+///
+/// Returns the totals of the files listed below, so `--tree-dir-totals` can annotate each
+/// directory once its children are done.
+fn render_scan(
+    entries: &[ScanEntry],
+    prefix: &str,
+    filters: &FilterRules,
+    lines: &mut Vec<String>,
+) -> DirTotals {
+    let mut totals = DirTotals::default();
+    let connectors = filters.tree_connectors;
+    let count = entries.len();
+    for (i, entry) in entries.iter().enumerate() {
+        let is_last = i == count - 1;
+        let connector = connectors.connector(is_last);
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        match &entry.kind {
+            EntryKind::File => {
+                if filters.tree_dir_totals {
+                    totals.add(DirTotals {
+                        files: 1,
+                        bytes: file_size(&entry.path),
+                    });
+                }
+                let details = detail_suffix(&entry.path, &filters.tree_details);
+                lines.push(format!("{}{}{}{}", prefix, connector, name, details));
+            }
+            EntryKind::Link => lines.push(format!("{}{}{}", prefix, connector, name)),
+            EntryKind::Truncated(below) => {
+                let ellipsis = connectors.ellipsis;
+                let marker = match below.files {
+                    0 => String::new(),
+                    _ if filters.tree_dir_totals => {
+                        format!(" {}{}", ellipsis, below.suffix().replace(')', " below)"))
+                    }
+                    1 => format!(" {} (1 file below)", ellipsis),
+                    n => format!(" {} ({} files below)", ellipsis, format_thousands(n)),
+                };
+                lines.push(format!("{}{}{}{}", prefix, connector, name, marker));
+                totals.add(*below);
+            }
+            EntryKind::Dir(children) => {
+                let line_index = lines.len();
+                lines.push(format!("{}{}{}", prefix, connector, name));
+                let new_prefix = connectors.child_prefix(prefix, is_last);
+                let below = render_scan(children, &new_prefix, filters, lines);
+                if filters.tree_dir_totals {
+                    lines[line_index].push_str(&below.suffix());
+                    totals.add(below);
+                }
+            }
+        }
+    }
+    totals
+}
+
+/// Counts the files under `dir` that would be collected without a depth limit, with their
+/// size when `--tree-dir-totals` needs it.
+fn count_files_below(root: &Path, dir: &Path, filters: &FilterRules) -> DirTotals {
+    WalkDir::new(dir)
+        .min_depth(1)
+        .follow_links(filters.follow_symlinks)
+        .into_iter()
+        .filter_entry(|e| !e.file_type().is_dir() || !is_dir_excluded(root, e.path(), filters))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_file_selected(root, e.path(), filters))
+        .fold(DirTotals::default(), |mut totals, e| {
+            totals.files += 1;
+            if filters.tree_dir_totals {
+                totals.bytes += e.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+            totals
+        })
+}
+
+/// Directory node used to render a tree from an explicit list of paths.
+#[derive(Default)]
+struct PathTreeNode {
+    children: BTreeMap<String, PathTreeNode>,
+    /// Set on the nodes for the listed files, to look up `--tree-details`.
+    file: Option<PathBuf>,
+    /// A file removed since `--changed-since`, listed with a `[deleted]` marker.
+    deleted: bool,
+}
+
+/// Builds tree text from explicit file paths, reconstructing their parent directories.
+///
+/// `files` are relative to `base`, which is only used to stat them for `details` and
+/// `dir_totals`. `deleted` files are listed with a `[deleted]` marker and never stat'ed.
+pub(crate) fn build_tree_from_paths(
+    root_name: &str,
+    base: &Path,
+    files: &[PathBuf],
+    deleted: &[PathBuf],
+    details: &[TreeDetail],
+    dir_totals: bool,
+    connectors: TreeConnectors,
+) -> String {
+    let mut root = PathTreeNode::default();
+    let listed = files.iter().map(|file| (file, false));
+    for (file, is_deleted) in listed.chain(deleted.iter().map(|file| (file, true))) {
+        let mut node = &mut root;
+        for component in file.components() {
+            let name = match component {
+                Component::CurDir => continue,
+                Component::RootDir => "/".to_string(),
+                other => other.as_os_str().to_string_lossy().into_owned(),
+            };
+            node = node.children.entry(name).or_default();
+        }
+        node.file = Some(base.join(file));
+        node.deleted = is_deleted;
+    }
+    let mut lines = vec![root_name.to_string()];
+    let totals = render_path_tree(&root, "", details, dir_totals, connectors, &mut lines);
+    if dir_totals {
+        lines[0].push_str(&totals.suffix());
+    }
+    lines.join("\n")
+}
+
+fn render_path_tree(
+    node: &PathTreeNode,
+    prefix: &str,
+    details: &[TreeDetail],
+    dir_totals: bool,
+    connectors: TreeConnectors,
+    lines: &mut Vec<String>,
+) -> DirTotals {
+    let mut totals = DirTotals::default();
+    let count = node.children.len();
+    for (i, (name, child)) in node.children.iter().enumerate() {
+        let is_last = i == count - 1;
+        let connector = connectors.connector(is_last);
+        let suffix = match &child.file {
+            Some(_) if child.deleted => " [deleted]".to_string(),
+            Some(file) => {
+                if dir_totals {
+                    totals.add(DirTotals {
+                        files: 1,
+                        bytes: file_size(file),
+                    });
+                }
+                detail_suffix(file, details)
+            }
+            None => String::new(),
+        };
+        let line_index = lines.len();
+        lines.push(format!("{}{}{}{}", prefix, connector, name, suffix));
+        let new_prefix = connectors.child_prefix(prefix, is_last);
+        let below = render_path_tree(child, &new_prefix, details, dir_totals, connectors, lines);
+        if dir_totals && child.file.is_none() {
+            lines[line_index].push_str(&below.suffix());
+            totals.add(below);
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{collect_files, ExclusionCounts, FilterRules};
+    use crate::test_support::TestTempDir;
+    use std::collections::HashSet;
+
+    #[test]
+    fn max_depth_limits_tree_and_files() {
+        let temp_dir = TestTempDir::new("max_depth");
+        let top = temp_dir.write_file("top.rs", "");
+        let nested = temp_dir.write_file("a/nested.rs", "");
+        temp_dir.write_file("a/b/deep.rs", "");
+        temp_dir.write_file("a/b/c/deeper.rs", "");
+        temp_dir.write_file("a/skipped/notes.bin", "");
+        fs::create_dir_all(temp_dir.path.join("a/empty")).unwrap();
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            max_depth: Some(1),
+            ..Default::default()
+        };
+
+        let files = collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new());
+        assert_eq!(files, vec![nested, top]);
+
+        let tree = build_tree(&temp_dir.path, &filters);
+        let lines: Vec<&str> = tree.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "├── a",
+                "│   ├── b … (2 files below)",
+                "│   ├── empty",
+                "│   ├── nested.rs",
+                "│   └── skipped",
+                "└── top.rs",
+            ]
+        );
+
+        filters.tree_connectors = TreeConnectors::ASCII;
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(tree.ends_with("\n|-- a\n|   |-- b ... (2 files below)\n|   |-- empty\n|   |-- nested.rs\n|   `-- skipped\n`-- top.rs"));
+    }
+
+    #[test]
+    fn max_depth_cutoff_counts_the_files_below() {
+        let temp_dir = TestTempDir::new("max_depth_cutoff");
+        temp_dir.write_file("docs/notes.bin", "");
+        temp_dir.write_file("lib/one.rs", "");
+        temp_dir.write_file("src/main.rs", "");
+        temp_dir.write_file("src/cli/args.rs", "");
+        temp_dir.write_file("src/cli/deep/parse.rs", "");
+        temp_dir.write_file("src/cli/deep/readme.bin", "");
+        temp_dir.write_file("top.rs", "");
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            max_depth: Some(0),
+            ..Default::default()
+        };
+        let tree = build_tree(&temp_dir.path, &filters);
+        let lines: Vec<&str> = tree.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "├── docs",
+                "├── lib … (1 file below)",
+                "├── src … (3 files below)",
+                "└── top.rs",
+            ]
+        );
+
+        filters.max_depth = Some(1);
+        let tree = build_tree(&temp_dir.path, &filters);
+        let lines: Vec<&str> = tree.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "├── docs",
+                "├── lib",
+                "│   └── one.rs",
+                "├── src",
+                "│   ├── cli … (2 files below)",
+                "│   └── main.rs",
+                "└── top.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn ascii_connectors_mark_last_children_and_nested_indents() {
+        let temp_dir = TestTempDir::new("ascii_tree");
+        temp_dir.write_file("a/x.rs", "");
+        temp_dir.write_file("a/y/z.rs", "");
+        temp_dir.write_file("b/c/d.rs", "");
+        temp_dir.write_file("b/w.rs", "");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            tree_connectors: TreeConnectors::ASCII,
+            ..Default::default()
+        };
+        let tree = build_tree(&temp_dir.path, &filters);
+        let lines: Vec<&str> = tree.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "|-- a",
+                "|   |-- x.rs",
+                "|   `-- y",
+                "|       `-- z.rs",
+                "`-- b",
+                "    |-- c",
+                "    |   `-- d.rs",
+                "    `-- w.rs",
+            ]
+        );
+    }
+
+    #[test]
+    fn tree_from_paths_reconstructs_parents() {
+        let files = vec![
+            PathBuf::from("./src/main.rs"),
+            PathBuf::from("Cargo.toml"),
+            PathBuf::from("src/cli/args.rs"),
+        ];
+
+        let tree = build_tree_from_paths(
+            ".",
+            Path::new("."),
+            &files,
+            &[],
+            &[],
+            false,
+            TreeConnectors::UNICODE,
+        );
+
+        assert_eq!(
+            tree,
+            ".\n├── Cargo.toml\n└── src\n    ├── cli\n    │   └── args.rs\n    └── main.rs"
+        );
+
+        let tree = build_tree_from_paths(
+            ".",
+            Path::new("."),
+            &files,
+            &[],
+            &[],
+            false,
+            TreeConnectors::ASCII,
+        );
+        assert_eq!(
+            tree,
+            ".\n|-- Cargo.toml\n`-- src\n    |-- cli\n    |   `-- args.rs\n    `-- main.rs"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directories_follow_only_when_asked() {
+        let temp_dir = TestTempDir::new("symlinks");
+        let lib = temp_dir.write_file("repo/src/lib.rs", "");
+        temp_dir.write_file("shared/doc.rs", "");
+        std::os::unix::fs::symlink("../shared", temp_dir.path.join("repo/docs")).unwrap();
+        std::os::unix::fs::symlink("..", temp_dir.path.join("repo/src/loop")).unwrap();
+        let repo = temp_dir.path.join("repo");
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            collect_files(&repo, &filters, &mut ExclusionCounts::new()),
+            vec![lib.clone()]
+        );
+        assert_eq!(
+            build_tree(&repo, &filters),
+            "repo\n├── docs\n└── src\n    ├── lib.rs\n    └── loop"
+        );
+
+        filters.follow_symlinks = true;
+        assert_eq!(
+            collect_files(&repo, &filters, &mut ExclusionCounts::new()),
+            vec![repo.join("docs/doc.rs"), lib]
+        );
+        assert_eq!(
+            build_tree(&repo, &filters),
+            "repo\n├── docs\n│   └── doc.rs\n└── src\n    ├── lib.rs\n    └── loop"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlinks_are_kept_when_following() {
+        let temp_dir = TestTempDir::new("broken_symlinks");
+        let lib = temp_dir.write_file("lib.rs", "");
+        let gone = temp_dir.path.join("gone.rs");
+        std::os::unix::fs::symlink("missing.rs", &gone).unwrap();
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new()),
+            vec![lib.clone()]
+        );
+
+        filters.follow_symlinks = true;
+        assert_eq!(
+            collect_files(&temp_dir.path, &filters, &mut ExclusionCounts::new()),
+            vec![gone, lib]
+        );
+        assert!(build_tree(&temp_dir.path, &filters).ends_with("\n├── gone.rs\n└── lib.rs"));
+    }
+
+    #[test]
+    fn tree_details_annotate_files_only() {
+        let temp_dir = TestTempDir::new("tree_details");
+        temp_dir.write_file("src/big.rs", &"x".repeat(14_541));
+        temp_dir.write_file("small.rs", "fn s() {}\n");
+
+        let filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            tree_details: vec![TreeDetail::Size],
+            ..Default::default()
+        };
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(tree.ends_with("\n├── small.rs (10 B)\n└── src\n    └── big.rs (14.2 KB)"));
+
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+        // Noon UTC falls on the same date in the time zones the tests run in.
+        let noon = UNIX_EPOCH + std::time::Duration::from_secs(43_200);
+        let day = std::time::Duration::from_secs(86_400);
+        assert_eq!(local_date(noon).as_deref(), Some("1970-01-01"));
+        assert_eq!(
+            local_date(noon + day * 19_845).as_deref(),
+            Some("2024-05-02")
+        );
+        assert_eq!(
+            detail_suffix(&temp_dir.path.join("missing.rs"), &[TreeDetail::Mtime]),
+            ""
+        );
+
+        let lines = temp_dir.write_file("lines.rs", "a\nb\nc");
+        let one = temp_dir.write_file("one.rs", "a\n");
+        let binary = temp_dir.write_file("data.bin", "a\0\n");
+        let stats = [TreeDetail::Size, TreeDetail::Lines];
+        assert_eq!(detail_suffix(&lines, &stats), " (5 B, 3 lines)");
+        assert_eq!(detail_suffix(&one, &stats), " (2 B, 1 line)");
+        assert_eq!(detail_suffix(&binary, &stats), " (3 B)");
+    }
+
+    #[test]
+    fn tree_dir_totals_sum_selected_files_below_each_directory() {
+        let temp_dir = TestTempDir::new("tree_dir_totals");
+        temp_dir.write_file("src/a.rs", &"a".repeat(1_000));
+        temp_dir.write_file("src/deep/b.rs", &"b".repeat(2_000));
+        temp_dir.write_file("src/deep/skip.txt", "not counted");
+        temp_dir.write_file("docs/readme.txt", "filtered out");
+        temp_dir.write_file("top.rs", "x");
+
+        let mut filters = FilterRules {
+            allowed: HashSet::from([".rs".to_string()]),
+            tree_dir_totals: true,
+            ..Default::default()
+        };
+        let tree = build_tree(&temp_dir.path, &filters);
+        let lines: Vec<&str> = tree.lines().collect();
+        assert!(lines[0].ends_with(" (2.9 KB, 3 files)"));
+        assert_eq!(
+            lines[1..],
+            [
+                "├── docs (0 files)",
+                "├── src (2.9 KB, 2 files)",
+                "│   ├── a.rs",
+                "│   └── deep (2.0 KB, 1 file)",
+                "│       └── b.rs",
+                "└── top.rs",
+            ]
+        );
+
+        filters.max_depth = Some(0);
+        let tree = build_tree(&temp_dir.path, &filters);
+        assert!(tree.contains("\n├── src … (2.9 KB, 2 files below)\n"));
+
+        let files = [PathBuf::from("src/a.rs"), PathBuf::from("top.rs")];
+        let tree = build_tree_from_paths(
+            "repo",
+            &temp_dir.path,
+            &files,
+            &[],
+            &[],
+            true,
+            TreeConnectors::UNICODE,
+        );
+        assert_eq!(
+            tree,
+            "repo (1001 B, 2 files)\n├── src (1000 B, 1 file)\n│   └── a.rs\n└── top.rs"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::report::write_report;
+use crate::{Args, CliError};
+use oreuit::{ReportOutput, Summarizer, SummaryOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often `--watch` checks the selected files.
+const WATCH_POLL: Duration = Duration::from_millis(500);
+/// How long the selected files must stay unchanged after a change before `--watch` writes
+/// the report.
+const WATCH_SETTLE: Duration = Duration::from_millis(300);
+
+/// Set by the first Ctrl-C while watching.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Writes the report, then again each time a selected file is added, removed, or modified,
+/// until Ctrl-C. Errors from a run are printed and watching goes on.
+pub(crate) fn watch(
+    args: &Args,
+    mut options: SummaryOptions,
+    output: &ReportOutput,
+) -> Result<(), CliError> {
+    catch_interrupt();
+    loop {
+        let summarizer = Summarizer::new(options.clone())?;
+        if let Err(e) = write_report(args, &summarizer, output) {
+            eprintln!("Error: {}", e);
+        }
+        // Written into a scanned directory, the report would otherwise start the next run
+        // and be read into it.
+        let outputs = report_files(args, output);
+        let own: Vec<PathBuf> = summarizer
+            .selected_files()
+            .into_iter()
+            .filter(|file| fs::canonicalize(file).is_ok_and(|file| outputs.contains(&file)))
+            .collect();
+        if !own.is_empty() {
+            options = options.deselect(own);
+        }
+        if !args.quiet {
+            eprintln!("Watching for changes; press Ctrl-C to stop.");
+        }
+        // Built again so that changed ignore files are read anew.
+        if !wait_for_change(&Summarizer::new(options.clone())?) {
+            return Ok(());
+        }
+    }
+}
+
+/// Polls the selected files until one is added, removed, or modified and they then stay
+/// unchanged for `WATCH_SETTLE`, so a checkout or a formatter touching many files starts
+/// one run. Returns `false` on Ctrl-C.
+fn wait_for_change(summarizer: &Summarizer) -> bool {
+    let snapshot = || -> Vec<(PathBuf, Option<(SystemTime, u64)>)> {
+        summarizer
+            .selected_files()
+            .into_iter()
+            .map(|file| {
+                let stamp = fs::metadata(&file)
+                    .ok()
+                    .map(|m| (m.modified().unwrap_or(SystemTime::UNIX_EPOCH), m.len()));
+                (file, stamp)
+            })
+            .collect()
+    };
+    let mut last = snapshot();
+    let mut changed = false;
+    loop {
+        thread::sleep(if changed { WATCH_SETTLE } else { WATCH_POLL });
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            return false;
+        }
+        let next = snapshot();
+        if next != last {
+            last = next;
+            changed = true;
+        } else if changed {
+            return true;
+        }
+    }
+}
+
+/// The files a run writes, canonicalized: `--output` or its numbered parts,
+/// `--hash-manifest`, and `--report`.
+fn report_files(args: &Args, output: &ReportOutput) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if output.is_split() {
+        paths.extend(
+            (1..)
+                .map_while(|part| output.part_path(part))
+                .take_while(|path| Path::new(path).exists()),
+        );
+    } else if !args.clipboard {
+        paths.extend(output.path());
+    }
+    paths.extend(args.hash_manifest.clone());
+    paths.extend(args.report.clone());
+    paths
+        .iter()
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .collect()
+}
+
+/// Makes the first Ctrl-C only set `INTERRUPTED`, so `--watch` stops between runs with the
+/// report complete and temporary clones removed; a second Ctrl-C ends the process at once.
+#[cfg(unix)]
+fn catch_interrupt() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        // SAFETY: `signal` is async-signal-safe.
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+    // SAFETY: the handler only stores to an atomic and restores the default action.
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// Elsewhere Ctrl-C ends the process as usual.
+#[cfg(not(unix))]
+fn catch_interrupt() {}
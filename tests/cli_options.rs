@@ -0,0 +1,1012 @@
+use flate2::read::GzDecoder;
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+struct TestTempDir {
+    path: PathBuf,
+}
+
+impl TestTempDir {
+    fn new(name: &str) -> Self {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = env::temp_dir().join(format!(
+            "oreuit_integration_{}_{}_{}",
+            name,
+            process::id(),
+            unique
+        ));
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+}
+
+impl Drop for TestTempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("config_roundtrip_case")
+}
+
+fn oreuit_bin() -> &'static str {
+    env!("CARGO_BIN_EXE_oreuit")
+}
+
+fn run_summary(output_path: &Path, extra_args: &[&str]) -> String {
+    let output = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap()])
+        .args(["-o", output_path.to_str().unwrap()])
+        .args(extra_args)
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "oreuit failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    fs::read_to_string(output_path).unwrap()
+}
+
+#[test]
+fn tree_and_contents_match_the_golden_output() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let temp_dir = TestTempDir::new("tree_and_contents_match_the_golden_output");
+    let cases: [(&str, &[&str]); 2] = [
+        ("scan_case.golden.txt", &[]),
+        (
+            "scan_case_max_depth.golden.txt",
+            &[
+                "--max-depth",
+                "1",
+                "--tree-dir-totals",
+                "--tree-details",
+                "size,lines",
+            ],
+        ),
+    ];
+    for (golden, args) in cases {
+        let output_path = temp_dir.path.join(golden);
+        let output = Command::new(oreuit_bin())
+            .args(["-d", fixtures.join("scan_case").to_str().unwrap()])
+            .args(["-o", output_path.to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            fs::read_to_string(&output_path).unwrap(),
+            fs::read_to_string(fixtures.join(golden)).unwrap(),
+            "{} differs",
+            golden
+        );
+    }
+}
+
+#[test]
+fn parallel_reading_keeps_sequential_order() {
+    let temp_dir = TestTempDir::new("parallel_reading_keeps_sequential_order");
+
+    let sequential = run_summary(&temp_dir.path.join("sequential.txt"), &["--jobs", "1"]);
+    let parallel = run_summary(&temp_dir.path.join("parallel.txt"), &["--jobs", "4"]);
+
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn placeholders_keep_their_position_with_threads() {
+    let temp_dir = TestTempDir::new("placeholders_keep_their_position_with_threads");
+    let source = temp_dir.path.join("source");
+    fs::create_dir_all(&source).unwrap();
+    for i in 0..40 {
+        let name = format!("file{:02}.txt", i);
+        let content: Vec<u8> = match i % 3 {
+            0 => b"\0binary".to_vec(),
+            1 => "x".repeat(64).into_bytes(),
+            _ => format!("text {}\n", i).into_bytes(),
+        };
+        fs::write(source.join(name), content).unwrap();
+    }
+
+    let run = |output: &str, threads: &str| {
+        let output_path = temp_dir.path.join(output);
+        let status = Command::new(oreuit_bin())
+            .args([
+                "-d",
+                source.to_str().unwrap(),
+                "--max-size",
+                "32",
+                "--no-token-counts",
+            ])
+            .args(["--threads", threads, "-o", output_path.to_str().unwrap()])
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+        fs::read_to_string(output_path).unwrap()
+    };
+    let sequential = run("sequential.txt", "1");
+    let parallel = run("parallel.txt", "8");
+
+    assert_eq!(sequential, parallel);
+    assert!(sequential.contains(
+        "file03.txt (in source):\n--------------------------------------------------------------------------------\n[Binary file skipped]\n"
+    ));
+    assert!(sequential.contains("file04.txt (in source):\n--------------------------------------------------------------------------------\n[File size exceeds limit; skipped]\n"));
+}
+
+#[test]
+fn files_from_stdin_skips_directory_walk() {
+    let temp_dir = TestTempDir::new("files_from_stdin_skips_directory_walk");
+    let output_path = temp_dir.path.join("summary.txt");
+
+    let mut child = Command::new(oreuit_bin())
+        .current_dir(fixture_dir())
+        .args(["--files-from", "-", "-o", output_path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"src/main.rs\nimage.png\n\nmissing.rs\n")
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    let summary = fs::read_to_string(&output_path).unwrap();
+    assert!(summary.contains(".\n├── image.png\n└── src\n    └── main.rs\n"));
+    assert!(summary.contains("src/main.rs (in .):"));
+    assert!(summary.contains("image.png (in .):"));
+    assert!(!summary.contains("lib.rs"));
+}
+
+#[test]
+fn files_from_null_separated_keeps_paths_verbatim() {
+    let temp_dir = TestTempDir::new("files_from_null_separated_keeps_paths_verbatim");
+    fs::write(temp_dir.path.join(" spaced name.rs"), "fn spaced() {}\n").unwrap();
+    fs::write(temp_dir.path.join("line\nbreak.rs"), "fn line_break() {}\n").unwrap();
+    fs::write(
+        temp_dir.path.join("list.txt"),
+        b" spaced name.rs\0line\nbreak.rs\0",
+    )
+    .unwrap();
+
+    let output = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["--files-from", "list.txt", "-0", "-o", "summary.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("Warning"));
+
+    let summary = fs::read_to_string(temp_dir.path.join("summary.txt")).unwrap();
+    assert!(summary.contains("fn spaced() {}"));
+    assert!(summary.contains("fn line_break() {}"));
+}
+
+#[test]
+fn file_entries_in_directory_bypass_filters() {
+    let temp_dir = TestTempDir::new("file_entries_in_directory_bypass_filters");
+    fs::create_dir_all(temp_dir.path.join("src")).unwrap();
+    fs::write(temp_dir.path.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+    fs::write(temp_dir.path.join("src/notes.log"), "walked, not listed\n").unwrap();
+    fs::write(temp_dir.path.join("build.log"), "explicitly requested\n").unwrap();
+
+    let output = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["-d", "src,build.log", "--ignore-files", "build.log"])
+        .args(["--stdout", "--no-token-counts"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let summary = String::from_utf8(output.stdout).unwrap();
+    assert!(summary.contains("=== Tree for src ===\nsrc\n└── lib.rs\n"));
+    assert!(summary.contains("=== Tree for . ===\n.\n└── build.log\n"));
+    assert!(summary.contains("build.log (in .):\n"));
+    assert!(summary.contains("explicitly requested"));
+    assert!(!summary.contains("walked, not listed"));
+}
+
+#[test]
+fn dash_output_writes_report_to_stdout() {
+    let temp_dir = TestTempDir::new("dash_output_writes_report_to_stdout");
+    let written = run_summary(&temp_dir.path.join("summary.txt"), &[]);
+
+    for output_args in [&["-o", "-"][..], &["--stdout"][..]] {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", fixture_dir().to_str().unwrap()])
+            .args(output_args)
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, written);
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("Output completed"));
+    }
+}
+
+#[test]
+fn stdout_output_rejects_clipboard() {
+    let output = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap(), "-o", "-", "-c"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn jsonl_streams_the_same_lines_as_a_buffered_run() {
+    let run = |extra_args: &[&str]| {
+        Command::new(oreuit_bin())
+            .args([
+                "-d",
+                fixture_dir().to_str().unwrap(),
+                "--format",
+                "jsonl",
+                "-o",
+                "-",
+            ])
+            .args(extra_args)
+            .output()
+            .unwrap()
+    };
+    let streamed = run(&[]);
+    // A token budget makes oreuit read every file before writing.
+    let buffered = run(&["--max-tokens", "100000000"]);
+
+    assert!(streamed.status.success());
+    assert_eq!(streamed.stdout, buffered.stdout);
+    let stdout = String::from_utf8(streamed.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("{\"trees\":[{\"directory\":"));
+    assert!(lines.all(|line| line.starts_with("{\"path\":") && line.ends_with('}')));
+
+    assert!(!run(&["-c"]).status.success());
+}
+
+#[test]
+fn glob_options_are_repeatable() {
+    let temp_dir = TestTempDir::new("glob_options_are_repeatable");
+
+    let summary = run_summary(
+        &temp_dir.path.join("summary.txt"),
+        &[
+            "--include-glob",
+            "src/*.rs",
+            "--include-glob",
+            "msg/*.msg,notes.md",
+            "--exclude-glob",
+            "lib.rs",
+            "--exclude-glob",
+            "notes.md",
+        ],
+    );
+
+    assert!(summary.contains("src/main.rs (in config_roundtrip_case)"));
+    assert!(summary.contains("msg/sample.msg (in config_roundtrip_case)"));
+    assert!(summary.contains("Dockerfile (in config_roundtrip_case)"));
+    assert!(!summary.contains("lib.rs"));
+    assert!(!summary.contains("notes.md"));
+    assert!(!summary.contains("README (in"));
+}
+
+#[test]
+fn gzip_output_appends_extension_and_round_trips() {
+    let temp_dir = TestTempDir::new("gzip_output_appends_extension_and_round_trips");
+    let plain = run_summary(&temp_dir.path.join("plain.txt"), &[]);
+
+    let output = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap()])
+        .args(["-o", temp_dir.path.join("summary.txt").to_str().unwrap()])
+        .args(["--gzip", "--gzip-level", "9"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!temp_dir.path.join("summary.txt").exists());
+
+    let compressed = fs::File::open(temp_dir.path.join("summary.txt.gz")).unwrap();
+    let mut decompressed = String::new();
+    GzDecoder::new(compressed)
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, plain);
+
+    let rejected = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap(), "--gzip", "-c"])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+    assert!(String::from_utf8_lossy(&rejected.stderr).contains("--gzip"));
+}
+
+#[test]
+fn dry_run_lists_files_without_writing_output() {
+    let temp_dir = TestTempDir::new("dry_run_lists_files_without_writing_output");
+    let output_path = temp_dir.path.join("summary.txt");
+
+    let output = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap()])
+        .args(["-o", output_path.to_str().unwrap(), "--dry-run"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert!(!output_path.exists());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("  src/main.rs (in config_roundtrip_case)\n"));
+    assert!(stdout.contains("\nTotal: "));
+    assert!(stdout.contains(" tokens\n"));
+    assert!(stdout.contains("\nExcluded: 1 ignored extension\n"));
+    assert!(!stdout.contains("fn main"));
+
+    let empty = Command::new(oreuit_bin())
+        .args(["-d", temp_dir.path.to_str().unwrap(), "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(!empty.status.success());
+    assert!(String::from_utf8(empty.stdout)
+        .unwrap()
+        .contains("Total: 0 file(s), 0 bytes, ~0 tokens\n"));
+}
+
+#[test]
+fn stats_footer_follows_file_contents() {
+    let temp_dir = TestTempDir::new("stats_footer_follows_file_contents");
+
+    let plain = run_summary(&temp_dir.path.join("plain.txt"), &[]);
+    let with_stats = run_summary(&temp_dir.path.join("stats.txt"), &["--stats"]);
+
+    assert!(!plain.contains("＜Statistics＞"));
+    let footer = with_stats.strip_prefix(plain.as_str()).unwrap();
+    assert!(footer.starts_with("\n＜Statistics＞\n\nDirectories: 1\nFiles: "));
+    assert!(footer.contains("\nTokens: ~"));
+    assert!(footer.contains("\n  .rs: 2 file(s), "));
+    assert!(footer.ends_with("\nSkipped: 0 too large, 0 binary, 0 undecodable\n"));
+
+    let stderr = |args: &[&str]| {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", fixture_dir().to_str().unwrap(), "-o", "-"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let stats_line = stderr(&[]);
+    let stats_line = stats_line.lines().find(|line| line.starts_with("Stats: "));
+    assert!(stats_line
+        .unwrap()
+        .ends_with("; skipped 0 too large, 0 binary, 0 undecodable"));
+    assert!(!stderr(&["--no-stats"]).contains("Stats: "));
+}
+
+#[test]
+fn split_size_writes_numbered_parts() {
+    let temp_dir = TestTempDir::new("split_size_writes_numbered_parts");
+    let plain = run_summary(&temp_dir.path.join("plain.txt"), &[]);
+
+    for (flag, limit, name) in [
+        ("--split-size", "200", "bytes"),
+        ("--split-tokens", "30", "tokens"),
+    ] {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", fixture_dir().to_str().unwrap()])
+            .args([
+                "-o",
+                temp_dir
+                    .path
+                    .join(format!("{}.txt", name))
+                    .to_str()
+                    .unwrap(),
+            ])
+            .args([flag, limit])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        assert!(!temp_dir.path.join(format!("{}.txt", name)).exists());
+        let mut parts = Vec::new();
+        for i in 1.. {
+            let path = temp_dir.path.join(format!("{}.{:03}.txt", name, i));
+            if !path.exists() {
+                break;
+            }
+            parts.push(fs::read_to_string(path).unwrap());
+        }
+        assert!(parts.len() > 1);
+        let total = parts.len();
+        let mut joined = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            let body = part
+                .strip_prefix(&format!("Part {} of {}\n\n", i + 1, total))
+                .unwrap();
+            assert_eq!(body.contains("＜Directory Structure＞"), i == 0);
+            joined.push_str(body);
+        }
+        assert_eq!(joined, plain);
+    }
+
+    let rejected = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap(), "--split-size", "10"])
+        .args(["-o", "-"])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+    assert!(String::from_utf8_lossy(&rejected.stderr).contains("--split-size"));
+}
+
+#[test]
+fn hidden_mode_defaults_to_exclude() {
+    let temp_dir = TestTempDir::new("hidden_mode_defaults_to_exclude");
+    let source = temp_dir.path.join("source");
+    fs::create_dir_all(source.join(".github")).unwrap();
+    fs::write(source.join(".github/ci.yml.txt"), "jobs\n").unwrap();
+    fs::write(source.join(".env.local"), "SECRET=1\n").unwrap();
+    fs::write(source.join("main.rs"), "fn main() {}\n").unwrap();
+
+    let run = |extra_args: &[&str]| {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", source.to_str().unwrap(), "-o", "-"])
+            .args(["--extensions", "rs,txt,local"])
+            .args(extra_args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    for excluded in [run(&[]), run(&["--hidden", "exclude"])] {
+        assert!(excluded.contains("main.rs"));
+        assert!(!excluded.contains(".github"));
+        assert!(!excluded.contains("SECRET"));
+    }
+    let included = run(&["--hidden"]);
+    assert!(included.contains(".github/ci.yml.txt (in source)"));
+    assert!(included.contains("SECRET=1"));
+    let whitelisted = run(&["--hidden=exclude", "--whitelist-filenames", ".github"]);
+    assert!(whitelisted.contains(".github/ci.yml.txt (in source)"));
+    assert!(!whitelisted.contains("SECRET"));
+}
+
+#[test]
+fn max_lines_per_file_keeps_both_ends() {
+    let temp_dir = TestTempDir::new("max_lines_per_file_keeps_both_ends");
+    let source = temp_dir.path.join("source");
+    fs::create_dir_all(&source).unwrap();
+    let text: String = (1..=10).map(|i| format!("行 {}\n", i)).collect();
+    fs::write(source.join("utf8.txt"), &text).unwrap();
+    // "行" in Shift_JIS, so the file does not decode as UTF-8.
+    let sjis: Vec<u8> = (1..=10)
+        .flat_map(|i| [&[0x8d, 0x73][..], format!(" {}\n", i).as_bytes()].concat())
+        .collect();
+    fs::write(source.join("sjis.txt"), sjis).unwrap();
+
+    let output = Command::new(oreuit_bin())
+        .args([
+            "-d",
+            source.to_str().unwrap(),
+            "-o",
+            "-",
+            "--no-token-counts",
+        ])
+        .args(["--max-lines-per-file", "5"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let kept = "行 1\n行 2\n行 3\n... [5 lines omitted] ...\n行 9\n行 10\n";
+    assert_eq!(stdout.matches(kept).count(), 2);
+
+    let rejected = Command::new(oreuit_bin())
+        .args(["-d", source.to_str().unwrap(), "-o", "-"])
+        .args(["--max-lines-per-file", "5", "--head-lines", "2"])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+
+    let config = temp_dir.path.join("oreuit.toml");
+    fs::write(&config, "max_lines_per_file = 0\n").unwrap();
+    for zero in [
+        &["--max-lines-per-file", "0"][..],
+        &["--config", config.to_str().unwrap()],
+    ] {
+        let rejected = Command::new(oreuit_bin())
+            .args(["-d", source.to_str().unwrap(), "-o", "-"])
+            .args(zero)
+            .output()
+            .unwrap();
+        assert!(!rejected.status.success());
+        assert!(rejected.stdout.is_empty());
+    }
+}
+
+#[test]
+fn tree_only_writes_the_directory_structure_alone() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let golden = fs::read_to_string(fixtures.join("scan_case.golden.txt")).unwrap();
+    let (structure, _) = golden.split_once("\n\n＜File Contents＞").unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", fixtures.join("scan_case").to_str().unwrap()])
+            .args(["-o", "-", "--stats"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(run(&["--tree-only"]), format!("{}\n", structure));
+    let (_, tree) = structure.split_once(" ===\n").unwrap();
+    assert_eq!(
+        run(&["--tree-only", "--bare", "--format", "xml"]),
+        format!("{}\n", tree)
+    );
+    let markdown = run(&["--tree-only", "--format", "markdown"]);
+    assert!(markdown.ends_with("            └── frame.rs\n```\n"));
+    assert!(!markdown.contains("# File Contents"));
+    assert_eq!(run(&["--no-content"]), run(&["--tree-only"]));
+}
+
+#[test]
+fn no_tree_writes_the_file_contents_alone() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures");
+    let golden = fs::read_to_string(fixtures.join("scan_case.golden.txt")).unwrap();
+    let (_, contents) = golden.split_once("\n\n＜File Contents＞").unwrap();
+    let run = |args: &[&str]| {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", fixtures.join("scan_case").to_str().unwrap()])
+            .args(["-o", "-", "--no-tree"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(run(&[]), format!("＜File Contents＞{}", contents));
+    let xml = run(&["--format", "xml"]);
+    assert!(xml.starts_with("<documents>\n<document index=\"1\">\n<source>"));
+    assert!(!xml.contains("DIRECTORY_TREE"));
+    assert!(run(&["--format", "markdown"]).starts_with("# File Contents\n\n### Makefile"));
+    assert!(run(&["--format", "jsonl"]).starts_with("{\"path\":\"Makefile\""));
+}
+
+#[test]
+fn exit_codes_tell_failures_apart() {
+    let temp_dir = TestTempDir::new("exit_codes");
+    let run = |directory: &Path, args: &[&str]| {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", directory.to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        (output.status.code(), stderr)
+    };
+    let status = |args: &[&str]| run(&fixture_dir(), args);
+    let output = temp_dir.path.join("summary.txt");
+    let output = output.to_str().unwrap();
+
+    assert_eq!(status(&["-o", output]).0, Some(0));
+    let (code, stderr) = run(&temp_dir.path.join("missing"), &["-o", output]);
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("Error: No valid directories specified or found."));
+    let (code, stderr) = status(&["-o", output, "--no-such-option"]);
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("error:"));
+    let unmatched = temp_dir.path.join("unmatched");
+    fs::create_dir_all(&unmatched).unwrap();
+    fs::write(unmatched.join("data.bin"), "not listed").unwrap();
+    assert_eq!(run(&unmatched, &["-o", output]).0, Some(2));
+    assert!(fs::read_to_string(output)
+        .unwrap()
+        .contains("＜File Contents＞"));
+    assert_eq!(
+        run(&unmatched, &["-o", output, "--format", "jsonl"]).0,
+        Some(2)
+    );
+    assert_eq!(run(&unmatched, &["-o", output, "--tree-only"]).0, Some(0));
+    let (code, stderr) = status(&["-o", temp_dir.path.to_str().unwrap()]);
+    assert_eq!(code, Some(3));
+    assert!(stderr.contains("Error: Failed to write"));
+}
+
+#[test]
+fn hashes_note_each_file_and_fill_the_manifest() {
+    let temp_dir = TestTempDir::new("hashes");
+    fs::write(temp_dir.path.join("abc.txt"), "abc").unwrap();
+    fs::write(temp_dir.path.join("blob.txt"), b"\x89PNG\0\0").unwrap();
+    fs::write(temp_dir.path.join("big.txt"), "x".repeat(64)).unwrap();
+    let manifest = temp_dir.path.join("summary.sha256");
+    let output = Command::new(oreuit_bin())
+        .args(["-d", temp_dir.path.to_str().unwrap(), "-o", "-"])
+        .args(["-e", ".txt", "--max-size", "32"])
+        .args(["--hash-manifest", manifest.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("abc.txt (in "));
+    assert!(report.contains(") [sha256:ba7816bf8f01]: ~"));
+    let manifest = fs::read_to_string(manifest).unwrap();
+    let lines: Vec<&str> = manifest.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(
+        lines[0].starts_with("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  ")
+    );
+    assert!(lines[0].ends_with("abc.txt"));
+    assert!(lines[1].ends_with("big.txt"));
+    assert!(lines[2].ends_with("blob.txt"));
+}
+
+#[test]
+fn directory_globs_expand_to_each_matching_directory_once() {
+    let temp_dir = TestTempDir::new("directory_globs");
+    for crate_name in ["alpha", "beta"] {
+        let src = temp_dir.path.join("crates").join(crate_name).join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.rs"), "pub fn f() {}\n").unwrap();
+    }
+    fs::write(temp_dir.path.join("crates/README.md"), "# crates\n").unwrap();
+    let output = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["-d", "crates/*/src,crates/alpha/src,missing/*", "-o", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(report.matches("=== Tree for src ===").count(), 2);
+    assert_eq!(report.matches("lib.rs (in src)").count(), 2);
+    assert!(!report.contains("README.md"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Warning: No directory matches glob, skipping: missing/*"));
+}
+
+#[test]
+fn since_keeps_files_modified_after_a_date_or_duration() {
+    let temp_dir = TestTempDir::new("since");
+    let old = temp_dir.path.join("old.rs");
+    fs::write(&old, "fn old() {}\n").unwrap();
+    fs::write(temp_dir.path.join("new.rs"), "fn new() {}\n").unwrap();
+    // 2001-01-01T00:00:00Z
+    let touched = Command::new("touch")
+        .args(["-d", "@978307200"])
+        .arg(&old)
+        .status()
+        .unwrap();
+    assert!(touched.success());
+    let run = |since: &str| {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", temp_dir.path.to_str().unwrap(), "--stdout"])
+            .args(["--since", since])
+            .output()
+            .unwrap();
+        (
+            output.status.code(),
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    for since in ["1w", "2001-01-01T00:00:01"] {
+        let (code, stdout, _) = run(since);
+        assert_eq!(code, Some(0));
+        assert!(stdout.contains("new.rs"));
+        assert!(!stdout.contains("old.rs"), "{}", since);
+    }
+    let (_, stdout, _) = run("2001-01-01");
+    assert!(stdout.contains("old.rs") && stdout.contains("new.rs"));
+    let (code, _, stderr) = run("7x");
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("Invalid --since value"));
+}
+
+#[cfg(unix)]
+#[test]
+fn tree_details_dates_files_in_local_time() {
+    let temp_dir = TestTempDir::new("tree_mtime");
+    let file = temp_dir.path.join("dated.rs");
+    fs::write(&file, "fn dated() {}\n").unwrap();
+    // 2001-01-01T00:00:00Z
+    let touched = Command::new("touch")
+        .args(["-d", "@978307200"])
+        .arg(&file)
+        .status()
+        .unwrap();
+    assert!(touched.success());
+    for (tz, date) in [
+        ("UTC0", "2001-01-01"),
+        ("JST-9", "2001-01-01"),
+        ("EST5", "2000-12-31"),
+    ] {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", temp_dir.path.to_str().unwrap(), "--stdout"])
+            .args(["--tree-details", "mtime", "--tree-only"])
+            .env("TZ", tz)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            stdout.contains(&format!("dated.rs ({})", date)),
+            "{}: {}",
+            tz,
+            stdout
+        );
+    }
+}
+
+#[test]
+fn append_adds_each_run_after_a_separator() {
+    let temp_dir = TestTempDir::new("append");
+    let output = temp_dir.path.join("summary.md");
+    let run = |args: &[&str]| {
+        Command::new(oreuit_bin())
+            .args(["-d", fixture_dir().to_str().unwrap()])
+            .args(["-o", output.to_str().unwrap(), "--format", "markdown"])
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    assert!(run(&["--append"]).status.success());
+    let once = fs::read_to_string(&output).unwrap();
+    assert!(!once.starts_with("\n---"));
+    assert!(run(&["--append"]).status.success());
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        format!("{}\n---\n\n{}", once, once)
+    );
+    assert!(run(&[]).status.success());
+    assert_eq!(fs::read_to_string(&output).unwrap(), once);
+
+    let split = run(&["--append", "--split-size", "1000"]);
+    assert_eq!(split.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&split.stderr).contains("--split-size"));
+}
+
+#[cfg(unix)]
+#[test]
+fn watch_rewrites_the_report_when_a_selected_file_changes() {
+    let temp_dir = TestTempDir::new("watch");
+    let lib = temp_dir.path.join("lib.rs");
+    fs::write(&lib, "fn one() {}\n").unwrap();
+    fs::create_dir_all(temp_dir.path.join("target")).unwrap();
+    let output = temp_dir.path.join("summary.rs");
+    let mut child = Command::new(oreuit_bin())
+        .args(["-d", temp_dir.path.to_str().unwrap()])
+        .args(["-o", output.to_str().unwrap(), "--watch", "-q"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let wait_for = |expected: &str| {
+        for _ in 0..100 {
+            match fs::read_to_string(&output) {
+                Ok(report) if report.contains(expected) => return report,
+                _ => std::thread::sleep(std::time::Duration::from_millis(100)),
+            }
+        }
+        panic!("report never contained {:?}", expected);
+    };
+
+    wait_for("fn one");
+    fs::write(&lib, "fn one() {}\nfn two() {}\n").unwrap();
+    let report = wait_for("fn two");
+    // The report lands in the scanned directory with an allowed extension, yet is
+    // neither read into the next report nor a reason to write one.
+    fs::write(temp_dir.path.join("target/gen.rs"), "fn ignored() {}\n").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    assert_eq!(fs::read_to_string(&output).unwrap(), report);
+    assert!(!report.contains("summary.rs"));
+
+    let pid = child.id().to_string();
+    assert!(Command::new("kill")
+        .args(["-INT", &pid])
+        .status()
+        .unwrap()
+        .success());
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn report_lists_skipped_files_with_their_reason() {
+    let temp_dir = TestTempDir::new("report");
+    let dir = temp_dir.path.join("repo");
+    fs::create_dir_all(dir.join("target")).unwrap();
+    fs::write(dir.join("target/out.rs"), "fn out() {}\n").unwrap();
+    fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.join("data.rs"), b"\0\x01\x02").unwrap();
+    fs::write(dir.join("logo.png"), b"png").unwrap();
+    let output = temp_dir.path.join("summary.txt");
+    let report = temp_dir.path.join("skipped.json");
+
+    let run = |args: &[&str]| {
+        let run = Command::new(oreuit_bin())
+            .args(["-d", dir.to_str().unwrap(), "-o", output.to_str().unwrap()])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(run.status.success());
+        fs::read_to_string(&output).unwrap()
+    };
+
+    let plain = run(&[]);
+    assert_eq!(run(&["--report", report.to_str().unwrap()]), plain);
+    let base = dir.to_str().unwrap();
+    assert_eq!(
+        fs::read_to_string(&report).unwrap(),
+        format!(
+            "{{\n  \"counts\": {{\"binary\": 1, \"ignored-dir\": 1, \"ignored-ext\": 1}},\n  \"skipped\": [\n    \
+             {{\"path\": \"{0}/data.rs\", \"reason\": \"binary\", \"description\": \"binary\", \"directory\": false}},\n    \
+             {{\"path\": \"{0}/logo.png\", \"reason\": \"ignored-ext\", \"description\": \"ignored extension\", \"directory\": false}},\n    \
+             {{\"path\": \"{0}/target\", \"reason\": \"ignored-dir\", \"description\": \"ignored directory\", \"directory\": true}}\n  ]\n}}\n",
+            base
+        )
+    );
+}
+
+#[test]
+fn print_selection_lists_files_that_files_from_reads_back() {
+    let temp_dir = TestTempDir::new("print_selection");
+    fs::create_dir_all(temp_dir.path.join("src")).unwrap();
+    fs::write(temp_dir.path.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp_dir.path.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+    fs::write(temp_dir.path.join("src/logo.png"), b"png").unwrap();
+
+    let printed = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["-d", "src", "--print-selection", "--interactive"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(printed.status.success());
+    assert_eq!(
+        String::from_utf8(printed.stdout.clone()).unwrap(),
+        "src/lib.rs\nsrc/main.rs\n"
+    );
+    assert!(String::from_utf8_lossy(&printed.stderr).contains("--interactive needs a terminal"));
+    assert!(!temp_dir.path.join("summary.txt").exists());
+
+    fs::write(temp_dir.path.join("picked.txt"), &printed.stdout).unwrap();
+    let replayed = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["--files-from", "picked.txt", "-o", "-"])
+        .output()
+        .unwrap();
+    let report = String::from_utf8(replayed.stdout).unwrap();
+    assert!(report.contains("fn main() {}") && report.contains("pub fn lib() {}"));
+
+    let empty = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["-d", "src", "-e", ".py", "--print-selection"])
+        .output()
+        .unwrap();
+    assert_eq!(empty.status.code(), Some(2));
+}
+
+#[cfg(not(feature = "clipboard"))]
+#[test]
+fn tee_keeps_the_file_when_the_clipboard_fails() {
+    let temp_dir = TestTempDir::new("tee");
+    let output = temp_dir.path.join("summary.txt");
+    let plain = run_summary(&output, &[]);
+    fs::remove_file(&output).unwrap();
+
+    let tee = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap()])
+        .args(["-o", output.to_str().unwrap(), "--tee"])
+        .output()
+        .unwrap();
+    assert_eq!(tee.status.code(), Some(3));
+    assert_eq!(fs::read_to_string(&output).unwrap(), plain);
+    assert!(String::from_utf8_lossy(&tee.stdout).contains("Output completed: "));
+    let stderr = String::from_utf8_lossy(&tee.stderr);
+    assert!(stderr.contains("Clipboard feature is not enabled"));
+    assert!(stderr.contains(&format!("still written to {}", output.display())));
+
+    let both = Command::new(oreuit_bin())
+        .args(["-d", fixture_dir().to_str().unwrap(), "--tee", "-o", "-"])
+        .output()
+        .unwrap();
+    assert_eq!(both.status.code(), Some(1));
+}
+
+#[test]
+fn pinned_files_come_first_and_are_marked() {
+    let temp_dir = TestTempDir::new("pin");
+    let src = temp_dir.path.join("src");
+    fs::create_dir_all(&src).unwrap();
+    fs::write(src.join("a.txt"), "alpha\n").unwrap();
+    fs::write(src.join("b.txt"), "bravo\n").unwrap();
+    fs::write(src.join("c.txt"), "charlie\n").unwrap();
+    fs::write(src.join("large.txt"), "x".repeat(64)).unwrap();
+
+    let output = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["-d", "src", "-e", ".txt", "--max-size", "32", "-o", "-"])
+        .args(["--pin", "./src/c.txt", "--pin", "src/large.txt"])
+        .args(["--pin", "src/missing.txt", "--pin", "src/c.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    let position = |needle: &str| {
+        assert_eq!(report.matches(needle).count(), 1, "{}", needle);
+        report.find(needle).unwrap()
+    };
+    let c = position("c.txt (in src) [PINNED]:");
+    let large = position("large.txt (in src) [PINNED]:");
+    let a = position("a.txt (in src):");
+    let b = position("b.txt (in src):");
+    assert!(c < large && large < a && a < b);
+    assert!(report[large..a].contains("[File size exceeds limit; skipped]"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--pin src/missing.txt matches no selected file"));
+}
+
+#[test]
+fn dedupe_compares_whole_files_when_they_are_truncated() {
+    let temp_dir = TestTempDir::new("dedupe_truncated");
+    let dir = temp_dir.path.join("dd");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("one.txt"), "a\nb\nc\nX").unwrap();
+    fs::write(dir.join("three.txt"), "a\nb\nc\nX").unwrap();
+    fs::write(dir.join("two.txt"), "a\nb\nc\nY").unwrap();
+    fs::write(dir.join("four.txt"), "Z\nb\nc\nX").unwrap();
+
+    let truncations: [&[&str]; 3] = [
+        &["--head-lines", "3"],
+        &["--tail-lines", "3"],
+        &["--max-lines-per-file", "2"],
+    ];
+    for truncation in truncations {
+        let output = Command::new(oreuit_bin())
+            .args(["-d", dir.to_str().unwrap(), "-e", ".txt", "-o", "-"])
+            .args(["--dedupe", "--stats"])
+            .args(truncation)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let report = String::from_utf8(output.stdout).unwrap();
+        // Only three.txt repeats one.txt; two.txt and four.txt differ in a line cut off.
+        assert_eq!(
+            report.matches("[Duplicate of ").count(),
+            1,
+            "{:?}",
+            truncation
+        );
+        assert!(report.contains("[Duplicate of one.txt (in dd)]"));
+        assert!(report.contains("1 duplicate(s) (7 bytes saved)"));
+    }
+}
@@ -147,3 +147,100 @@ fn generate_config_is_stable_across_runs() {
 
     assert_eq!(first, second);
 }
+
+#[test]
+fn project_config_is_discovered_and_cli_flags_win() {
+    let temp_dir = TestTempDir::new("project_config_is_discovered_and_cli_flags_win");
+    let project = temp_dir.path.join("project");
+    fs::create_dir_all(project.join("web")).unwrap();
+    fs::write(project.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(project.join("web/app.vue"), "<template/>\n").unwrap();
+    let config_output = temp_dir.path.join("from_project_config.md");
+    fs::write(
+        project.join(".oreuit.toml"),
+        format!(
+            "extensions_add = [\".vue\"]\nformat = \"markdown\"\noutput = {:?}\n",
+            config_output.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+
+    let status = Command::new(oreuit_bin())
+        .args(["-d", project.to_str().unwrap()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let summary = fs::read_to_string(&config_output).unwrap();
+    assert!(summary.starts_with("# Directory Structure"));
+    assert!(summary.contains("### web/app.vue (in project)"));
+    assert!(summary.contains("### main.rs (in project)"));
+
+    let output = Command::new(oreuit_bin())
+        .args([
+            "-d",
+            project.to_str().unwrap(),
+            "--format",
+            "text",
+            "-o",
+            "-",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("＜Directory Structure＞"));
+
+    // A config key that conflicts with a flag on the command line gives way to it.
+    fs::write(
+        project.join(".oreuit.toml"),
+        format!(
+            "no_stats = true\npin = [{:?}]\n",
+            project.join("web/app.vue").to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    let output = Command::new(oreuit_bin())
+        .args(["-d", project.to_str().unwrap(), "--stats", "-o", "-"])
+        .args(["-e", "+,.vue"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("＜Statistics＞"));
+    let pinned = stdout.find("web/app.vue (in project) [PINNED]").unwrap();
+    assert!(pinned < stdout.find("main.rs (in project)").unwrap());
+
+    fs::write(project.join(".oreuit.toml"), "max_size = \"big\"\n").unwrap();
+    let output = Command::new(oreuit_bin())
+        .args(["-d", project.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 1 (`max_size = "));
+}
+
+#[test]
+fn oreuit_toml_in_the_current_directory_accepts_a_plus_list() {
+    let temp_dir = TestTempDir::new("oreuit_toml_in_the_current_directory_accepts_a_plus_list");
+    let project = temp_dir.path.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(project.join("app.vue"), "<template/>\n").unwrap();
+    fs::write(
+        temp_dir.path.join("oreuit.toml"),
+        "extensions = [\"+\", \".vue\"]\nformat = \"markdown\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(oreuit_bin())
+        .current_dir(&temp_dir.path)
+        .args(["-d", "project", "-o", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let summary = String::from_utf8(output.stdout).unwrap();
+    assert!(summary.starts_with("# Directory Structure"));
+    assert!(summary.contains("### app.vue (in project)"));
+    assert!(summary.contains("### main.rs (in project)"));
+}
@@ -0,0 +1,3 @@
+pub mod proto;
+
+pub fn serve() {}
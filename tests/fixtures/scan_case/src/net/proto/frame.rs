@@ -0,0 +1,4 @@
+// Wire format.
+pub struct Frame {
+    pub len: u32,
+}
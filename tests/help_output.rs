@@ -27,11 +27,13 @@ fn long_help_describes_behavior_and_defaults() {
     assert!(stdout.contains("Selection precedence (highest first):"));
     assert!(stdout.contains("match basenames only, not relative paths"));
     assert!(stdout.contains("all non-ignored extensions and all extensionless files are eligible"));
-    assert!(stdout.contains("These options still work with `--config`"));
+    assert!(stdout.contains(
+        "Precedence, highest first: flags given on the command line, then the config file"
+    ));
     assert!(stdout.contains("[File size exceeds limit; skipped]"));
     assert!(stdout.contains("[Binary file skipped]"));
     assert!(stdout.contains("[Cannot decode file content]"));
-    assert!(stdout.contains("UTF-8 first, then falls back to Shift_JIS"));
+    assert!(stdout.contains("oreuit reads UTF-8 first; other files are read as ISO-2022-JP"));
     assert!(stdout.contains("Default allowed extensions:"));
     assert!(stdout.contains("Default ignored directories:"));
 }
@@ -0,0 +1,739 @@
+use oreuit::{
+    HiddenFiles, OutputFormat, Progress, RenderOptions, ReportOutput, SkipReason, SplitLimit,
+    Summarizer, SummaryOptions, TocPlacement, TrimStrategy, TrimSummary,
+};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+struct TestTempDir {
+    path: PathBuf,
+}
+
+impl TestTempDir {
+    fn new(name: &str) -> Self {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = env::temp_dir().join(format!(
+            "oreuit_library_{}_{}_{}",
+            name,
+            process::id(),
+            unique
+        ));
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+}
+
+impl Drop for TestTempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[test]
+fn summarizer_collects_and_renders_like_the_cli() {
+    let temp_dir = TestTempDir::new("summarizer");
+    fs::create_dir_all(temp_dir.path.join("src")).unwrap();
+    fs::create_dir_all(temp_dir.path.join("target")).unwrap();
+    fs::write(temp_dir.path.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+    fs::write(temp_dir.path.join("notes.txt"), "hello\n").unwrap();
+    fs::write(temp_dir.path.join("data.bin.txt"), b"\0\0").unwrap();
+    fs::write(temp_dir.path.join("target/out.rs"), "fn out() {}\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .allowed_extensions(["rs", "txt"]);
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    let relative: Vec<&str> = summary
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(relative, vec!["data.bin.txt", "notes.txt", "src/lib.rs"]);
+    assert_eq!(summary.files[0].skipped, Some(SkipReason::Binary));
+    assert!(!summary.trees[0].tree.contains("target"));
+
+    let text = summary.render(OutputFormat::Text, &RenderOptions::default());
+    assert!(text.starts_with("＜Directory Structure＞\n\n=== Tree for "));
+    assert!(text.contains("\nsrc/lib.rs ("));
+    assert!(text.ends_with("pub fn f() {}\n"));
+}
+
+#[test]
+fn summarizer_rejects_invalid_globs() {
+    let options = SummaryOptions::new().include_globs(["src/["]);
+
+    let err = Summarizer::new(options).err().unwrap();
+
+    assert!(err.to_string().contains("Invalid --include-glob pattern"));
+}
+
+#[test]
+fn progress_reports_scanning_then_each_read() {
+    let temp_dir = TestTempDir::new("progress");
+    fs::write(temp_dir.path.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp_dir.path.join("b.rs"), "fn b() {}\n").unwrap();
+    fs::write(temp_dir.path.join(".gitignore"), "{a\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .allowed_extensions(["rs"])
+        .respect_gitignore(true);
+    let events = Mutex::new(Vec::new());
+    let max_bytes = AtomicU64::new(0);
+    Summarizer::new(options)
+        .unwrap()
+        .run_with_progress(|event| {
+            let event = match event {
+                Progress::Scanning(_) => "scanning".to_string(),
+                Progress::Reading {
+                    done, total, bytes, ..
+                } => {
+                    max_bytes.fetch_max(bytes, Ordering::Relaxed);
+                    format!("reading {}/{}", done, total)
+                }
+                Progress::Warning(message) => format!("warning {}", message),
+                Progress::Skipped { path, .. } => format!("skipped {}", path.display()),
+            };
+            events.lock().unwrap().push(event);
+        })
+        .unwrap();
+
+    let mut events = events.into_inner().unwrap();
+    assert_eq!(events[0], "scanning");
+    assert!(events[1].starts_with("warning Failed to parse "));
+    // `.gitignore` is one of the default extensionless filenames, so it is read too.
+    events[2..].sort();
+    assert_eq!(events[2..], ["reading 1/3", "reading 2/3", "reading 3/3"]);
+    assert_eq!(max_bytes.into_inner(), 23);
+}
+
+#[test]
+fn progress_reports_why_entries_were_skipped() {
+    let temp_dir = TestTempDir::new("skipped");
+    fs::create_dir_all(temp_dir.path.join("target/debug")).unwrap();
+    fs::write(temp_dir.path.join("target/debug/out.rs"), "fn out() {}\n").unwrap();
+    fs::write(temp_dir.path.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp_dir.path.join("logo.png"), [0u8; 4]).unwrap();
+    fs::write(temp_dir.path.join("big.rs"), "x".repeat(64)).unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .ignore_dirs(["target"])
+        .max_size(32);
+    let skipped = Mutex::new(Vec::new());
+    Summarizer::new(options)
+        .unwrap()
+        .run_with_progress(|event| {
+            if let Progress::Skipped {
+                path,
+                name,
+                reason,
+                directory,
+            } = event
+            {
+                let path = path.strip_prefix(&temp_dir.path).unwrap().to_owned();
+                skipped
+                    .lock()
+                    .unwrap()
+                    .push((path, name, reason, directory));
+            }
+        })
+        .unwrap();
+
+    let mut skipped = skipped.into_inner().unwrap();
+    skipped.sort();
+    assert_eq!(
+        skipped,
+        [
+            (
+                PathBuf::from("big.rs"),
+                "too-large",
+                "larger than --max-size",
+                false
+            ),
+            (
+                PathBuf::from("logo.png"),
+                "ignored-ext",
+                "ignored extension",
+                false
+            ),
+            (
+                PathBuf::from("target"),
+                "ignored-dir",
+                "ignored directory",
+                true
+            ),
+        ]
+    );
+}
+
+#[test]
+fn summarizer_rejects_unknown_encodings() {
+    let options = SummaryOptions::new().encodings(["utf-8", "klingon"]);
+
+    let err = Summarizer::new(options).err().unwrap();
+
+    assert!(err
+        .to_string()
+        .starts_with("Unknown --encoding label 'klingon'; valid labels: utf-8, utf-16le,"));
+}
+
+#[cfg(unix)]
+#[test]
+fn broken_symlinks_get_a_placeholder() {
+    let temp_dir = TestTempDir::new("broken_symlink");
+    std::os::unix::fs::symlink("missing.rs", temp_dir.path.join("gone.rs")).unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .follow_symlinks(true);
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    assert_eq!(summary.files.len(), 1);
+    assert_eq!(summary.files[0].skipped, Some(SkipReason::BrokenSymlink));
+    assert_eq!(summary.files[0].content, "[Broken symlink]\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn files_reached_through_symlinks_are_listed_once() {
+    let temp_dir = TestTempDir::new("symlink_alias");
+    fs::create_dir_all(temp_dir.path.join("a")).unwrap();
+    fs::create_dir_all(temp_dir.path.join("shared")).unwrap();
+    fs::write(temp_dir.path.join("shared/x.txt"), "shared text\n").unwrap();
+    std::os::unix::fs::symlink("../shared", temp_dir.path.join("a/link")).unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .follow_symlinks(true);
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    let relative: Vec<&str> = summary
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(relative, ["a/link/x.txt"]);
+    let tree = &summary.trees[0].tree;
+    assert_eq!(tree.matches("x.txt").count(), 1, "{}", tree);
+    assert!(tree.ends_with("├── a\n│   └── link\n│       └── x.txt\n└── shared"));
+}
+
+#[test]
+fn grep_keeps_matching_files_in_tree_and_contents() {
+    let temp_dir = TestTempDir::new("grep");
+    fs::create_dir_all(temp_dir.path.join("src/flags")).unwrap();
+    fs::create_dir_all(temp_dir.path.join("docs")).unwrap();
+    fs::write(
+        temp_dir.path.join("src/flags/on.rs"),
+        "let f = FeatureFlag::On;\n",
+    )
+    .unwrap();
+    fs::write(temp_dir.path.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp_dir.path.join("docs/notes.md"), "see FeatureFlag\n").unwrap();
+    fs::write(temp_dir.path.join("docs/blob.md"), b"FeatureFlag\0\0").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .line_numbers(true)
+        .grep(Some(r"Feature\w+".to_string()));
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    let relative: Vec<&str> = summary
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(relative, vec!["docs/notes.md", "src/flags/on.rs"]);
+    assert_eq!(summary.files[0].content, "1 | see FeatureFlag\n");
+    let tree = &summary.trees[0].tree;
+    assert!(
+        tree.ends_with("\n├── docs\n│   └── notes.md\n└── src\n    └── flags\n        └── on.rs")
+    );
+
+    let invalid = SummaryOptions::new().grep(Some("(".to_string()));
+    let err = Summarizer::new(invalid).err().unwrap();
+    assert!(err.to_string().starts_with("Invalid --grep pattern '('"));
+}
+
+#[test]
+fn grep_exclude_wins_over_grep() {
+    let temp_dir = TestTempDir::new("grep_exclude");
+    fs::write(
+        temp_dir.path.join("a.rs"),
+        "// @generated
+FeatureFlag
+",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path.join("b.rs"),
+        "FeatureFlag
+",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path.join("c.rs"),
+        "// DO NOT SUBMIT
+",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path.join("d.rs"),
+        "fn d() {}
+",
+    )
+    .unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .grep_exclude(["@generated", "DO NOT SUBMIT"]);
+    let summarizer = Summarizer::new(options.clone()).unwrap();
+    let summary = summarizer.run().unwrap();
+    let relative: Vec<&str> = summary
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(relative, vec!["b.rs", "d.rs"]);
+    assert!(summary.trees[0].tree.ends_with("\n├── b.rs\n└── d.rs"));
+
+    let options = options.grep(Some("FeatureFlag".to_string()));
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+    assert_eq!(summary.files.len(), 1);
+    assert_eq!(summary.files[0].relative_path, "b.rs");
+
+    let invalid = SummaryOptions::new().grep_exclude(["ok", "a{2,1}"]);
+    let err = Summarizer::new(invalid).err().unwrap();
+    assert!(err
+        .to_string()
+        .starts_with("Invalid --grep-exclude pattern 'a{2,1}'"));
+}
+
+#[test]
+fn changed_since_lists_changes_and_marks_deletions() {
+    let temp_dir = TestTempDir::new("changed_since");
+    let repo = &temp_dir.path;
+    let git = |args: &[&str]| {
+        let output = process::Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {:?} failed", args);
+    };
+    fs::create_dir_all(repo.join("src")).unwrap();
+    fs::write(repo.join("src/lib.rs"), "pub fn f() {}\n").unwrap();
+    fs::write(
+        repo.join("src/old.rs"),
+        "fn old() { /* keep this text */ }\n",
+    )
+    .unwrap();
+    fs::write(repo.join("src/gone.rs"), "fn gone() {}\n").unwrap();
+    fs::write(repo.join("main.rs"), "fn main() {}\n").unwrap();
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["commit", "-q", "-m", "base"]);
+
+    fs::write(repo.join("src/lib.rs"), "pub fn f() -> u8 { 1 }\n").unwrap();
+    git(&["mv", "src/old.rs", "src/new.rs"]);
+    fs::remove_file(repo.join("src/gone.rs")).unwrap();
+    fs::write(repo.join("added.rs"), "fn added() {}\n").unwrap();
+    git(&["add", "added.rs"]);
+    fs::write(repo.join("scratch.rs"), "fn scratch() {}\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([repo])
+        .changed_since(Some("HEAD".to_string()));
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+    let relative: Vec<&str> = summary
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(relative, vec!["added.rs", "src/lib.rs", "src/new.rs"]);
+    assert!(summary.trees[0].tree.ends_with(
+        "\n├── added.rs\n└── src\n    ├── gone.rs [deleted]\n    ├── lib.rs\n    └── new.rs"
+    ));
+
+    let outside = TestTempDir::new("changed_since_outside");
+    let options = SummaryOptions::new()
+        .directories([&outside.path])
+        .changed_since(Some("HEAD".to_string()));
+    let err = Summarizer::new(options).err().unwrap();
+    assert!(err.to_string().ends_with("is not in a git repository"));
+
+    let options = SummaryOptions::new()
+        .directories([repo])
+        .changed_since(Some("no-such-ref".to_string()));
+    let err = Summarizer::new(options).err().unwrap();
+    assert!(err.to_string().contains("cannot diff"));
+}
+
+#[test]
+fn directory_name_replaces_the_root_label() {
+    let temp_dir = TestTempDir::new("directory_name");
+    fs::write(temp_dir.path.join("lib.rs"), "pub fn f() {}\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .directory_name(&temp_dir.path, "owner/repo");
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    assert_eq!(summary.trees[0].dir_name, "owner/repo");
+    assert!(summary.trees[0].tree.starts_with("owner/repo\n"));
+    assert_eq!(summary.files[0].dir_name, "owner/repo");
+    let text = summary.render(OutputFormat::Text, &RenderOptions::default());
+    assert!(text.contains("=== Tree for owner/repo ==="));
+    assert!(text.contains("lib.rs (in owner/repo)"));
+}
+
+#[test]
+fn relative_to_makes_header_paths_comparable() {
+    let temp_dir = TestTempDir::new("relative_to");
+    let outside = TestTempDir::new("relative_to_outside");
+    for name in ["crate-a", "crate-b"] {
+        fs::create_dir_all(temp_dir.path.join(name).join("src")).unwrap();
+        fs::write(
+            temp_dir.path.join(name).join("src/lib.rs"),
+            "pub fn f() {}\n",
+        )
+        .unwrap();
+    }
+    fs::write(outside.path.join("main.rs"), "fn main() {}\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([
+            temp_dir.path.join("crate-a"),
+            temp_dir.path.join("crate-b"),
+            outside.path.clone(),
+        ])
+        .relative_to(Some(temp_dir.path.clone()));
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    let labels: Vec<(&str, &str)> = summary
+        .files
+        .iter()
+        .map(|f| (f.relative_path.as_str(), f.dir_name.as_str()))
+        .collect();
+    assert_eq!(
+        labels[..2],
+        [
+            ("crate-a/src/lib.rs", "crate-a"),
+            ("crate-b/src/lib.rs", "crate-b")
+        ]
+    );
+    let absolute = fs::canonicalize(&outside.path).unwrap().join("main.rs");
+    assert_eq!(labels[2].0, absolute.to_string_lossy());
+
+    let missing = SummaryOptions::new().relative_to(Some(temp_dir.path.join("missing")));
+    let err = Summarizer::new(missing).err().unwrap();
+    assert!(err.to_string().starts_with("--relative-to: cannot resolve"));
+}
+
+#[test]
+fn max_size_for_overrides_the_limit_by_extension() {
+    let temp_dir = TestTempDir::new("max_size_for");
+    fs::write(temp_dir.path.join("main.rs"), "x".repeat(64)).unwrap();
+    fs::write(temp_dir.path.join("package-lock.json"), "x".repeat(64)).unwrap();
+    fs::write(temp_dir.path.join("small.json"), "{}").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .allowed_extensions(["rs", "json"])
+        .max_size(1024)
+        .max_size_for([("JSON", 16)]);
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    let skipped: Vec<(&str, Option<SkipReason>)> = summary
+        .files
+        .iter()
+        .map(|f| (f.relative_path.as_str(), f.skipped))
+        .collect();
+    assert_eq!(
+        skipped,
+        [
+            ("main.rs", None),
+            ("package-lock.json", Some(SkipReason::TooLarge)),
+            ("small.json", None),
+        ]
+    );
+}
+
+#[test]
+fn largest_trim_strategy_cuts_the_biggest_files_first() {
+    let temp_dir = TestTempDir::new("trim_largest");
+    fs::write(temp_dir.path.join("a.rs"), "fn a() {}\n").unwrap();
+    fs::write(temp_dir.path.join("b.rs"), "word ".repeat(200)).unwrap();
+    fs::write(temp_dir.path.join("c.rs"), "word ".repeat(100)).unwrap();
+    fs::write(temp_dir.path.join("d.rs"), "fn d() {}\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .max_tokens(Some(120))
+        .trim_strategy(TrimStrategy::Largest);
+    let summary = Summarizer::new(options).unwrap().run().unwrap();
+
+    let skipped: Vec<(&str, Option<SkipReason>)> = summary
+        .files
+        .iter()
+        .map(|f| (f.relative_path.as_str(), f.skipped))
+        .collect();
+    assert_eq!(
+        skipped,
+        [
+            ("a.rs", None),
+            ("b.rs", Some(SkipReason::TokenBudget)),
+            ("c.rs", Some(SkipReason::TokenBudget)),
+            ("d.rs", None),
+        ]
+    );
+    assert_eq!(
+        summary.trimmed,
+        TrimSummary {
+            dropped_files: 2,
+            dropped_tokens: 300,
+        }
+    );
+    assert_eq!(summary.files[3].content, "fn d() {}\n");
+}
+
+#[test]
+fn deselected_files_leave_the_tree_and_the_contents() {
+    let temp_dir = TestTempDir::new("deselect");
+    fs::create_dir_all(temp_dir.path.join("src")).unwrap();
+    fs::write(temp_dir.path.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(temp_dir.path.join("src/scratch.rs"), "// wip\n").unwrap();
+
+    let options = SummaryOptions::new().directories([&temp_dir.path]);
+    let planned = Summarizer::new(options.clone()).unwrap().dry_run();
+    let scratch = planned
+        .files
+        .iter()
+        .find(|f| f.relative_path == "src/scratch.rs")
+        .unwrap();
+    let summary = Summarizer::new(options.deselect([scratch.path.clone()]))
+        .unwrap()
+        .run()
+        .unwrap();
+
+    let relative: Vec<&str> = summary
+        .files
+        .iter()
+        .map(|f| f.relative_path.as_str())
+        .collect();
+    assert_eq!(relative, vec!["src/main.rs"]);
+    assert!(summary.trees[0]
+        .tree
+        .ends_with("\n└── src\n    └── main.rs"));
+}
+
+#[test]
+fn large_files_are_streamed_to_the_same_output() {
+    let temp_dir = TestTempDir::new("stream");
+    let line = "let s = \"<a & b>\"; // ```fence``` é 日本語 \t\u{1}\n";
+    fs::write(temp_dir.path.join("big.rs"), line.repeat(5000).trim_end()).unwrap();
+    let sjis_line: &[u8] = b"\x93\xfa\x96\x7b\x8c\xea\x82\xcc\x83\x65\x83\x4c\x83\x58\x83\x67\n";
+    fs::write(temp_dir.path.join("sjis.txt"), sjis_line.repeat(20000)).unwrap();
+    fs::write(temp_dir.path.join("small.md"), "# notes\n").unwrap();
+
+    let options = SummaryOptions::new().directories([&temp_dir.path]);
+    let whole = Summarizer::new(options.clone().stream_threshold(None))
+        .unwrap()
+        .run()
+        .unwrap();
+    let streamed = Summarizer::new(options.stream_threshold(Some(1024)))
+        .unwrap()
+        .run()
+        .unwrap();
+    let streamed_files: Vec<bool> = streamed
+        .files
+        .iter()
+        .map(|f| f.streamed.is_some())
+        .collect();
+    assert_eq!(streamed_files, vec![true, true, false]);
+    assert_eq!(streamed.files[1].encoding, Some("Shift_JIS"));
+    assert!(streamed.files[0].content.is_empty());
+    let tokens = |summary: &oreuit::Summary| -> Vec<usize> {
+        summary.files.iter().map(|f| f.tokens).collect()
+    };
+    assert_eq!(tokens(&streamed), tokens(&whole));
+
+    let render_options = RenderOptions {
+        stats: true,
+        toc: Some(TocPlacement::Before),
+        ..RenderOptions::default()
+    };
+    for format in [
+        OutputFormat::Text,
+        OutputFormat::Markdown,
+        OutputFormat::Xml,
+        OutputFormat::Jsonl,
+        OutputFormat::Html,
+    ] {
+        let expected = whole.render(format, &render_options);
+        assert!(expected.contains("日本語のテキスト"));
+        let mut written = Vec::new();
+        streamed
+            .write(format, &render_options, &mut written)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            expected,
+            "{:?}",
+            format
+        );
+        assert_eq!(streamed.render(format, &render_options), expected);
+    }
+}
+
+#[test]
+fn reports_are_written_to_files_and_numbered_parts() {
+    let temp_dir = TestTempDir::new("report_output");
+    let src = temp_dir.path.join("src");
+    fs::create_dir_all(&src).unwrap();
+    for name in ["a.rs", "b.rs", "c.rs"] {
+        fs::write(src.join(name), "fn f() {}\n".repeat(100)).unwrap();
+    }
+    let summarizer = Summarizer::new(SummaryOptions::new().directories([&src])).unwrap();
+    let summary = summarizer.run().unwrap();
+    let render_options = RenderOptions::default();
+
+    let output_path = temp_dir.path.join("summary.txt");
+    let output = ReportOutput::file(output_path.to_string_lossy());
+    let written = summary
+        .write_to(OutputFormat::Text, &render_options, &output)
+        .unwrap();
+    let expected = summary.render(OutputFormat::Text, &render_options);
+    assert_eq!(fs::read_to_string(&output_path).unwrap(), expected);
+    assert_eq!(written.paths, [output_path.to_string_lossy()]);
+    assert_eq!(written.tokens, oreuit::estimate_tokens(&expected));
+
+    let limit = SplitLimit::Bytes(expected.len() as u64 / 2);
+    let output = output.split(Some(limit));
+    assert!(output.is_split());
+    let written = summary
+        .write_to(OutputFormat::Text, &render_options, &output)
+        .unwrap();
+    let parts = summary.render_parts(OutputFormat::Text, &render_options, limit);
+    assert!(parts.len() > 1);
+    assert_eq!(written.paths.len(), parts.len());
+    for (i, part) in parts.iter().enumerate() {
+        assert_eq!(written.paths[i], output.part_path(i + 1).unwrap());
+        assert_eq!(&fs::read_to_string(&written.paths[i]).unwrap(), part);
+    }
+
+    let jsonl_path = temp_dir.path.join("summary.jsonl");
+    let (written, files) = summarizer
+        .write_jsonl_to(&ReportOutput::file(jsonl_path.to_string_lossy()), |_| {})
+        .unwrap();
+    assert_eq!(files, 3);
+    assert_eq!(written.paths, [jsonl_path.to_string_lossy()]);
+
+    let mut listing = Vec::new();
+    summarizer.dry_run().write_listing(&mut listing).unwrap();
+    let listing = String::from_utf8(listing).unwrap();
+    assert!(listing.starts_with("          SIZE           TOTAL  PATH\n"));
+    assert!(listing.contains("Total: 3 file(s), 3,000 bytes, "));
+    assert!(listing.ends_with("Excluded: none\nPlaceholders: none\n"));
+}
+
+/// Paths of the files drawn in `tree`, relative to `root`; entries that are directories on
+/// disk are left out.
+fn files_in_tree(tree: &str, root: &Path) -> Vec<String> {
+    let mut parents: Vec<String> = Vec::new();
+    let mut files = Vec::new();
+    for line in tree.lines().skip(1) {
+        let name_at = line.find("── ").unwrap() + "── ".len();
+        let depth = line[..name_at].chars().count() / 4 - 1;
+        parents.truncate(depth);
+        let path = parents
+            .iter()
+            .map(String::as_str)
+            .chain([&line[name_at..]])
+            .collect::<Vec<_>>()
+            .join("/");
+        if root.join(&path).is_file() {
+            files.push(path);
+        } else {
+            parents.push(line[name_at..].to_string());
+        }
+    }
+    files
+}
+
+#[test]
+fn tree_and_contents_list_the_same_files() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/scan_case");
+    let base = || SummaryOptions::new().directories([&fixture]);
+    for options in [
+        base(),
+        base().hidden(HiddenFiles::Include),
+        base().allowed_extensions([".rs"]),
+        base().ignore_files(["Makefile"]).exclude_globs(["docs/**"]),
+        base().whitelist_filenames(["logo.png"]),
+        base().include_globs(["src/**/*.rs"]),
+        base().exclude_regex(["proto"]),
+    ] {
+        let summary = Summarizer::new(options).unwrap().run().unwrap();
+        let in_tree = files_in_tree(&summary.trees[0].tree, &fixture);
+        let in_contents: Vec<&str> = summary
+            .files
+            .iter()
+            .map(|f| f.relative_path.as_str())
+            .collect();
+        assert!(!in_contents.is_empty());
+        assert_eq!(in_tree, in_contents);
+    }
+}
+
+#[test]
+fn embedded_images_become_data_uris_up_to_the_cap() {
+    let temp_dir = TestTempDir::new("embed_images");
+    fs::write(temp_dir.path.join("logo.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+    // Magic bytes win over the extension.
+    fs::write(temp_dir.path.join("photo.jpg"), b"GIF89a").unwrap();
+    fs::write(temp_dir.path.join("large.gif"), vec![0u8; 2048]).unwrap();
+    fs::write(temp_dir.path.join("main.rs"), "fn main() {}\n").unwrap();
+
+    let options = SummaryOptions::new()
+        .directories([&temp_dir.path])
+        .line_numbers(true);
+    let summary = Summarizer::new(options.clone()).unwrap().run().unwrap();
+    assert_eq!(summary.files.len(), 1);
+
+    let summary = Summarizer::new(options.embed_images(Some(1024)))
+        .unwrap()
+        .run()
+        .unwrap();
+    let contents: Vec<(&str, &str, Option<SkipReason>)> = summary
+        .files
+        .iter()
+        .map(|f| (f.relative_path.as_str(), f.content.as_str(), f.skipped))
+        .collect();
+    assert_eq!(
+        contents,
+        [
+            (
+                "large.gif",
+                "[image too large: 2.0 KB]\n",
+                Some(SkipReason::TooLarge)
+            ),
+            ("logo.png", "data:image/png;base64,iVBORw0KGgo=\n", None),
+            ("main.rs", "1 | fn main() {}\n", None),
+            ("photo.jpg", "data:image/gif;base64,R0lGODlh\n", None),
+        ]
+    );
+    assert!(summary.trees[0].tree.contains("logo.png"));
+}